@@ -0,0 +1,46 @@
+//! the hot paths: total energy, a Metropolis sweep and reading XYZ frames. Compare two revisions
+//! with criterion baselines: cargo bench -- --save-baseline before, then on the other revision
+//! cargo bench -- --baseline before
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use fuleren::anneal::Schedule;
+use fuleren::Fuleren;
+
+fn energy(c: &mut Criterion) {
+    for name in ["C60", "C70"] {
+        let mut f = Fuleren::reference(name).unwrap();
+        c.bench_function(&format!("energy_calc {}", name), |b| b.iter(|| black_box(f.energy_calc())));
+    }
+}
+
+fn sweep(c: &mut Criterion) {
+    let schedule = Schedule::default();
+    for (name, beta) in [("C60 cold", 100.), ("C60 hot", 1.)] {
+        let mut f = Fuleren::reference("C60").unwrap();
+        f.energy_calc();
+        c.bench_function(&format!("sweep {}", name), |b| b.iter(|| black_box(f.sweep(&schedule, beta))));
+    }
+}
+
+// 100 frames of 60 atoms, the atom positions along a helix
+fn xyz_frames() -> String {
+    let mut content = String::new();
+    for frame in 0..100 {
+        content += &format!("60\nframe {}\n", frame);
+        for i in 0..60 {
+            let t = 0.1*(i + frame) as f64;
+            content += &format!("C {:.8} {:.8} {:.8}\n", 3.5*t.cos(), 3.5*t.sin(), 0.1*t - 3.);
+        }
+    }
+    content
+}
+
+fn read_xyz(c: &mut Criterion) {
+    let content = xyz_frames();
+    c.bench_function("frames_from_str 100x60", |b| {
+        b.iter(|| black_box(Fuleren::frames_from_str(black_box(&content), "bench.xyz").unwrap()))
+    });
+}
+
+criterion_group!(benches, energy, sweep, read_xyz);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+fn main() {
+    // C header for the capi feature; only src/capi.rs is parsed, the exported items all live there
+    #[cfg(feature = "capi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("invalid cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(format!("{}/src/capi.rs", crate_dir))
+            .generate()
+            .expect("unable to generate the C header")
+            .write_to_file(format!("{}/include/fuleren.h", crate_dir));
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+    }
+}
@@ -0,0 +1,82 @@
+#ifndef FULEREN_H
+#define FULEREN_H
+
+/* generated by cbindgen from src/capi.rs, do not edit */
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct Fuleren Fuleren;
+
+/**
+ * n atoms at random on a sphere of radius r_start
+ */
+Fuleren *fuleren_new(size_t n, double r_start);
+
+/**
+ * first structure of an "x y z" file, NULL if it cannot be read
+ *
+ * # Safety
+ * path must be a valid NUL terminated string
+ */
+Fuleren *fuleren_from_file(const char *path);
+
+/**
+ * # Safety
+ * f must come from fuleren_new/fuleren_from_file (or be NULL) and is invalid afterwards
+ */
+void fuleren_free(Fuleren *f);
+
+/**
+ * # Safety
+ * f must be a valid handle
+ */
+size_t fuleren_size(const Fuleren *f);
+
+/**
+ * total energy [eV]
+ *
+ * # Safety
+ * f must be a valid handle
+ */
+double fuleren_energy(Fuleren *f);
+
+/**
+ * power law annealing from beta_min to beta_max in it_max iterations;
+ * returns the number of iterations done
+ *
+ * # Safety
+ * f must be a valid handle
+ */
+size_t fuleren_anneal(Fuleren *f, double beta_min, double beta_max, double p, size_t it_max);
+
+/**
+ * FIRE relaxation to the nearest minimum, returns the number of steps
+ *
+ * # Safety
+ * f must be a valid handle
+ */
+size_t fuleren_relax(Fuleren *f, double f_tol, size_t max_steps);
+
+/**
+ * copies the cartesian positions into xyz as x0 y0 z0 x1 ... (3*size doubles)
+ *
+ * # Safety
+ * f must be a valid handle, xyz must have room for 3*size doubles
+ */
+void fuleren_positions(const Fuleren *f, double *xyz);
+
+/**
+ * sets the positions from xyz laid out as in fuleren_positions
+ *
+ * # Safety
+ * f must be a valid handle, xyz must hold 3*size doubles
+ */
+void fuleren_set_positions(Fuleren *f, const double *xyz);
+
+/**
+ * seeds the random generator of the calling thread
+ */
+void fuleren_seed(uint64_t seed);
+
+#endif  /* FULEREN_H */
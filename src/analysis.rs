@@ -0,0 +1,258 @@
+use std::f64::consts::PI;
+use std::io::Write;
+
+use crate::cna::Environment;
+use crate::extxyz::read_extxyz;
+use crate::potential::Potential;
+use crate::radial::RadialProfile;
+use crate::ring_strain::ring_strain;
+use crate::selection::Selection;
+use crate::stress::EV_A3_GPA;
+use crate::trajectory::Trajectory;
+use crate::utilities::{get_file_buffer, save_xy};
+use crate::{parse_xyz, pcf_bins, Element, Fuleren, Point6, StepSizes, VectorFloat, R1, R_CORE};
+
+impl Fuleren {
+    /// reads a trajectory: frames of "x y z" lines separated by blank lines ("#" lines, such as
+    /// the metadata header, skipped), or (extended) XYZ frames, recognized by the atom count line;
+    /// a single structure is a trajectory of one frame. Atoms keep the order of the file.
+    /// With the chemfiles feature other extensions (.dcd, .pdb, ...) are read by chemfiles
+    pub fn frames_from_file(path: &str) -> Result<Vec<Fuleren>, String> {
+        #[cfg(feature = "chemfiles")]
+        if crate::chemfiles_io::uses_chemfiles(path) {
+            return crate::chemfiles_io::read_frames(path);
+        }
+
+        let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Fuleren::frames_from_str(&content, path)
+    }
+
+    /// frames_from_file on the content of a file; path only labels the errors
+    pub fn frames_from_str(content: &str, path: &str) -> Result<Vec<Fuleren>, String> {
+        let first = content.lines().find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#')).unwrap_or("");
+        if first.trim().parse::<usize>().is_ok() {
+            return read_extxyz(content, path);
+        }
+
+        let mut frames = Vec::new();
+        let mut positions = Vec::new();
+        for (n, line) in content.lines().chain(std::iter::once("")).enumerate() {
+            if line.trim().is_empty() {
+                if !positions.is_empty() {
+                    let positions = std::mem::take(&mut positions).into_iter().collect::<crate::Point6Array>();
+                    frames.push(Fuleren { size: positions.len(), species: vec![Element::C; positions.len()], E: 0.,
+                                          r_core: R_CORE, steps: StepSizes::default(), potential: Potential::default(),
+                                          positions });
+                }
+                continue
+            }
+            if line.trim_start().starts_with('#') { continue }
+            let mut cols = line.split_ascii_whitespace();
+            let xyz = parse_xyz(&mut cols).filter(|_| cols.next().is_none())
+                                          .ok_or_else(|| format!("{}:{}: expected \"x y z\"", path, n + 1))?;
+            positions.push(Point6::from_cartesian(&xyz));
+        }
+        Ok(frames)
+    }
+
+    /// bond angle distribution: angles j-i-k between bonds (pairs inside R1) on [0, PI],
+    /// normalized to unit area
+    pub fn adf(&self, n_bins: usize) -> VectorFloat {
+        let mut adf = VectorFloat::zeros(n_bins);
+        let dtheta = PI/n_bins as f64;
+        let bonds = self.bond_graph(R1);
+        let mut count = 0;
+
+        for (i, neighbours) in bonds.iter().enumerate() {
+            for (n, &j) in neighbours.iter().enumerate() {
+                for &k in &neighbours[n+1..] {
+                    let (rij, rik) = (self._vec_ij::<f64>(i, j), self._vec_ij::<f64>(i, k));
+                    let cos = (rij[0]*rik[0] + rij[1]*rik[1] + rij[2]*rik[2])/(self._r_ij(i, j)*self._r_ij(i, k));
+                    let m = ((cos.clamp(-1., 1.).acos()/dtheta) as usize).min(n_bins - 1);
+                    adf[m] += 1.;
+                    count += 1;
+                }
+            }
+        }
+        if count > 0 {
+            adf /= count as f64*dtheta;
+        }
+        adf
+    }
+
+    /// rings up to max_size atoms as sorted atom lists; every ring is the smallest one through
+    /// some bond angle, which for fullerene cages are the faces
+    pub fn rings(&self, max_size: usize) -> Vec<Vec<usize>> {
+        let bonds = self.bond_graph(R1);
+        let mut rings: Vec<Vec<usize>> = Vec::new();
+
+        for i in 0..self.size {
+            for (n, &j) in bonds[i].iter().enumerate() {
+                for &k in &bonds[i][n+1..] {
+                    if let Some(mut ring) = _shortest_path(&bonds, j, k, i, max_size - 1) {
+                        ring.push(i);
+                        ring.sort_unstable();
+                        rings.push(ring);
+                    }
+                }
+            }
+        }
+        rings.sort();
+        rings.dedup();
+        rings
+    }
+
+    /// number of rings of every size up to max_size (index = ring size), see rings
+    pub fn ring_statistics(&self, max_size: usize) -> Vec<usize> {
+        let mut stats = vec![0; max_size + 1];
+        for ring in self.rings(max_size) {
+            stats[ring.len()] += 1;
+        }
+        stats
+    }
+}
+
+// BFS path from a to b not going through `avoid`, with at most max_len vertices
+pub(crate) fn _shortest_path(bonds: &[Vec<usize>], a: usize, b: usize, avoid: usize, max_len: usize) -> Option<Vec<usize>> {
+    let mut prev = vec![usize::MAX; bonds.len()];
+    let mut depth = vec![0; bonds.len()];
+    let mut queue = std::collections::VecDeque::from([a]);
+    prev[a] = a;
+    depth[a] = 1;
+
+    while let Some(v) = queue.pop_front() {
+        if v == b {
+            let mut path = vec![b];
+            while *path.last().unwrap() != a {
+                path.push(prev[*path.last().unwrap()]);
+            }
+            return Some(path)
+        }
+        if depth[v] == max_len { continue }
+        for &w in &bonds[v] {
+            if w != avoid && prev[w] == usize::MAX {
+                prev[w] = v;
+                depth[w] = depth[v] + 1;
+                queue.push_back(w);
+            }
+        }
+    }
+    None
+}
+
+// radial profile of analyze up to 2 mean radii of the first frame
+const RADIAL_RANGE: f64 = 2.;
+const RADIAL_BINS: usize = 200;
+
+/// highest degree of the shape spectrum of analyze
+pub const SHAPE_L_MAX: usize = 8;
+
+/// analysis of a structure or trajectory file without annealing: per frame energy, coordination,
+/// ring counts, convex hull volume and sphericity, global Steinhardt Q4, Q6, W6, CNA environment
+/// counts and the fullerene isomer of closed cages on stdout (energies with the given potential);
+/// frame averaged PCF and ADF, the radial density profile accumulated over the frames (radial.dat), the per-atom Q4, Q6, W6 and the shape spectrum (relative power of the
+/// spherical harmonic components of r(theta, phi), l up to SHAPE_L_MAX) of every frame saved to out_dir,
+/// with the virial stress tensor, pressure and shell tension of every frame (stress.dat) and the
+/// radial virial of every atom (atom_virial.dat), and the mean pentagon and hexagon energies with the
+/// pentagon strain of every frame (ring_strain.dat). With a selection the per-atom files
+/// (bond_order.dat, atom_virial.dat) only list the selected atoms of every frame.
+/// Frames are read one at a time from the memory mapped file (see Trajectory)
+pub fn analyze(path: &str, out_dir: &str, potential: &Potential, selection: Option<&Selection>) -> Result<(), String> {
+    let frames = Trajectory::open(path)?;
+    if frames.is_empty() {
+        return Err(format!("{}: no structures found", path));
+    }
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("cannot create {}: {}", out_dir, e))?;
+
+    let mut pcf = VectorFloat::zeros(100);
+    let mut adf = VectorFloat::zeros(180);
+    let mut radial: Option<RadialProfile> = None;
+
+    let mut shape = get_file_buffer(&format!("{}/shape.dat", out_dir)).map_err(|e| e.to_string())?;
+    writeln!(shape, "# {:<6} {:<4} {:<12}", "frame", "l", "P_l/P_0").map_err(|e| e.to_string())?;
+    let mut order = get_file_buffer(&format!("{}/bond_order.dat", out_dir)).map_err(|e| e.to_string())?;
+    writeln!(order, "# {:<6} {:<6} {:<10} {:<10} {:<10}", "frame", "atom", "q4", "q6", "w6").map_err(|e| e.to_string())?;
+    let mut stress = get_file_buffer(&format!("{}/stress.dat", out_dir)).map_err(|e| e.to_string())?;
+    writeln!(stress, "# {:<6} {:<12} {:<14} {:<12} {:<12} {:<12} {:<12} {:<12} {:<12}", "frame", "P[GPa]",
+             "gamma[eV/A^2]", "s_xx[GPa]", "s_yy[GPa]", "s_zz[GPa]", "s_xy[GPa]", "s_xz[GPa]", "s_yz[GPa]")
+        .map_err(|e| e.to_string())?;
+    let mut strain = get_file_buffer(&format!("{}/ring_strain.dat", out_dir)).map_err(|e| e.to_string())?;
+    writeln!(strain, "# {:<6} {:<6} {:<6} {:<12} {:<12} {:<12}", "frame", "n5", "n6", "E5/atom", "E6/atom", "strain5[eV]")
+        .map_err(|e| e.to_string())?;
+    let mut atom_virial = get_file_buffer(&format!("{}/atom_virial.dat", out_dir)).map_err(|e| e.to_string())?;
+    writeln!(atom_virial, "# {:<6} {:<6} {:<10} {:<12}", "frame", "atom", "r[A]", "virial[eV]").map_err(|e| e.to_string())?;
+
+    println!("{:<6} {:<6} {:<12} {:<10} {:<16} {:<16} {:<10} {:<8} {:<8} {:<8} {:<8} {:<10} CNA isomer", "frame", "N", "E",
+             "r_mean", "coord(1,2,3,4+)", "rings(3..8)", "V[A^3]", "psi", "Q4", "Q6", "W6", "shape_l");
+    for (n, f) in frames.frames().enumerate() {
+        let mut f = f?;
+        f.set_potential(potential.clone())?;
+        f.energy_calc();
+        pcf += &f.pcf();
+        adf += &f.adf(180);
+        // the range is fixed by the first frame
+        radial.get_or_insert_with(|| RadialProfile::new(RADIAL_RANGE*f.mean_r(), RADIAL_BINS)).add(&f);
+
+        let mut coordination = [0; 4];
+        for c in f.coordination() {
+            if c > 0 { coordination[c.min(4) - 1] += 1; }
+        }
+        let rings = f.ring_statistics(8);
+        let (q4, q6) = (f.bond_order(4), f.bond_order(6));
+        let cna = f.cna();
+        let environments = Environment::ALL.iter()
+                                           .map(|e| (e.label(), cna.iter().filter(|&c| c == e).count()))
+                                           .filter(|&(_, count)| count > 0)
+                                           .map(|(label, count)| format!("{}:{}", label, count))
+                                           .collect::<Vec<_>>();
+        let hull = f.hull();
+        let spectrum = f.shape_spectrum(SHAPE_L_MAX);
+        for (l, p) in spectrum.iter().enumerate() {
+            writeln!(shape, "  {:<6} {:<4} {:<12.6e}", n, l, p).map_err(|e| e.to_string())?;
+        }
+        // strongest non-spherical components
+        let mut dominant: Vec<usize> = (1..spectrum.len()).collect();
+        dominant.sort_by(|&a, &b| spectrum[b].total_cmp(&spectrum[a]));
+        dominant.truncate(3);
+        println!("{:<6} {:<6} {:<12.5} {:<10.5} {:<16} {:<16} {:<10.3} {:<8.4} {:<8.4} {:<8.4} {:<8.4} {:<10} {} {}", n, f.size,
+                 f.E, f.mean_r(), format!("{:?}", coordination), format!("{:?}", &rings[3..]), hull.volume,
+                 hull.sphericity(), q4.q, q6.q, q6.w, format!("{:?}", dominant), environments.join(","),
+                 f.isomer().map_or("-".to_string(), |i| i.to_string()));
+        let r = ring_strain(&f.ring_energies());
+        writeln!(strain, "  {:<6} {:<6} {:<6} {:<12.5} {:<12.5} {:<12.5}", n, r.pentagons, r.hexagons, r.e_pentagon,
+                 r.e_hexagon, r.pentagon_strain).map_err(|e| e.to_string())?;
+        let s = f.stress();
+        let t = s.tensor.map(|row| row.map(|x| x*EV_A3_GPA));
+        writeln!(stress, "  {:<6} {:<12.5} {:<14.6} {:<12.5} {:<12.5} {:<12.5} {:<12.5} {:<12.5} {:<12.5}", n,
+                 s.pressure*EV_A3_GPA, s.tension, t[0][0], t[1][1], t[2][2], t[0][1], t[0][2], t[1][2])
+            .map_err(|e| e.to_string())?;
+        let selected = selection.map_or_else(|| vec![true; f.size], |s| f.select(s));
+        let c = f.center_of_mass();
+        for (i, p) in f.positions.iter().enumerate().filter(|&(i, _)| selected[i]) {
+            let r = ((p.x() - c[0]).powi(2) + (p.y() - c[1]).powi(2) + (p.z() - c[2]).powi(2)).sqrt();
+            writeln!(atom_virial, "  {:<6} {:<6} {:<10.5} {:<12.5e}", n, i, r, s.atom_virial[i]).map_err(|e| e.to_string())?;
+        }
+        for i in (0..f.size).filter(|&i| selected[i]) {
+            writeln!(order, "  {:<6} {:<6} {:<10.5} {:<10.5} {:<10.5}", n, i, q4.q_atom[i], q6.q_atom[i], q6.w_atom[i])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    order.flush().map_err(|e| e.to_string())?;
+    stress.flush().map_err(|e| e.to_string())?;
+    strain.flush().map_err(|e| e.to_string())?;
+    atom_virial.flush().map_err(|e| e.to_string())?;
+    shape.flush().map_err(|e| e.to_string())?;
+    pcf /= frames.len() as f64;
+    adf /= frames.len() as f64;
+
+    if let Some(radial) = &radial {
+        radial.save(&format!("{}/radial.dat", out_dir)).map_err(|e| e.to_string())?;
+        if radial.detached > 0 {
+            tracing::warn!(detached = radial.detached, samples = radial.samples, "atoms off the shell");
+        }
+    }
+    // frames of different size share the pcf bins in units of their mean radius
+    save_xy(&pcf_bins(), &pcf, ["r/r_mean", "g(r)"], &format!("{}/pcf.dat", out_dir)).map_err(|e| e.to_string())?;
+    let theta = VectorFloat::from_iter((0..adf.len()).map(|m| (m as f64 + 0.5)*PI/adf.len() as f64));
+    save_xy(&theta, &adf, ["theta[rad]", "P(theta)"], &format!("{}/adf.dat", out_dir)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,729 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::bond_cache::BondCache;
+use crate::calibration::Calibration;
+use crate::cv::CollectiveVariable;
+use crate::movie::FrameWriter;
+use crate::observables::ObservableWriter;
+use crate::potential::POTENTIAL_KEYS;
+use crate::selection::Selection;
+use crate::shell_release::ShellRelease;
+use crate::snapshots::Snapshots;
+use crate::units::{Beta, Temperature};
+use crate::watchdog::{Perturbation, Watchdog};
+use crate::{Fuleren, get_beta};
+
+/// power law cooling schedule, beta(it) = b_min + (it/it_max)^p * (b_max - b_min),
+/// or a tabulated one (see from_file);
+/// optionally preceded by `equilibration` iterations at b_min with step size tuning.
+/// Under-coordinated atoms (less than 3 bonds) are moved with beta*defect_beta, so
+/// defect_beta < 1 keeps them hotter than the formed cage and speeds up the healing.
+/// Temperature `groups` scale beta for the atoms of a selection (see TemperatureGroup), e.g. to
+/// heal a defective patch while the rest of the cage stays cold. The `fixed` atoms are not moved
+/// at all; the global radius move, which would scale them, is then left out.
+/// Atoms evaporated from the cluster (fragments out of interaction range) are reported at every
+/// log row, with `retether` they are moved back next to the largest fragment.
+/// With a `calibration` beta_max is not used as given but chosen by a pre-run before the cooling
+/// (see calibrate_beta_max).
+/// The colored and speculative moves run on `move_threads` threads, 0 for all cores; speculative
+/// moves evaluate blocks of `speculation_depth` atoms.
+/// With a `shell_release` the atoms are held near a spherical shell by a restraint that decays
+/// over the first iterations (see shell_release); logged energies include it until the release.
+/// A confining wall of the potential that follows mean_r is moved before every sweep.
+/// A `watchdog` perturbs runs jammed at a high energy (see watchdog)
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub beta_min: f64,
+    pub beta_max: f64,
+    pub p: f64,
+    pub it_max: usize,
+    pub equilibration: usize,
+    pub stop: Stopping,
+    pub table: Option<Vec<(usize, f64)>>,
+    pub defect_beta: f64,
+    pub moves: Moves,
+    pub retether: bool,
+    pub calibration: Option<Calibration>,
+    pub move_threads: usize,
+    pub speculation_depth: usize,
+    pub shell_release: Option<ShellRelease>,
+    pub watchdog: Option<Watchdog>,
+    pub groups: Vec<TemperatureGroup>,
+    pub fixed: Option<Selection>,
+    pub gpu: bool, // full energy evaluations (energy_calc) on the GPU, gpu feature
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule { beta_min: 1., beta_max: 100., p: 2., it_max: 100_000, equilibration: 0,
+                   stop: Stopping::default(), table: None, defect_beta: 1., moves: Moves::Uniform,
+                   retether: false, calibration: None, move_threads: 0,
+                   speculation_depth: 16, shell_release: None, watchdog: None, groups: Vec::new(), fixed: None,
+                   gpu: false }
+    }
+}
+
+/// atoms of a selection, re-evaluated before every sweep, moved with beta*beta_factor; an atom
+/// in several groups takes the first. beta_factor inf anneals them at T = 0 (downhill moves only),
+/// not with smart moves, whose step a*beta*F would be infinite
+#[derive(Debug, Clone)]
+pub struct TemperatureGroup {
+    pub beta_factor: f64,
+    pub selection: Selection,
+}
+
+/// atom moves of the cooling sweeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Moves {
+    Uniform, // random shifts in spherical coordinates
+    Smart,   // force-bias shifts
+    Hmc,     // one hybrid MC trajectory of all atoms per sweep
+    Colored,     // uniform shifts, independent atoms in parallel (see colored_sweep)
+    Speculative, // uniform shifts evaluated ahead in parallel (see speculative_sweep)
+}
+
+/// an iteration of anneal_with as its callback sees it, after the sweep
+pub struct Step<'a> {
+    pub it: usize,
+    pub beta: f64,
+    pub accepted: usize, // accepted atom moves of the sweep
+    /// schedule of the remaining iterations; changes (beta, it_max, moves, stopping, ...) apply
+    /// from the next iteration on
+    pub schedule: &'a mut Schedule,
+}
+
+/// answer of the anneal_with callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// when anneal_observed writes a log row and records a frame: never in the first `burn_in`
+/// cooling iterations, then at the `log` and `frames` cadences
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cadence {
+    pub burn_in: usize,
+    pub log: Every,
+    pub frames: Every,
+}
+
+impl Cadence {
+    /// a log row and a frame every log_step iterations from the start
+    pub fn every(log_step: usize) -> Cadence {
+        Cadence { burn_in: 0, log: Every::Iterations(log_step), frames: Every::Iterations(log_step) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Every {
+    Iterations(usize),
+    /// first iteration of each of k windows of equal width in T, from the start to the end temperature
+    TemperatureWindows(usize),
+}
+
+impl Every {
+    // true if iteration it of the schedule is saved
+    fn due(&self, it: usize, schedule: &Schedule) -> bool {
+        match *self {
+            Every::Iterations(step) => it.is_multiple_of(step.max(1)),
+            Every::TemperatureWindows(k) => {
+                let (t_start, t_end) = (schedule.temperature(0).0, schedule.temperature(schedule.it_max).0);
+                let window = |it: usize| ((k as f64*(t_start - schedule.temperature(it).0)/(t_start - t_end)).floor()
+                                           .max(0.) as usize).min(k.max(1) - 1);
+                it == 0 || window(it) != window(it - 1)
+            }
+        }
+    }
+}
+
+// parameters of the smart MC and HMC moves
+const SMART_A: f64 = 5e-4;
+const HMC_DT: f64 = 0.1;
+const HMC_STEPS: usize = 10;
+
+/// early termination of the cooling, checked every `window` iterations; whichever criterion
+/// is met first stops the run. e_tol = 0 and min_acceptance = 0 disable the respective check
+#[derive(Debug, Clone)]
+pub struct Stopping {
+    pub window: usize,
+    pub e_tol: f64,
+    pub min_acceptance: f64,
+    pub wall_time: Option<Duration>,
+}
+
+impl Default for Stopping {
+    fn default() -> Self {
+        Stopping { window: 1000, e_tol: 0., min_acceptance: 0., wall_time: None }
+    }
+}
+
+/// observables in the anneal logs
+pub const ANNEAL_COLUMNS: [&str; 4] = ["beta", "T", "E", "r_mean"];
+
+/// ANNEAL_COLUMNS followed by the names of the observed collective variables
+pub fn anneal_columns(observables: &[Box<dyn CollectiveVariable>]) -> Vec<String> {
+    ANNEAL_COLUMNS.iter().map(|c| c.to_string()).chain(observables.iter().map(|cv| cv.name())).collect()
+}
+
+/// config keys with integer values
+pub const INTEGER_KEYS: [&str; 10] = ["it_max", "equilibration", "window", "calibrate_it", "move_threads",
+                                      "speculation_depth", "shell_release", "watchdog_window", "watchdog_kicks",
+                                      "watchdog_reheat"];
+
+// step size tuning during equilibration
+const TARGET_ACCEPTANCE: f64 = 0.5;
+const TUNE_EVERY: usize = 100;
+
+impl Schedule {
+    /// reads "key value" lines ('#' comments allowed) over the defaults; keys are the field names
+    /// beta_min, beta_max, p, it_max, equilibration, defect_beta,
+    /// moves (uniform, smart, hmc, colored, speculative), move_threads, speculation_depth,
+    /// T_start and T_end [K] as an alternative to beta_min and beta_max,
+    /// the stopping criteria window, e_tol, min_acceptance, wall_time [s],
+    /// schedule <file> for a tabulated schedule read by from_file, and
+    /// beta_max auto for a calibrated beta_max with the band calibrate_low, calibrate_high and the
+    /// pre-run length calibrate_it (any of them enables the calibration, a numeric beta_max or
+    /// T_end disables it), and shell_k [eV/A^2], shell_release (iterations) and shell_radius [A]
+    /// for a shell restraint at the start (any of them enables it), and the watchdog keys
+    /// watchdog_window, watchdog_acceptance, watchdog_e_max [eV], watchdog_kicks, watchdog_reheat
+    /// (iterations rewound) or watchdog_shake (fraction of atoms) with watchdog_amplitude [A]
+    /// (any of them enables it), group <beta_factor> <selection> (repeatable) for the
+    /// temperature groups and fixed <selection> for atoms held in place; defect_beta, groups and
+    /// fixed atoms need atom moves, not moves hmc
+    pub fn from_config(path: &str) -> Result<Schedule, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Schedule::from_config_str(&content, path)
+    }
+
+    /// from_config on the content of a config; path labels the errors and anchors relative paths
+    pub fn from_config_str(content: &str, path: &str) -> Result<Schedule, String> {
+        let mut schedule = Schedule::default();
+
+        for (n, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() { continue }
+
+            let err = || format!("{}:{}: cannot parse \"{}\"", path, n + 1, line);
+            let (key, value) = line.split_once(char::is_whitespace).ok_or_else(err)?;
+            let value = value.trim();
+            let float = || value.parse::<f64>().map_err(|_| err());
+            let int = || value.parse::<usize>().map_err(|_| err());
+            let window = || match int()? {
+                0 => Err(format!("{}:{}: {} must be at least 1", path, n + 1, key)),
+                w => Ok(w),
+            };
+
+            match key {
+                "beta_min" => schedule.beta_min = float()?,
+                "beta_max" if value == "auto" => { schedule.calibration.get_or_insert_with(Calibration::default); }
+                "beta_max" => (schedule.beta_max, schedule.calibration) = (float()?, None),
+                "T_start" => schedule.beta_min = Temperature(float()?).beta().0,
+                "T_end" => (schedule.beta_max, schedule.calibration) = (Temperature(float()?).beta().0, None),
+                "calibrate_low" => schedule.calibration.get_or_insert_with(Calibration::default).low = float()?,
+                "calibrate_high" => schedule.calibration.get_or_insert_with(Calibration::default).high = float()?,
+                "calibrate_it" => schedule.calibration.get_or_insert_with(Calibration::default).it = int()?,
+                "shell_k" => schedule.shell_release.get_or_insert_with(ShellRelease::default).k = float()?,
+                "shell_release" => schedule.shell_release.get_or_insert_with(ShellRelease::default).it = int()?,
+                "shell_radius" => schedule.shell_release.get_or_insert_with(ShellRelease::default).radius = Some(float()?),
+                "watchdog_window" => schedule.watchdog.get_or_insert_with(Watchdog::default).window = window()?,
+                "watchdog_acceptance" => schedule.watchdog.get_or_insert_with(Watchdog::default).min_acceptance = float()?,
+                "watchdog_e_max" => schedule.watchdog.get_or_insert_with(Watchdog::default).e_max = float()?,
+                "watchdog_kicks" => schedule.watchdog.get_or_insert_with(Watchdog::default).max_kicks = int()?,
+                "watchdog_reheat" => schedule.watchdog.get_or_insert_with(Watchdog::default).perturbation = Perturbation::Reheat(int()?),
+                "watchdog_shake" | "watchdog_amplitude" => {
+                    let watchdog = schedule.watchdog.get_or_insert_with(Watchdog::default);
+                    let (fraction, amplitude) = match watchdog.perturbation {
+                        Perturbation::Shake { fraction, amplitude } => (fraction, amplitude),
+                        Perturbation::Reheat(_) => (0.2, 0.3),
+                    };
+                    watchdog.perturbation = match key {
+                        "watchdog_shake" => Perturbation::Shake { fraction: float()?, amplitude },
+                        _ => Perturbation::Shake { fraction, amplitude: float()? },
+                    };
+                }
+                "p" => schedule.p = float()?,
+                "it_max" => schedule.it_max = int()?,
+                "equilibration" => schedule.equilibration = int()?,
+                "defect_beta" => schedule.defect_beta = float()?,
+                "group" => {
+                    let (factor, selection) = value.split_once(char::is_whitespace).ok_or_else(err)?;
+                    let beta_factor = factor.parse::<f64>().map_err(|_| err())?;
+                    if beta_factor.is_nan() || beta_factor <= 0. {
+                        return Err(format!("{}:{}: the beta factor of a group must be positive", path, n + 1));
+                    }
+                    let selection = Selection::parse(selection).map_err(|e| format!("{}:{}: {}", path, n + 1, e))?;
+                    schedule.groups.push(TemperatureGroup { beta_factor, selection });
+                }
+                "fixed" => schedule.fixed = Some(Selection::parse(value).map_err(|e| format!("{}:{}: {}", path, n + 1, e))?),
+                "move_threads" => schedule.move_threads = int()?,
+                "speculation_depth" => schedule.speculation_depth = int()?,
+                "moves" => schedule.moves = match value {
+                    "uniform" => Moves::Uniform,
+                    "smart" => Moves::Smart,
+                    "hmc" => Moves::Hmc,
+                    "colored" => Moves::Colored,
+                    "speculative" => Moves::Speculative,
+                    _ => return Err(err()),
+                },
+                "retether" => schedule.retether = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(err()),
+                },
+                "gpu" => schedule.gpu = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(err()),
+                },
+                "window" => schedule.stop.window = window()?,
+                "e_tol" => schedule.stop.e_tol = float()?,
+                "min_acceptance" => schedule.stop.min_acceptance = float()?,
+                "wall_time" => schedule.stop.wall_time = Some(Duration::from_secs_f64(float()?)),
+                "schedule" => {
+                    // relative to the config file
+                    let table_path = std::path::Path::new(path).with_file_name(value);
+                    let table = Schedule::from_file(&table_path.to_string_lossy())?;
+                    schedule = Schedule { beta_min: table.beta_min, beta_max: table.beta_max,
+                                          it_max: table.it_max, table: table.table, ..schedule };
+                }
+                // read by Potential::from_config
+                _ if POTENTIAL_KEYS.contains(&key) => {}
+                _ => return Err(format!("{}:{}: unknown key {}", path, n + 1, key)),
+            }
+        }
+        if schedule.gpu && !cfg!(feature = "gpu") {
+            return Err(format!("{}: gpu on needs a build with the gpu feature", path));
+        }
+        if schedule.fixed.is_some() && schedule.moves == Moves::Hmc {
+            return Err(format!("{}: fixed atoms need atom moves, hmc moves all atoms together", path));
+        }
+        if (schedule.defect_beta != 1. || !schedule.groups.is_empty()) && schedule.moves == Moves::Hmc {
+            return Err(format!("{}: defect_beta and temperature groups need atom moves, hmc moves all atoms at one beta", path));
+        }
+        let infinite = schedule.defect_beta.is_infinite() || schedule.groups.iter().any(|g| g.beta_factor.is_infinite());
+        if infinite && schedule.moves == Moves::Smart {
+            return Err(format!("{}: an infinite beta factor needs uniform, colored or speculative moves, \
+                                smart moves step along beta*F", path));
+        }
+        Ok(schedule)
+    }
+
+    /// config in the from_config format; a tabulated schedule is referenced as schedule.dat,
+    /// next to the config, where save_table puts it
+    pub fn to_config(&self) -> String {
+        let mut config = String::new();
+        if self.table.is_some() {
+            config += "schedule schedule.dat\n";
+        }
+        else {
+            config += &format!("beta_min {}\nbeta_max {}\np {}\nit_max {}\n", self.beta_min, self.beta_max, self.p, self.it_max);
+            if let Some(c) = &self.calibration {
+                config += &format!("beta_max auto\ncalibrate_low {}\ncalibrate_high {}\ncalibrate_it {}\n", c.low, c.high, c.it);
+            }
+        }
+        config += &format!("equilibration {}\ndefect_beta {}\nmoves {}\n", self.equilibration, self.defect_beta,
+                           format!("{:?}", self.moves).to_lowercase());
+        if matches!(self.moves, Moves::Colored | Moves::Speculative) {
+            config += &format!("move_threads {}\n", self.move_threads);
+        }
+        if self.moves == Moves::Speculative {
+            config += &format!("speculation_depth {}\n", self.speculation_depth);
+        }
+        if let Some(s) = &self.shell_release {
+            config += &format!("shell_k {}\nshell_release {}\n", s.k, s.it);
+            if let Some(r) = s.radius {
+                config += &format!("shell_radius {}\n", r);
+            }
+        }
+        if let Some(w) = &self.watchdog {
+            config += &format!("watchdog_window {}\nwatchdog_acceptance {}\nwatchdog_e_max {}\nwatchdog_kicks {}\n",
+                               w.window, w.min_acceptance, w.e_max, w.max_kicks);
+            config += &match w.perturbation {
+                Perturbation::Reheat(it) => format!("watchdog_reheat {}\n", it),
+                Perturbation::Shake { fraction, amplitude } => format!("watchdog_shake {}\nwatchdog_amplitude {}\n", fraction, amplitude),
+            };
+        }
+        for group in &self.groups {
+            config += &format!("group {} {}\n", group.beta_factor, group.selection);
+        }
+        if let Some(fixed) = &self.fixed {
+            config += &format!("fixed {}\n", fixed);
+        }
+        config += &format!("retether {}\n", if self.retether { "on" } else { "off" });
+        if self.gpu {
+            config += "gpu on\n";
+        }
+        config += &format!("window {}\ne_tol {}\nmin_acceptance {}\n", self.stop.window, self.stop.e_tol, self.stop.min_acceptance);
+        if let Some(t) = self.stop.wall_time {
+            config += &format!("wall_time {}\n", t.as_secs_f64());
+        }
+        config
+    }
+
+    /// writes the tabulated schedule in the from_file format
+    pub fn save_table(&self, path: &str) -> io::Result<()> {
+        crate::utilities::write_atomic(path, |f| {
+            for &(it, beta) in self.table.iter().flatten() {
+                writeln!(f, "{} {}", it, beta)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// reads a two column file "iteration beta" (increasing iterations, '#' comments allowed);
+    /// beta is interpolated linearly between the points, it_max is the last iteration + 1
+    pub fn from_file(path: &str) -> Result<Schedule, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+
+        let mut table = Vec::new();
+        for (n, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let cols: Vec<&str> = line.split_ascii_whitespace().collect();
+            let point = match cols[..] {
+                [it, beta] => it.parse::<usize>().ok().zip(beta.parse::<f64>().ok()),
+                _ => None,
+            };
+            let point = point.ok_or(format!("{}:{}: expected \"iteration beta\"", path, n + 1))?;
+
+            if table.last().is_some_and(|&(it_last, _)| point.0 <= it_last) {
+                return Err(format!("{}:{}: iterations must be increasing", path, n + 1));
+            }
+            table.push(point);
+        }
+        if table.is_empty() {
+            return Err(format!("{}: empty schedule", path));
+        }
+
+        let betas = table.iter().map(|&(_, b)| b);
+        Ok(Schedule { beta_min: betas.clone().fold(f64::INFINITY, f64::min),
+                      beta_max: betas.fold(f64::NEG_INFINITY, f64::max),
+                      it_max: table[table.len() - 1].0 + 1,
+                      table: Some(table),
+                      ..Default::default() })
+    }
+
+    /// power law schedule cooling from t_start to t_end, other settings default
+    pub fn from_temperatures(t_start: Temperature, t_end: Temperature) -> Schedule {
+        Schedule { beta_min: t_start.beta().0, beta_max: t_end.beta().0, ..Default::default() }
+    }
+
+    /// the schedule with beta_max calibrated on f if a calibration is set (tabulated schedules are
+    /// never calibrated), a plain copy otherwise
+    pub fn calibrated(&self, f: &Fuleren) -> Schedule {
+        match &self.calibration {
+            Some(c) if self.table.is_none() => {
+                Schedule { beta_max: f.calibrate_beta_max(self, c), calibration: None, ..self.clone() }
+            }
+            _ => Schedule { calibration: None, ..self.clone() },
+        }
+    }
+
+    /// temperature at iteration it
+    pub fn temperature(&self, it: usize) -> Temperature {
+        Beta(self.beta(it)).temperature()
+    }
+
+    pub fn beta(&self, it: usize) -> f64 {
+        match &self.table {
+            Some(table) => interpolate(table, it),
+            None => get_beta(it, self.it_max, self.beta_min, self.beta_max, self.p),
+        }
+    }
+}
+
+// linear interpolation, constant outside of the table
+fn interpolate(table: &[(usize, f64)], it: usize) -> f64 {
+    let k = table.partition_point(|&(i, _)| i <= it);
+    if k == 0 { return table[0].1 }
+    if k == table.len() { return table[k - 1].1 }
+
+    let (i0, b0) = table[k - 1];
+    let (i1, b1) = table[k];
+    b0 + (it - i0) as f64/(i1 - i0) as f64 * (b1 - b0)
+}
+
+impl Fuleren {
+    /// standard anneal: every iteration a sweep of atom moves and one global radius shift;
+    /// every log_step iterations a row of ANNEAL_COLUMNS is streamed to log.
+    /// Returns the number of cooling iterations done, less than it_max if schedule.stop was met
+    pub fn anneal<W: Write>(&mut self, schedule: &Schedule, log: &mut ObservableWriter<W>, log_step: usize) -> io::Result<usize> {
+        self.anneal_observed(schedule, log, &Cadence::every(log_step), &[], None, None)
+    }
+
+    /// anneal with the collective variables logged after the ANNEAL_COLUMNS (columns: anneal_columns)
+    /// and, with `frames`, the structure recorded, both at the given cadence; the frames carry the
+    /// values of the collective variables in their info line (punctuation of the names as '_'); with
+    /// `snapshots` its triggers are checked after every sweep, burn-in included
+    pub fn anneal_observed<W: Write>(&mut self, schedule: &Schedule, log: &mut ObservableWriter<W>, cadence: &Cadence,
+                                     observables: &[Box<dyn CollectiveVariable>], mut frames: Option<&mut FrameWriter>,
+                                     mut snapshots: Option<&mut Snapshots>) -> io::Result<usize> {
+        let mut n_fragments = 1;
+        let mut written = Ok(());
+
+        let it = self.anneal_with(schedule, |f, step| {
+            let it = step.it;
+            let (beta, temperature) = (step.beta, Beta(step.beta).temperature().0);
+            if let Some(snapshots) = snapshots.as_deref_mut() {
+                written = snapshots.check(f, it, temperature);
+                if written.is_err() { return Control::Stop }
+            }
+            if it < cadence.burn_in {
+                return Control::Continue
+            }
+            if let Some(frames) = frames.as_deref_mut().filter(|_| cadence.frames.due(it, step.schedule)) {
+                let mut info = format!("it={} beta={:.6} T={:.1}", it, beta, temperature);
+                for cv in observables {
+                    let key = cv.name().replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+                    info += &format!(" {}={:.6}", key, cv.value(f));
+                }
+                written = frames.write(f, &info);
+                if written.is_err() { return Control::Stop }
+            }
+            if !cadence.log.due(it, step.schedule) {
+                return Control::Continue
+            }
+            let fragments = f.fragments(f.potential.brenner.r2);
+            if fragments.len() != n_fragments && fragments.len() > 1 {
+                let sizes: Vec<usize> = fragments.iter().map(|f| f.len()).collect();
+                tracing::warn!(it, ?sizes, "cluster fragmented, atoms evaporated");
+            }
+            n_fragments = fragments.len();
+            if step.schedule.retether && n_fragments > 1 {
+                f.retether(&fragments);
+                f.energy_calc();
+                tracing::info!(it, E = f.E, "fragments moved back to the cluster");
+                n_fragments = 1;
+            }
+
+            let mut row = vec![beta, temperature, f.E, f.mean_r()];
+            row.extend(observables.iter().map(|cv| cv.value(f)));
+            written = log.row(it, &row);
+            if written.is_ok() { Control::Continue } else { Control::Stop }
+        });
+        written?;
+        log.flush()?;
+        Ok(it)
+    }
+
+    /// the anneal loop with a callback after every sweep instead of the logging: the callback sees
+    /// the structure and the iteration (Step), may change both the structure and the schedule of
+    /// the remaining iterations, and stops the run by returning Control::Stop. Equilibration,
+    /// calibration, the shell restraint, the watchdog and schedule.stop are handled as in anneal_observed.
+    /// Returns the number of cooling iterations done
+    pub fn anneal_with<F>(&mut self, schedule: &Schedule, mut callback: F) -> usize
+    where F: FnMut(&mut Fuleren, &mut Step) -> Control {
+        #[cfg(feature = "gpu")]
+        let _kernel = match schedule.gpu {
+            true => crate::gpu::KernelGuard::install(self)
+                        .map_err(|e| tracing::warn!(error = %e, "energies of the anneal on the CPU"))
+                        .ok(),
+            false => None,
+        };
+        // the restraint holds during the equilibration at its initial strength
+        self.potential.shell = schedule.shell_release.and_then(|s| s.restraint(0, self.size));
+        self.place_wall();
+        if schedule.equilibration > 0 {
+            self.equilibrate(schedule, schedule.beta_min, schedule.equilibration);
+            tracing::info!(it = schedule.equilibration, E = self.E, steps = ?self.steps, "equilibration finished");
+        }
+        let mut schedule = match schedule.calibration {
+            Some(_) => schedule.calibrated(self),
+            None => schedule.clone(),
+        };
+
+        // only read the clock when needed, Instant is not available on every target (wasm)
+        let start = schedule.stop.wall_time.map(|_| Instant::now());
+        let mut e_window = self.E;
+        let mut accepted = 0;
+        let (mut watch_accepted, mut kicks) = (0, 0);
+
+        let mut it = 0;
+        while it < schedule.it_max {
+            if let Some(release) = &schedule.shell_release {
+                let restraint = release.restraint(it, self.size);
+                if restraint.is_none() && self.potential.shell.is_some() {
+                    tracing::info!(it, "shell restraint released");
+                }
+                self.potential.shell = restraint;
+            }
+            self.place_wall();
+            let beta = schedule.beta(it);
+            let accepted_sweep = self.sweep(&schedule, beta);
+            accepted += accepted_sweep;
+            tracing::debug!(it, beta, E = self.E, "sweep");
+
+            let mut step = Step { it, beta, accepted: accepted_sweep, schedule: &mut schedule };
+            if callback(self, &mut step) == Control::Stop {
+                tracing::info!(it, "anneal stopped by the callback");
+                self.release_shell();
+                return it + 1;
+            }
+
+            if let Some(w) = schedule.watchdog {
+                watch_accepted += accepted_sweep;
+                if (it + 1) % w.window == 0 {
+                    let acceptance = watch_accepted as f64/(w.window*self.size) as f64;
+                    let e_per_atom = self.E/self.size as f64;
+                    watch_accepted = 0;
+                    if kicks < w.max_kicks && w.stuck(acceptance, e_per_atom) {
+                        kicks += 1;
+                        match w.perturbation {
+                            Perturbation::Reheat(back) => {
+                                tracing::warn!(it, acceptance, e_per_atom, kicks, to = it.saturating_sub(back),
+                                               "run stuck, schedule rewound");
+                                it = it.saturating_sub(back);
+                                continue
+                            }
+                            Perturbation::Shake { fraction, amplitude } => {
+                                let shaken = self.shake_worst(fraction, amplitude);
+                                tracing::warn!(it, acceptance, e_per_atom, kicks, shaken = shaken.len(), E = self.E,
+                                               "run stuck, atoms of highest energy shaken");
+                            }
+                        }
+                    }
+                }
+            }
+
+            let stop = &schedule.stop;
+            if (it + 1) % stop.window == 0 {
+                let acceptance = accepted as f64/(stop.window*self.size) as f64;
+                let de = (self.E - e_window).abs();
+                let out_of_time = matches!((start, stop.wall_time), (Some(s), Some(t)) if s.elapsed() > t);
+
+                if de < stop.e_tol || acceptance < stop.min_acceptance || out_of_time {
+                    tracing::info!(it, de, acceptance, out_of_time, "stopping criterion met");
+                    self.release_shell();
+                    return it + 1;
+                }
+                e_window = self.E;
+                accepted = 0;
+            }
+            it += 1;
+        }
+        self.release_shell();
+        schedule.it_max
+    }
+
+    // drops a shell restraint left when the cooling ends before its release
+    fn release_shell(&mut self) {
+        if self.potential.shell.take().is_some() {
+            self.energy_calc();
+        }
+    }
+
+    /// single anneal iteration at beta: atom moves of schedule.moves and one global radius shift;
+    /// returns the number of accepted atom moves
+    pub fn sweep(&mut self, schedule: &Schedule, beta: f64) -> usize {
+        let mut accepted = 0;
+
+        if schedule.moves == Moves::Hmc {
+            if self.hmc_step(beta, HMC_DT, HMC_STEPS) { accepted += self.size; }
+        }
+        else if matches!(schedule.moves, Moves::Colored | Moves::Speculative) {
+            let betas = self.atom_betas(schedule, beta).unwrap_or_else(|| vec![beta; self.size]);
+            let fixed = self.fixed_atoms(schedule);
+            let threads = match schedule.move_threads {
+                0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+                n => n,
+            };
+            accepted += match schedule.moves {
+                Moves::Colored => self.colored_sweep(&betas, &fixed, threads),
+                _ => self.speculative_sweep(&betas, &fixed, schedule.speculation_depth, threads),
+            };
+        }
+        else {
+            let betas = self.atom_betas(schedule, beta);
+            let fixed = self.fixed_atoms(schedule);
+            let mut cache = BondCache::new(self.size);
+
+            for i in (0..self.size).filter(|&i| !fixed[i]) {
+                let beta_i = betas.as_ref().map_or(beta, |b| b[i]);
+                let accept = match schedule.moves {
+                    Moves::Smart => self.smart_atom_shift(i, beta_i, SMART_A),
+                    _ => self.random_atom_shift(i, beta_i, &mut cache),
+                };
+                if accept { accepted += 1; }
+            }
+        }
+        // the radius move is the one refreshing E, the atom moves only keep the local energies
+        if schedule.fixed.is_none() {
+            self.random_global_r_shift(beta);
+        }
+        else {
+            self.energy_calc();
+        }
+        accepted
+    }
+
+    // atoms held in place by schedule.fixed
+    fn fixed_atoms(&self, schedule: &Schedule) -> Vec<bool> {
+        schedule.fixed.as_ref().map_or_else(|| vec![false; self.size], |s| self.select(s))
+    }
+
+    // per atom beta of the sweep from defect_beta and the temperature groups; None when every
+    // atom has beta (the bond graph and the selections are only evaluated when needed)
+    fn atom_betas(&self, schedule: &Schedule, beta: f64) -> Option<Vec<f64>> {
+        if schedule.defect_beta == 1. && schedule.groups.is_empty() {
+            return None
+        }
+        let mut betas = match (schedule.defect_beta != 1.).then(|| self.coordination()) {
+            Some(c) => c.iter().map(|&n| if n < 3 { beta*schedule.defect_beta } else { beta }).collect(),
+            None => vec![beta; self.size],
+        };
+        let mut grouped = vec![false; self.size];
+        for group in &schedule.groups {
+            for (i, selected) in self.select(&group.selection).into_iter().enumerate() {
+                if selected && !grouped[i] {
+                    betas[i] *= group.beta_factor;
+                    grouped[i] = true;
+                }
+            }
+        }
+        Some(betas)
+    }
+}
+
+impl Fuleren {
+    /// n_it iterations at fixed beta, with the fixed atoms, defect_beta and temperature groups of
+    /// the schedule as in sweep; every TUNE_EVERY iterations the step sizes are scaled towards
+    /// TARGET_ACCEPTANCE, so the cooling starts from an equilibrated structure
+    pub fn equilibrate(&mut self, schedule: &Schedule, beta: f64, n_it: usize) {
+        let (mut acc_atom, mut acc_all, mut tried_atom) = (0, 0, 0);
+
+        for it in 1..=n_it {
+            let betas = self.atom_betas(schedule, beta);
+            let fixed = self.fixed_atoms(schedule);
+            let mut cache = BondCache::new(self.size);
+            for i in (0..self.size).filter(|&i| !fixed[i]) {
+                let beta_i = betas.as_ref().map_or(beta, |b| b[i]);
+                if self.random_atom_shift(i, beta_i, &mut cache) { acc_atom += 1; }
+                tried_atom += 1;
+            }
+            if schedule.fixed.is_none() {
+                if self.random_global_r_shift(beta) { acc_all += 1; }
+            }
+            else {
+                self.energy_calc();
+            }
+
+            if it % TUNE_EVERY == 0 {
+                let ratio_atom = acc_atom as f64/tried_atom.max(1) as f64;
+                let factor_atom = (ratio_atom/TARGET_ACCEPTANCE).clamp(0.5, 2.);
+                self.steps.w_r *= factor_atom;
+                self.steps.w_phi *= factor_atom;
+                self.steps.w_theta *= factor_atom;
+                // no radius moves with fixed atoms, w_all stays
+                let ratio_all = acc_all as f64/TUNE_EVERY as f64;
+                if schedule.fixed.is_none() {
+                    self.steps.w_all *= (ratio_all/TARGET_ACCEPTANCE).clamp(0.5, 2.);
+                }
+                tracing::debug!(it, ratio_atom, ratio_all, "step sizes tuned");
+                (acc_atom, acc_all, tried_atom) = (0, 0, 0);
+            }
+        }
+    }
+}
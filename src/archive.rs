@@ -0,0 +1,140 @@
+//! archives of the lowest-energy distinct structures per N across runs: dir/archive_N<n>.extxyz
+//! holds up to `size` frames ordered by energy, each with the run it came from. A new structure is
+//! a duplicate of an archived one when their bond graphs are isomorphic and the RMSD after
+//! alignment (see unlabelled_rmsd) is below rmsd_tol; the lower energy of the two is kept.
+//! Updates take a lock file, so the threads of a runner and the processes of a distributed sweep
+//! can share an archive; the energies are compared as stored, keep one potential per archive
+use std::fs::OpenOptions;
+use std::time::Duration;
+
+use crate::bonds::isomorphic;
+use crate::compare::{centered, unlabelled_rmsd};
+use crate::extxyz::key_values;
+use crate::utilities::write_atomic;
+use crate::{Fuleren, R1};
+
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub dir: String,
+    pub size: usize,   // structures kept per N
+    pub rmsd_tol: f64, // [A]
+}
+
+/// an archived structure with E set, and the run it came from
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub structure: Fuleren,
+    pub source: String,
+}
+
+// a lock file older than this is left over from a crashed process
+const STALE_LOCK: Duration = Duration::from_secs(60);
+
+impl Archive {
+    pub fn new(dir: &str) -> Archive {
+        Archive { dir: dir.to_string(), size: 10, rmsd_tol: 0.1 }
+    }
+
+    pub fn path(&self, n: usize) -> String {
+        format!("{}/archive_N{}.extxyz", self.dir, n)
+    }
+
+    /// archived structures of n atoms, lowest energy first; none without an archive file
+    pub fn read(&self, n: usize) -> Result<Vec<ArchiveEntry>, String> {
+        let path = self.path(n);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("cannot read {}: {}", path, e)),
+        };
+        let frames = Fuleren::frames_from_str(&content, &path)?;
+        Ok(frames.into_iter().zip(comment_lines(&content)).map(|(mut structure, comment)| {
+            let mut source = String::new();
+            for (key, value) in key_values(comment) {
+                match key.as_str() {
+                    "energy" => structure.E = value.parse().unwrap_or(f64::NAN),
+                    "source" => source = value,
+                    _ => {}
+                }
+            }
+            ArchiveEntry { structure, source }
+        }).collect())
+    }
+
+    /// offers f (E up to date) from source to the archive of its size; returns its rank (0 for
+    /// the lowest energy) if kept, None if the archive is full of lower energies or holds the
+    /// same structure at a lower energy
+    pub fn add(&self, f: &Fuleren, source: &str) -> Result<Option<usize>, String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| format!("cannot create {}: {}", self.dir, e))?;
+        let _lock = Lock::acquire(&format!("{}/archive_N{}.lock", self.dir, f.size))?;
+
+        let mut entries = self.read(f.size)?;
+        let (graph, xyz) = (f.bond_graph(R1), centered(f));
+        let duplicate = entries.iter().position(|e| {
+            isomorphic(&graph, &e.structure.bond_graph(R1)) && unlabelled_rmsd(&xyz, &centered(&e.structure)) < self.rmsd_tol
+        });
+        match duplicate {
+            Some(k) if entries[k].structure.E <= f.E => return Ok(None),
+            Some(k) => { entries.remove(k); }
+            None => {}
+        }
+        let rank = entries.iter().take_while(|e| e.structure.E <= f.E).count();
+        if rank >= self.size {
+            return Ok(None)
+        }
+        entries.insert(rank, ArchiveEntry { structure: f.clone(), source: source.to_string() });
+        entries.truncate(self.size);
+
+        let path = self.path(f.size);
+        write_atomic(&path, |out| {
+            // per-atom energies with the potential of f, the stored totals as they are
+            for entry in entries.iter_mut() {
+                entry.structure.potential = f.potential.clone();
+                entry.structure.write_extxyz(out, &format!("source=\"{}\"", entry.source))?;
+            }
+            Ok(())
+        }).map_err(|e| format!("cannot write {}: {}", path, e))?;
+        Ok(Some(rank))
+    }
+}
+
+// comment lines of the frames of an (extended) XYZ file
+fn comment_lines(content: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    while let Some(size) = lines.next().and_then(|l| l.trim().parse::<usize>().ok()) {
+        let Some(comment) = lines.next() else { break };
+        comments.push(comment);
+        lines.by_ref().take(size).for_each(drop);
+    }
+    comments
+}
+
+// exclusive access to an archive across threads and processes: a file created with create_new,
+// removed on drop; stale locks are broken
+struct Lock(String);
+
+impl Lock {
+    fn acquire(path: &str) -> Result<Lock, String> {
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => return Ok(Lock(path.to_string())),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let age = std::fs::metadata(path).and_then(|m| m.modified()).ok().and_then(|t| t.elapsed().ok());
+                    if age.is_some_and(|age| age > STALE_LOCK) {
+                        tracing::warn!(path, "stale archive lock removed");
+                        let _ = std::fs::remove_file(path);
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                Err(e) => return Err(format!("cannot lock {}: {}", path, e)),
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
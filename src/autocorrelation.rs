@@ -0,0 +1,104 @@
+//! energy autocorrelation of the temperature windows of an anneal log: integrated autocorrelation
+//! time, effective sample size and the error of the window mean. Times are in log rows, one row
+//! every log_step sweeps
+use std::io::{self, Write};
+
+use tracing::warn;
+
+use crate::reweighting::Window;
+use crate::units::Beta;
+use crate::utilities::write_atomic;
+
+/// effective samples below which the mean of a window is not trusted
+pub const MIN_EFFECTIVE_SAMPLES: f64 = 20.;
+
+// Sokal's automatic window: the sum of the autocorrelation stops at the first lag M >= C tau(M)
+const SOKAL_C: f64 = 5.;
+
+/// normalized autocorrelation rho(t) of a series for lags 0..n/2; empty for constant series
+pub fn autocorrelation(series: &[f64]) -> Vec<f64> {
+    let n = series.len();
+    let mean = series.iter().sum::<f64>()/n.max(1) as f64;
+    let d: Vec<f64> = series.iter().map(|x| x - mean).collect();
+    let c0 = d.iter().map(|x| x*x).sum::<f64>()/n.max(1) as f64;
+    if c0.is_nan() || c0 <= 0. {
+        return Vec::new();
+    }
+    (0..n/2).map(|t| d[..n - t].iter().zip(d[t..].iter()).map(|(a, b)| a*b).sum::<f64>()/(n as f64*c0))
+            .collect()
+}
+
+/// integrated autocorrelation time tau = 1/2 + sum_t rho(t) with Sokal's automatic window;
+/// 0.5 for uncorrelated rows
+pub fn integrated_time(series: &[f64]) -> f64 {
+    let rho = autocorrelation(series);
+    let mut tau = 0.5;
+    for (t, r) in rho.iter().enumerate().skip(1) {
+        tau += r;
+        if t as f64 >= SOKAL_C*tau {
+            break
+        }
+    }
+    tau.max(0.5)
+}
+
+/// diagnostics of one temperature window
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    pub beta: f64,
+    pub samples: usize,
+    pub tau: f64, // integrated autocorrelation time [rows]
+    pub ess: f64, // effective sample size n/(2 tau)
+    pub mean: f64,
+    pub error: f64, // standard error of the mean corrected by the correlation [eV]
+}
+
+/// diagnostics of the energies of every window
+pub fn diagnose(windows: &[Window]) -> Vec<Diagnostics> {
+    windows.iter().map(|w| {
+        let n = w.energies.len();
+        let mean = w.energies.iter().sum::<f64>()/n.max(1) as f64;
+        let var = w.energies.iter().map(|e| (e - mean).powi(2)).sum::<f64>()/(n.max(2) - 1) as f64;
+        let tau = integrated_time(&w.energies);
+        let ess = n as f64/(2.*tau);
+        Diagnostics { beta: w.beta, samples: n, tau, ess, mean, error: (var/ess.max(1.)).sqrt() }
+    }).collect()
+}
+
+/// rows "T beta samples tau ESS <E> error"
+pub fn save_diagnostics(diagnostics: &[Diagnostics], path: &str) -> io::Result<()> {
+    write_atomic(path, |f| {
+        writeln!(f, "# {:<12} {:<12} {:<8} {:<10} {:<10} {:<14} {:<12}", "T[K]", "beta[1/eV]", "samples",
+                 "tau[rows]", "ESS", "<E>[eV]", "error[eV]")?;
+        for d in diagnostics {
+            writeln!(f, "  {:<12.3} {:<12.6} {:<8} {:<10.3} {:<10.1} {:<14.6} {:<12.6}", Beta(d.beta).temperature().0,
+                     d.beta, d.samples, d.tau, d.ess, d.mean, d.error)?;
+        }
+        Ok(())
+    })
+}
+
+/// warns about windows whose log rows are already uncorrelated, so log_step is too coarse to
+/// resolve the correlation time, and about windows with too few effective samples for their mean,
+/// from too few rows or too correlated sweeps
+pub fn warn_diagnostics(diagnostics: &[Diagnostics], log_step: usize) {
+    let unresolved = diagnostics.iter().filter(|d| d.tau < 1.).count();
+    if unresolved > 0 {
+        warn!(windows = unresolved, log_step,
+              "energy correlation time shorter than log_step, not resolved by the log; use a smaller log_step");
+    }
+    // temperatures of the unreliable windows, by the reason
+    let few = |too_correlated: bool| -> Vec<String> {
+        diagnostics.iter()
+                   .filter(|d| d.ess < MIN_EFFECTIVE_SAMPLES && (d.tau >= 1.) == too_correlated)
+                   .map(|d| format!("{:.0}", Beta(d.beta).temperature().0))
+                   .collect()
+    };
+    let (rows, correlated) = (few(false), few(true));
+    if !rows.is_empty() {
+        warn!(T = ?rows, "too few log rows, the window averages are not meaningful");
+    }
+    if !correlated.is_empty() {
+        warn!(T = ?correlated, "sweeps too correlated, the window averages are not meaningful");
+    }
+}
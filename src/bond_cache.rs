@@ -0,0 +1,36 @@
+//! bond orders b_ij kept between energy evaluations of single atom moves. b_ij depends on the
+//! positions of i, j and the atoms within R2 of i, so moving an atom from p to q only changes the
+//! bond orders of atoms within R2 of p or q; all other entries stay valid. The cache lives for
+//! one sweep of atom moves, any other change of the positions needs a fresh one
+use crate::{Fuleren, Point6};
+
+/// b_ij of the evaluated pairs, row i holding the (j, b_ij) of atom i
+#[derive(Debug, Clone)]
+pub struct BondCache<T> {
+    rows: Vec<Vec<(usize, T)>>,
+}
+
+impl<T: Copy> BondCache<T> {
+    pub fn new(size: usize) -> BondCache<T> {
+        BondCache { rows: vec![Vec::new(); size] }
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<T> {
+        self.rows[i].iter().find(|&&(k, _)| k == j).map(|&(_, b)| b)
+    }
+
+    pub fn insert(&mut self, i: usize, j: usize, b: T) {
+        self.rows[i].push((j, b));
+    }
+
+    /// drops the bond orders changed by moving atom m between the positions `from` and `to`
+    pub fn moved(&mut self, f: &Fuleren, m: usize, from: &Point6, to: &Point6) {
+        let r2 = f.potential.brenner.r2.powi(2);
+        let near = |p: &Point6, q: &Point6| (p.x() - q.x()).powi(2) + (p.y() - q.y()).powi(2) + (p.z() - q.z()).powi(2) <= r2;
+        for (a, p) in f.positions.iter().enumerate() {
+            if a == m || near(p, from) || near(p, to) {
+                self.rows[a].clear();
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::sphere_cells::{SphereCells, CELLS_MIN_SIZE};
+use crate::{Fuleren, Point6, R1};
+
+impl Fuleren {
+    /// bond graph as neighbour lists, atoms closer than r_bond are bonded; large clusters are
+    /// searched through angular cells (see SphereCells)
+    pub fn bond_graph(&self, r_bond: f64) -> Vec<Vec<usize>> {
+        if self.size >= CELLS_MIN_SIZE {
+            let cells = SphereCells::build(self, r_bond);
+            return (0..self.size).map(|i| self.neighbours_in_cells(&cells, i, r_bond)).collect()
+        }
+        (0..self.size).map(|i| (0..self.size).filter(|&j| j != i && self._r_ij(i, j) <= r_bond).collect())
+                      .collect()
+    }
+
+    /// number of bonds of every atom; bonds are pairs inside R1, where the cutoff function is 1
+    pub fn coordination(&self) -> Vec<usize> {
+        self.bond_graph(R1).iter().map(|b| b.len()).collect()
+    }
+
+    /// connected components of the graph of atoms closer than r_bond, largest first
+    pub fn fragments(&self, r_bond: f64) -> Vec<Vec<usize>> {
+        let bonds = self.bond_graph(r_bond);
+        let mut seen = vec![false; self.size];
+        let mut fragments = Vec::new();
+        for start in 0..self.size {
+            if seen[start] { continue }
+            seen[start] = true;
+            let mut fragment = vec![start];
+            let mut k = 0;
+            while k < fragment.len() {
+                for &j in &bonds[fragment[k]] {
+                    if !seen[j] {
+                        seen[j] = true;
+                        fragment.push(j);
+                    }
+                }
+                k += 1;
+            }
+            fragment.sort_unstable();
+            fragments.push(fragment);
+        }
+        fragments.sort_by_key(|f| std::cmp::Reverse(f.len()));
+        fragments
+    }
+
+    /// moves every fragment but the first rigidly towards the atoms placed before it, until its
+    /// closest atom sits R0 from them (further out if that would overlap); E is not updated
+    pub fn retether(&mut self, fragments: &[Vec<usize>]) {
+        let Some((main, rest)) = fragments.split_first() else { return };
+        let mut placed = main.clone();
+
+        for fragment in rest {
+            let Some((a, b)) = fragment.iter()
+                                       .flat_map(|&a| placed.iter().map(move |&b| (a, b)))
+                                       .min_by(|&(a, b), &(c, d)| self._r_ij(a, b).total_cmp(&self._r_ij(c, d)))
+            else { continue };
+            let d = self._r_ij(a, b);
+            let (pa, pb) = (&self.positions[a], &self.positions[b]);
+            let unit = [(pb.x() - pa.x())/d, (pb.y() - pa.y())/d, (pb.z() - pa.z())/d];
+            let old: Vec<Point6> = fragment.iter().map(|&i| self.positions[i].clone()).collect();
+
+            let mut target = self.potential.brenner.r0;
+            while target < d {
+                for (&i, p) in fragment.iter().zip(old.iter()) {
+                    let shift = d - target;
+                    self.positions[i] = Point6::from_cartesian(&[p.x() + shift*unit[0], p.y() + shift*unit[1], p.z() + shift*unit[2]]);
+                }
+                let overlap = fragment.iter().any(|&i| placed.iter().any(|&j| self._r_ij(i, j) < self.r_core));
+                if !overlap { break }
+                target += 0.1;
+            }
+            if target >= d {
+                for (&i, p) in fragment.iter().zip(old) {
+                    self.positions[i] = p;
+                }
+            }
+            placed.extend(fragment.iter().copied());
+        }
+    }
+}
+
+/// whether two bond graphs are isomorphic; colour refinement for a quick rejection and
+/// for pruning, then backtracking over vertices in BFS order of a
+pub fn isomorphic(a: &[Vec<usize>], b: &[Vec<usize>]) -> bool {
+    let n = a.len();
+    if b.len() != n { return false }
+    if n == 0 { return true }
+
+    let (col_a, col_b) = refine_colours(a, b);
+    let mut hist_a = col_a.clone();
+    let mut hist_b = col_b.clone();
+    hist_a.sort_unstable();
+    hist_b.sort_unstable();
+    if hist_a != hist_b { return false }
+
+    // BFS order of every component, so most vertices have an already mapped neighbour
+    let mut order = Vec::with_capacity(n);
+    let mut seen = vec![false; n];
+    for root in 0..n {
+        if seen[root] { continue }
+        seen[root] = true;
+        let mut head = order.len();
+        order.push(root);
+        while head < order.len() {
+            for &w in &a[order[head]] {
+                if !seen[w] { seen[w] = true; order.push(w); }
+            }
+            head += 1;
+        }
+    }
+
+    let adj_b: Vec<Vec<bool>> = b.iter().map(|nb| (0..n).map(|w| nb.contains(&w)).collect()).collect();
+    let mut map = vec![usize::MAX; n];
+    let mut used = vec![false; n];
+    _extend(0, &order, a, &adj_b, &col_a, &col_b, &mut map, &mut used)
+}
+
+// tries to map order[k..] given the mapping of order[..k]
+#[allow(clippy::too_many_arguments)]
+fn _extend(k: usize, order: &[usize], a: &[Vec<usize>], adj_b: &[Vec<bool>], col_a: &[usize], col_b: &[usize],
+           map: &mut [usize], used: &mut [bool]) -> bool {
+    if k == order.len() { return true }
+    let v = order[k];
+
+    for w in 0..adj_b.len() {
+        if used[w] || col_b[w] != col_a[v] { continue }
+        // edges to the mapped vertices must match; degrees are equal by the colours
+        let consistent = order[..k].iter().all(|&u| a[v].contains(&u) == adj_b[w][map[u]]);
+        if !consistent { continue }
+
+        map[v] = w;
+        used[w] = true;
+        if _extend(k + 1, order, a, adj_b, col_a, col_b, map, used) { return true }
+        used[w] = false;
+    }
+    map[v] = usize::MAX;
+    false
+}
+
+// 1-WL colour refinement of both graphs together, so the colours are comparable
+fn refine_colours(a: &[Vec<usize>], b: &[Vec<usize>]) -> (Vec<usize>, Vec<usize>) {
+    let n = a.len();
+    let adj: Vec<Vec<usize>> = a.iter().cloned()
+                                .chain(b.iter().map(|nb| nb.iter().map(|&w| w + n).collect()))
+                                .collect();
+    let mut colours = vec![0; 2*n];
+    let mut n_colours = 1;
+
+    loop {
+        let signatures: Vec<(usize, Vec<usize>)> = adj.iter().enumerate()
+            .map(|(v, nb)| {
+                let mut s: Vec<usize> = nb.iter().map(|&w| colours[w]).collect();
+                s.sort_unstable();
+                (colours[v], s)
+            })
+            .collect();
+        let mut distinct = signatures.clone();
+        distinct.sort();
+        distinct.dedup();
+        colours = signatures.iter().map(|s| distinct.binary_search(s).unwrap()).collect();
+
+        if distinct.len() == n_colours { break }
+        n_colours = distinct.len();
+    }
+    let col_b = colours.split_off(n);
+    (colours, col_b)
+}
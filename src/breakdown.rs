@@ -0,0 +1,73 @@
+use crate::Fuleren;
+
+/// contributions to the total energy; total = repulsive + attractive + dispersion + torsion + coulomb
+#[derive(Debug, Clone, Default)]
+pub struct EnergyBreakdown {
+    pub total: f64,
+    pub repulsive: f64,          // sum of f_c V_R
+    pub attractive: f64,         // bond order weighted attraction, -sum of f_c b_ij V_A
+    pub angular: f64,            // attraction removed by the bond order, sum of f_c (1 - b_ij) V_A
+    pub cutoff: f64,             // part of the total coming from pairs with R1 < r <= R2
+    pub dispersion: f64,         // LJ term beyond R2, if switched on
+    pub torsion: f64,            // dihedral term, if switched on
+    pub coulomb: f64,            // electrostatics of the partial charges, if switched on
+    pub forbidden_angles: usize, // (i, j, k) triplets with cos > 0, which get the experimental g = 20
+}
+
+impl Fuleren {
+    /// energy split into its components; also sets E
+    pub fn energy_calc_detailed(&mut self) -> EnergyBreakdown {
+        let mut eb = EnergyBreakdown::default();
+        let brenner = &self.potential.brenner;
+
+        for i in 0..self.size {
+            for j in (0..self.size).filter(|&j| j != i) {
+                let r_ij = self._r_ij(i, j);
+                if r_ij > brenner.r2 { continue }
+
+                let fc = brenner.cutoff(r_ij);
+                let b = 0.5*(self._b_ij::<f64>(i, j) + self._b_ij::<f64>(j, i));
+                let (vr, va) = (self._repulsive(r_ij), self._attractive(r_ij));
+
+                // every pair is visited twice
+                eb.repulsive += 0.5*fc*vr;
+                eb.attractive -= 0.5*fc*b*va;
+                eb.angular += 0.5*fc*(1. - b)*va;
+                if r_ij > brenner.r1 {
+                    eb.cutoff += 0.5*fc*(vr - b*va);
+                }
+                eb.forbidden_angles += self._forbidden_angles(i, j);
+            }
+        }
+        eb.dispersion = self.dispersion_energy();
+        eb.torsion = self.torsion_energy();
+        eb.coulomb = self.coulomb_energy();
+        eb.total = eb.repulsive + eb.attractive + eb.dispersion + eb.torsion + eb.coulomb;
+        self.E = eb.total;
+        eb
+    }
+
+    // neighbours k of i making a cos(ijk) > 0 angle with the i-j bond
+    fn _forbidden_angles(&self, i: usize, j: usize) -> usize {
+        (0..self.size).filter(|&k| k != i && k != j && self._r_ij(i, k) <= self.potential.brenner.r2)
+                      .filter(|&k| {
+                          let (pi, pj, pk) = (&self.positions[i], &self.positions[j], &self.positions[k]);
+                          (pj.x() - pi.x())*(pk.x() - pi.x()) + (pj.y() - pi.y())*(pk.y() - pi.y()) + (pj.z() - pi.z())*(pk.z() - pi.z()) > 0.
+                      })
+                      .count()
+    }
+}
+
+impl std::fmt::Display for EnergyBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<18} {:>14.5}", "total", self.total)?;
+        writeln!(f, "{:<18} {:>14.5}", "repulsive", self.repulsive)?;
+        writeln!(f, "{:<18} {:>14.5}", "attractive", self.attractive)?;
+        writeln!(f, "{:<18} {:>14.5}", "angular penalty", self.angular)?;
+        writeln!(f, "{:<18} {:>14.5}", "cutoff region", self.cutoff)?;
+        writeln!(f, "{:<18} {:>14.5}", "dispersion", self.dispersion)?;
+        writeln!(f, "{:<18} {:>14.5}", "torsion", self.torsion)?;
+        writeln!(f, "{:<18} {:>14.5}", "coulomb", self.coulomb)?;
+        write!(f, "{:<18} {:>14}", "cos>0 triplets", self.forbidden_angles)
+    }
+}
@@ -0,0 +1,183 @@
+//! starting structures with a cage topology: a random face spiral (12 pentagons among hexagons)
+//! is wound up into the dual triangulation of a fullerene by the spiral algorithm of Fowler and
+//! Manolopoulos, the triangles of the dual are the atoms, and the cage is embedded on a sphere by
+//! a spring relaxation. Unlike random points on a sphere, these seeds start as one connected,
+//! trivalent cage
+use std::collections::{HashMap, VecDeque};
+use std::f64::consts::PI;
+
+use rand::seq::index;
+use tracing::debug;
+
+use crate::{rng, Fuleren, Point6, R1};
+
+// C-C bond length of the embedded cage [A]
+const BOND: f64 = 1.42;
+// random spirals tried before giving up (most do not close)
+const MAX_SPIRALS: usize = 1_000_000;
+// spring relaxation of the embedding
+const TOPOLOGICAL_STEPS: usize = 2000;
+const EMBED_STEPS: usize = 1000;
+const EMBED_DT: f64 = 0.05;
+
+impl Fuleren {
+    /// random fullerene cage of n atoms (even n, 20 or at least 24) embedded on a sphere, bonds
+    /// about 1.42 A long
+    pub fn spiral_cage(n: usize) -> Result<Fuleren, String> {
+        if n % 2 == 1 || n < 20 || n == 22 {
+            return Err(format!("there is no fullerene cage of {} atoms", n));
+        }
+        let n_faces = n/2 + 2;
+        let mut rng = rng();
+
+        for attempt in 0..MAX_SPIRALS {
+            let mut spiral = vec![6; n_faces];
+            for p in index::sample(&mut rng, n_faces, 12) {
+                spiral[p] = 5;
+            }
+            let Ok(dual) = windup(&spiral) else { continue };
+            let Some(bonds) = cage_graph(&dual, n) else { continue };
+            let mut f = Fuleren::new(n);
+            for (p, x) in f.positions.iter_mut().zip(embed(&bonds)) {
+                *p = Point6::from_cartesian(&x);
+            }
+            // an embedding folded over itself leaves bonds stretched beyond R1, try another cage
+            if (0..n).any(|i| bonds[i].iter().any(|&j| f._r_ij(i, j) > R1)) {
+                debug!(attempt, "cage embedding folded");
+                continue
+            }
+            debug!(attempt, ?spiral, "spiral wound up");
+            return Ok(f);
+        }
+        Err(format!("no closing spiral of {} atoms in {} attempts", n, MAX_SPIRALS))
+    }
+}
+
+// dual adjacency of the face spiral (face degrees in spiral order), or the face at which it fails
+// to close; the failure depends only on the faces up to that one.
+// Every face is joined to both ends of the open boundary of the faces placed so far; a boundary
+// face whose neighbours are complete is closed and its successor joined to the new face
+pub(crate) fn windup(spiral: &[usize]) -> Result<Vec<Vec<usize>>, usize> {
+    let n_faces = spiral.len();
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::with_capacity(6); n_faces];
+    let complete = |adjacency: &[Vec<usize>], a: usize| adjacency[a].len() == spiral[a];
+    let connect = |a: usize, b: usize, adjacency: &mut Vec<Vec<usize>>| -> Option<()> {
+        if complete(adjacency, a) || complete(adjacency, b) || adjacency[a].contains(&b) {
+            return None
+        }
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+        Some(())
+    };
+
+    connect(0, 1, &mut adjacency).ok_or(1_usize)?;
+    let mut boundary = VecDeque::from([0, 1]);
+    for k in 2..n_faces - 1 {
+        let place = |adjacency: &mut Vec<Vec<usize>>, boundary: &mut VecDeque<usize>| -> Option<()> {
+            connect(k, *boundary.back()?, adjacency)?;
+            connect(k, *boundary.front()?, adjacency)?;
+            while complete(adjacency, *boundary.front()?) {
+                boundary.pop_front();
+                connect(k, *boundary.front()?, adjacency)?;
+            }
+            while complete(adjacency, *boundary.back()?) {
+                boundary.pop_back();
+                connect(k, *boundary.back()?, adjacency)?;
+            }
+            (!complete(adjacency, k)).then_some(())
+        };
+        place(&mut adjacency, &mut boundary).ok_or(k)?;
+        boundary.push_back(k);
+    }
+
+    // the last face closes the boundary
+    for &b in &boundary {
+        connect(n_faces - 1, b, &mut adjacency).ok_or(n_faces - 1)?;
+    }
+    (0..n_faces).all(|a| complete(&adjacency, a)).then_some(adjacency).ok_or(n_faces - 1)
+}
+
+// bonds of the cage: the atoms are the triangles of the dual, bonded if they share an edge; None
+// unless there are n atoms of three bonds each
+fn cage_graph(dual: &[Vec<usize>], n: usize) -> Option<Vec<Vec<usize>>> {
+    let mut triangles = Vec::with_capacity(n);
+    for (a, na) in dual.iter().enumerate() {
+        for &b in na.iter().filter(|&&b| b > a) {
+            for &c in dual[b].iter().filter(|&&c| c > b && na.contains(&c)) {
+                triangles.push([a, b, c]);
+            }
+        }
+    }
+    if triangles.len() != n {
+        return None
+    }
+
+    let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (t, &[a, b, c]) in triangles.iter().enumerate() {
+        for edge in [(a, b), (a, c), (b, c)] {
+            edges.entry(edge).or_default().push(t);
+        }
+    }
+    let mut bonds = vec![Vec::with_capacity(3); n];
+    for pair in edges.values() {
+        let &[s, t] = pair.as_slice() else { return None };
+        bonds[s].push(t);
+        bonds[t].push(s);
+    }
+    bonds.iter().all(|b| b.len() == 3).then_some(bonds)
+}
+
+// positions of the atoms: topological coordinates (the eigenvectors of the adjacency matrix to the
+// second to fourth largest eigenvalues, found by subspace iteration) projected on the unit sphere,
+// then springs along the bonds and a short range repulsion of the other pairs are relaxed on the
+// sphere, which is finally scaled to BOND
+fn embed(bonds: &[Vec<usize>]) -> Vec<[f64; 3]> {
+    let n = bonds.len();
+    let mut x: Vec<[f64; 3]> = (0..n).map(|i| [0, 1, 2].map(|c| ((i*(c + 2)) as f64).sin())).collect();
+    for _ in 0..TOPOLOGICAL_STEPS {
+        // A + 3 shifts the spectrum to positive values, the constant vector (eigenvalue 3) is
+        // projected out
+        x = (0..n).map(|i| [0, 1, 2].map(|c| 3.*x[i][c] + bonds[i].iter().map(|&j| x[j][c]).sum::<f64>())).collect();
+        for c in 0..3 {
+            let mean = x.iter().map(|p| p[c]).sum::<f64>()/n as f64;
+            x.iter_mut().for_each(|p| p[c] -= mean);
+            for prev in 0..c {
+                let dot = x.iter().map(|p| p[c]*p[prev]).sum::<f64>();
+                x.iter_mut().for_each(|p| p[c] -= dot*p[prev]);
+            }
+            let norm = x.iter().map(|p| p[c]*p[c]).sum::<f64>().sqrt();
+            x.iter_mut().for_each(|p| p[c] /= norm);
+        }
+    }
+    let mut x: Vec<[f64; 3]> = x.into_iter().map(normalized).collect();
+
+    // bond length of a hexagonal lattice covering the unit sphere; atoms closer than the second
+    // neighbour distance of that lattice are pushed apart
+    let l0 = (16.*PI/(3.*3f64.sqrt()*n as f64)).sqrt();
+    let r_min = 3f64.sqrt()*l0;
+    for _ in 0..EMBED_STEPS {
+        let forces: Vec<[f64; 3]> = (0..n).map(|i| {
+            let mut force = [0.; 3];
+            for j in (0..n).filter(|&j| j != i) {
+                let d = [0, 1, 2].map(|c| x[j][c] - x[i][c]);
+                let r = d.iter().map(|v| v*v).sum::<f64>().sqrt().max(1e-9);
+                let stretch = if bonds[i].contains(&j) { r - l0 } else { (r - r_min).min(0.) };
+                (0..3).for_each(|c| force[c] += stretch*d[c]/r);
+            }
+            force
+        }).collect();
+        for (p, force) in x.iter_mut().zip(forces) {
+            *p = normalized([0, 1, 2].map(|c| p[c] + EMBED_DT*force[c]));
+        }
+    }
+
+    let bond_length = (0..n).flat_map(|i| bonds[i].iter().map(move |&j| (i, j)))
+                            .map(|(i, j)| (0..3).map(|c| (x[i][c] - x[j][c]).powi(2)).sum::<f64>().sqrt())
+                            .sum::<f64>()/(3*n) as f64;
+    x.iter().map(|p| p.map(|v| v*BOND/bond_length)).collect()
+}
+
+fn normalized(v: [f64; 3]) -> [f64; 3] {
+    let norm = (v[0]*v[0] + v[1]*v[1] + v[2]*v[2]).sqrt();
+    v.map(|c| c/norm)
+}
@@ -0,0 +1,96 @@
+//! beta_max from a short pre-run: the energy changes of proposed atom moves of a cooled copy of
+//! the structure give the acceptance as a function of beta, and beta_max is chosen so the late
+//! stage acceptance lands in a target band instead of being guessed
+use rand::Rng;
+
+use crate::anneal::Schedule;
+use crate::{rng, Fuleren};
+
+/// target band of the atom move acceptance at beta_max and length of the pre-run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub low: f64,
+    pub high: f64,
+    pub it: usize, // sweeps of the pre-run
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration { low: 0.05, high: 0.15, it: 2000 }
+    }
+}
+
+// refinements of the estimate; every pass cools the copy at the previous estimate
+const PASSES: usize = 3;
+// proposed moves per atom sampled after every pass
+const SAMPLES_PER_ATOM: usize = 20;
+// upper limit of the bisection
+const BETA_CAP: f64 = 1e5;
+
+impl Fuleren {
+    /// beta_max with an atom move acceptance in the middle of the calibration band; the
+    /// structure itself is left untouched, the pre-run uses a copy and the thread's generator
+    pub fn calibrate_beta_max(&self, schedule: &Schedule, calibration: &Calibration) -> f64 {
+        let target = 0.5*(calibration.low + calibration.high);
+        let mut work = self.clone();
+        // the late stage the band is meant for runs without the shell restraint
+        work.potential.shell = None;
+        work.energy_calc();
+        let mut beta = schedule.beta_min;
+
+        for pass in 0..PASSES {
+            for _ in 0..calibration.it/PASSES {
+                work.sweep(schedule, beta);
+            }
+            work.energy_calc();
+            let de = work.proposed_energy_changes(SAMPLES_PER_ATOM*work.size);
+            beta = solve_acceptance(&de, target, schedule.beta_min);
+            tracing::debug!(pass, beta, acceptance = acceptance(&de, beta), "beta_max calibration pass");
+        }
+
+        let de = work.proposed_energy_changes(SAMPLES_PER_ATOM*work.size);
+        let reached = acceptance(&de, beta);
+        if reached < calibration.low || reached > calibration.high {
+            tracing::warn!(beta, acceptance = reached, low = calibration.low, high = calibration.high,
+                           "calibrated beta_max misses the target acceptance band");
+        }
+        tracing::info!(beta_max = beta, T_end = %crate::units::Beta(beta).temperature(), acceptance = reached,
+                       "beta_max calibrated");
+        beta
+    }
+
+    // energy changes of n random atom moves of the anneal proposal, none of them applied;
+    // moves into the hard core are infinite
+    fn proposed_energy_changes(&mut self, n: usize) -> Vec<f64> {
+        let mut rng = rng();
+        (0..n).map(|_| {
+            let i = rng.gen_range(0..self.size);
+            let old = self.positions[i].clone();
+            let new = self._propose_shift(i);
+            if self._overlap_with(i, &new).is_some() {
+                return f64::INFINITY
+            }
+            let de = self._local_shift(i, new);
+            self.positions[i] = old;
+            de
+        }).collect()
+    }
+}
+
+// Metropolis acceptance of the sampled moves at beta
+fn acceptance(de: &[f64], beta: f64) -> f64 {
+    de.iter().map(|&d| (-beta*d).exp().min(1.)).sum::<f64>()/de.len().max(1) as f64
+}
+
+// beta in [beta_min, BETA_CAP] with the acceptance closest to target, by bisection in log beta
+// (the acceptance decreases with beta)
+fn solve_acceptance(de: &[f64], target: f64, beta_min: f64) -> f64 {
+    let (mut lo, mut hi) = (beta_min.max(f64::MIN_POSITIVE).ln(), BETA_CAP.ln());
+    if acceptance(de, lo.exp()) <= target { return lo.exp() }
+    if acceptance(de, hi.exp()) >= target { return hi.exp() }
+    for _ in 0..60 {
+        let mid = 0.5*(lo + hi);
+        if acceptance(de, mid.exp()) > target { lo = mid } else { hi = mid }
+    }
+    (0.5*(lo + hi)).exp()
+}
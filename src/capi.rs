@@ -0,0 +1,119 @@
+//! C ABI for using the simulation from C/Fortran codes; the header is generated into
+//! include/fuleren.h by the build script. Structures are opaque `Fuleren *` handles owned by
+//! the caller, created by fuleren_new/fuleren_from_file and released by fuleren_free
+
+use std::ffi::{c_char, CStr};
+use std::io;
+
+use crate::anneal::{Schedule, ANNEAL_COLUMNS};
+use crate::observables::ObservableWriter;
+use crate::{seed_rng, Fuleren};
+
+/// n atoms at random on a sphere of radius r_start
+#[no_mangle]
+pub extern "C" fn fuleren_new(n: usize, r_start: f64) -> *mut Fuleren {
+    let mut f = Fuleren::new(n);
+    f.randomize_on_sphere(r_start);
+    f.energy_calc();
+    Box::into_raw(Box::new(f))
+}
+
+/// first structure of an "x y z" file, NULL if it cannot be read
+///
+/// # Safety
+/// path must be a valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_from_file(path: *const c_char) -> *mut Fuleren {
+    let Ok(path) = CStr::from_ptr(path).to_str() else { return std::ptr::null_mut() };
+    match Fuleren::frames_from_file(path).ok().and_then(|frames| frames.into_iter().next()) {
+        Some(mut f) => {
+            f.energy_calc();
+            Box::into_raw(Box::new(f))
+        }
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// f must come from fuleren_new/fuleren_from_file (or be NULL) and is invalid afterwards
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_free(f: *mut Fuleren) {
+    if !f.is_null() {
+        drop(Box::from_raw(f));
+    }
+}
+
+/// # Safety
+/// f must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_size(f: *const Fuleren) -> usize {
+    (*f).size
+}
+
+/// total energy [eV]
+///
+/// # Safety
+/// f must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_energy(f: *mut Fuleren) -> f64 {
+    (*f).energy_calc()
+}
+
+/// power law annealing from beta_min to beta_max in it_max iterations;
+/// returns the number of iterations done
+///
+/// # Safety
+/// f must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_anneal(f: *mut Fuleren, beta_min: f64, beta_max: f64, p: f64, it_max: usize) -> usize {
+    let f = &mut *f;
+    let schedule = Schedule { beta_min, beta_max, p, it_max, ..Default::default() };
+    let mut log = ObservableWriter::new(io::sink(), &ANNEAL_COLUMNS).expect("Error during saving");
+    let it = f.anneal(&schedule, &mut log, usize::MAX).expect("Error during saving");
+    f.energy_calc();
+    it
+}
+
+/// FIRE relaxation to the nearest minimum, returns the number of steps
+///
+/// # Safety
+/// f must be a valid handle
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_relax(f: *mut Fuleren, f_tol: f64, max_steps: usize) -> usize {
+    let f = &mut *f;
+    let steps = f.relax(f_tol, max_steps);
+    f.energy_calc();
+    steps
+}
+
+/// copies the cartesian positions into xyz as x0 y0 z0 x1 ... (3*size doubles)
+///
+/// # Safety
+/// f must be a valid handle, xyz must have room for 3*size doubles
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_positions(f: *const Fuleren, xyz: *mut f64) {
+    let f = &*f;
+    let out = std::slice::from_raw_parts_mut(xyz, 3*f.size);
+    for (i, p) in f.positions.iter().enumerate() {
+        out[3*i..3*i + 3].copy_from_slice(&[p.x(), p.y(), p.z()]);
+    }
+}
+
+/// sets the positions from xyz laid out as in fuleren_positions
+///
+/// # Safety
+/// f must be a valid handle, xyz must hold 3*size doubles
+#[no_mangle]
+pub unsafe extern "C" fn fuleren_set_positions(f: *mut Fuleren, xyz: *const f64) {
+    let f = &mut *f;
+    let xyz = std::slice::from_raw_parts(xyz, 3*f.size);
+    let xyz = ndarray::ArrayView2::from_shape((f.size, 3), xyz).expect("wrong number of coordinates");
+    f.set_xyz_array(&xyz.to_owned());
+    f.energy_calc();
+}
+
+/// seeds the random generator of the calling thread
+#[no_mangle]
+pub extern "C" fn fuleren_seed(seed: u64) {
+    seed_rng(seed);
+}
@@ -0,0 +1,68 @@
+//! trajectories in the formats of the chemfiles library (DCD, TRR, XTC, PDB, CIF, ...), the
+//! format is chosen by chemfiles from the file extension. Positions and elements are read, atoms
+//! of an unknown element (or without one, as in DCD) are taken as carbon
+use std::path::Path;
+
+use chemfiles::{Atom, Frame, Trajectory};
+
+use crate::potential::Potential;
+use crate::{Element, Fuleren, Point6, StepSizes, R_CORE};
+
+/// extensions handled by the bespoke readers, everything else goes through chemfiles
+pub const NATIVE_EXTENSIONS: [&str; 4] = ["xyz", "extxyz", "dat", "txt"];
+
+pub fn uses_chemfiles(path: &str) -> bool {
+    Path::new(path).extension()
+                   .and_then(|e| e.to_str())
+                   .map_or(false, |e| !NATIVE_EXTENSIONS.contains(&e))
+}
+
+/// all frames of a trajectory
+pub fn read_frames(path: &str) -> Result<Vec<Fuleren>, String> {
+    let err = |e: chemfiles::Error| format!("cannot read {}: {}", path, e);
+    let mut trajectory = Trajectory::open(path, 'r').map_err(err)?;
+    let n_steps = trajectory.nsteps();
+
+    let mut frames = Vec::with_capacity(n_steps);
+    let mut frame = Frame::new();
+    for _ in 0..n_steps {
+        trajectory.read(&mut frame).map_err(err)?;
+        let positions: crate::Point6Array = frame.positions().iter().map(Point6::from_cartesian).collect();
+        let species = (0..frame.size()).map(|i| Element::from_symbol(&frame.atom(i).atomic_type()).unwrap_or_default())
+                                       .collect();
+        frames.push(Fuleren { size: positions.len(), species, E: 0., r_core: R_CORE,
+                              steps: StepSizes::default(), potential: Potential::default(), positions });
+    }
+    Ok(frames)
+}
+
+impl Fuleren {
+    /// saves the structure as a single frame in the format given by the extension of path
+    pub fn save_chemfiles(&self, path: &str) -> Result<(), String> {
+        ChemfilesWriter::create(path)?.write(self)
+    }
+}
+
+/// appends frames to a trajectory in the format given by the extension of its path
+pub struct ChemfilesWriter {
+    path: String,
+    trajectory: Trajectory,
+}
+
+impl ChemfilesWriter {
+    pub fn create(path: &str) -> Result<ChemfilesWriter, String> {
+        if let Some(dir) = Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+        }
+        let trajectory = Trajectory::open(path, 'w').map_err(|e| format!("cannot write {}: {}", path, e))?;
+        Ok(ChemfilesWriter { path: path.to_string(), trajectory })
+    }
+
+    pub fn write(&mut self, f: &Fuleren) -> Result<(), String> {
+        let mut frame = Frame::new();
+        for (p, element) in f.positions.iter().zip(&f.species) {
+            frame.add_atom(&Atom::new(element.symbol()), [p.x(), p.y(), p.z()], None);
+        }
+        self.trajectory.write(&frame).map_err(|e| format!("cannot write {}: {}", self.path, e))
+    }
+}
@@ -0,0 +1,1069 @@
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use ndarray::Array1;
+use tracing::{error, info, warn};
+
+use crate::anneal::{anneal_columns, Cadence, Every, Schedule};
+use crate::archive::Archive;
+use crate::cv::CollectiveVariable;
+#[cfg(feature = "rusqlite")]
+use crate::database::{Query, ResultStore};
+use crate::lammps::LammpsCheck;
+use crate::meta_anneal::ScheduleSearch;
+use crate::metadata::Metadata;
+use crate::minima_hopping::MinimaHopping;
+use crate::neb::Neb;
+use crate::observables::ObservableWriter;
+use crate::potential::Potential;
+use crate::quench::QuenchStudy;
+use crate::run_dir::RunDirectory;
+use crate::selection::Selection;
+use crate::traj::{self, FrameSink, Slice};
+use crate::runner::{self, ParallelRunner, ProcessGroup};
+use crate::sensitivity::{parse_parameter, Sampling, SensitivitySweep};
+use crate::snapshots::{Snapshots, Trigger};
+use crate::umbrella::{self, UmbrellaSampling};
+use crate::units::{Beta, Temperature};
+use crate::utilities::save_xy;
+use crate::movie::FrameWriter;
+use crate::{analysis, autocorrelation, compare, cv, explain, logging, movie, relax, report, reweighting, ring_strain, seed_rng, vibrations, Fuleren};
+
+#[derive(Parser)]
+#[command(about = "Fullerene structures from simulated annealing with the Brenner potential")]
+pub struct Cli {
+    /// seed of the random generator (sweep: seed of the first run)
+    #[arg(long, global = true)]
+    pub seed: Option<u64>,
+    /// base directory; every run writes into its own timestamped subdirectory
+    #[arg(long, global = true, default_value = "plots")]
+    pub out_dir: PathBuf,
+    /// fixed name of the run subdirectory instead of the timestamp (required by multi-process sweeps)
+    #[arg(long, global = true)]
+    pub name: Option<String>,
+    /// worker threads of the sweep, all cores by default
+    #[arg(long, global = true)]
+    pub threads: Option<usize>,
+    /// config file, "key value" lines of the schedule and the potential (see Schedule::from_config
+    /// and Potential::from_config)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// iterations between lines of the anneal logs
+    #[arg(long, global = true, default_value_t = 100)]
+    pub log_step: usize,
+    /// log a line at the start of each of this many windows of equal width in T instead of every
+    /// log_step iterations
+    #[arg(long, global = true)]
+    pub log_windows: Option<usize>,
+    /// first cooling iterations neither logged nor recorded (equilibration iterations never are)
+    #[arg(long, global = true, default_value_t = 0)]
+    pub burn_in: usize,
+    /// iterations between frames of the movie, by default a frame at every log line
+    #[arg(long, global = true)]
+    pub frame_step: Option<usize>,
+    /// a movie frame at the start of each of this many windows of equal width in T instead
+    #[arg(long, global = true, conflicts_with = "frame_step")]
+    pub frame_windows: Option<usize>,
+    /// save structures as extended XYZ (ASE) with per-atom energy and coordination
+    #[arg(long, global = true)]
+    pub extxyz: bool,
+    /// save structures through chemfiles in the format of this extension (pdb, cif, ...)
+    #[cfg(feature = "chemfiles")]
+    #[arg(long, global = true)]
+    pub format: Option<String>,
+    /// comma separated collective variables logged after the standard anneal columns (run, sweep):
+    /// radius, asphericity, volume, area, sphericity, t_conf, pentagons, hexagons, coord<c>, ring<k>, q<l>, w<l>
+    /// or rmsd:<reference> (RMSD to a reference isomer like C60 or a structure file, also in the movie frames)
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub observe: Vec<String>,
+    /// record the anneal (run) as trajectory.extxyz, a frame every frame_step iterations, and write
+    /// movie.py, an OVITO script rendering it into movie.gif
+    #[arg(long, global = true)]
+    pub movie: bool,
+    /// snapshot trigger (run, repeatable): "<observable><op><threshold>" on E, T, r_mean or a
+    /// collective variable of observe, e.g. "pentagons>=12" or "E<-400"; the structure is appended to
+    /// snapshots.extxyz every time the condition becomes true
+    #[arg(long, global = true)]
+    pub snapshot: Vec<String>,
+    /// directory of the per-N archives of the lowest-energy distinct structures across runs
+    /// (archive_N<n>.extxyz), updated with the final structure of every run (run, sweep, sensitivity, quench)
+    #[arg(long, global = true)]
+    pub archive: Option<PathBuf>,
+    /// structures kept per N in the archive
+    #[arg(long, global = true, default_value_t = 10)]
+    pub archive_size: usize,
+    /// RMSD below which structures with isomorphic bond graphs are the same in the archive [A]
+    #[arg(long, global = true, default_value_t = 0.1)]
+    pub archive_rmsd: f64,
+    /// SQLite results database: the final structure of every run (run, sweep, sensitivity, quench) is
+    /// inserted with its energy, seed, config and isomer; query it with the db subcommand
+    #[cfg(feature = "rusqlite")]
+    #[arg(long, global = true)]
+    pub db: Option<PathBuf>,
+    /// full energy evaluations of the anneals on the GPU (as gpu on in the config), and the energy
+    /// subcommand also on the GPU
+    #[cfg(feature = "gpu")]
+    #[arg(long, global = true)]
+    pub gpu: bool,
+    /// per-function time breakdown printed at the end of the run
+    #[arg(long, global = true)]
+    pub profile: bool,
+    /// teaching output: the first EXPLAIN uniform atom moves (20 without a number) printed step by
+    /// step with the proposal, dE, the acceptance probability and the decision (run)
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "20")]
+    pub explain: Option<usize>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// anneal a single structure of n atoms
+    Run {
+        #[arg(short, long, default_value_t = 60)]
+        n: usize,
+        /// radius of the random starting sphere
+        #[arg(long, default_value_t = 2.5)]
+        r_start: f64,
+        /// start from a random fullerene cage (spiral_cage) instead of random points
+        #[arg(long)]
+        cage: bool,
+    },
+    /// anneal every n in n_min..=n_max, `runs` seeds each, in parallel; writes summary.dat, the
+    /// mean E/N with its standard error per n as en_curve.dat, plotted by en_curve.gp, and the
+    /// fullerene isomers reached per n as isomers.dat
+    Sweep {
+        #[arg(long, default_value_t = 30)]
+        n_min: usize,
+        #[arg(long, default_value_t = 60)]
+        n_max: usize,
+        #[arg(long, default_value_t = 1)]
+        runs: u64,
+        /// multi-process sweeps: how long rank 0 waits for the partial summaries of the other ranks [s]
+        #[arg(long, default_value_t = 24*3600)]
+        gather_timeout: u64,
+        /// rank the closed cage isomers of every n in isomers.dat by harmonic free energy at this
+        /// temperature [K], from the lowest-energy run of each, relaxed (a hessian per isomer)
+        #[arg(long)]
+        free_energy: Option<f64>,
+    },
+    /// short anneals over a grid or Latin hypercube of one or two config parameters on top of
+    /// --config; writes sensitivity.dat with E/N and defect counts per point
+    Sensitivity {
+        #[arg(short, long, default_value_t = 60)]
+        n: usize,
+        /// scanned config key and range as key:min:max, e.g. beta_max:5:20 (one or two)
+        #[arg(long = "param", required = true, num_args = 1..=2, value_parser = parse_parameter)]
+        parameters: Vec<(String, f64, f64)>,
+        /// values per parameter of a grid
+        #[arg(long, default_value_t = 5, conflicts_with = "lhs")]
+        grid: usize,
+        /// number of Latin hypercube points instead of a grid
+        #[arg(long)]
+        lhs: Option<usize>,
+        /// seeds per point
+        #[arg(long, default_value_t = 3)]
+        runs: u64,
+        /// iterations of the anneals, overrides the config
+        #[arg(long)]
+        it_max: Option<usize>,
+    },
+    /// searches beta_min, beta_max, p and the equilibration share of the iterations by successive
+    /// halving over short pilot anneals of random starts of n atoms with the configured potential;
+    /// prints the best schedule and saves it as the config of the run directory
+    Tune {
+        #[arg(short, long, default_value_t = 60)]
+        n: usize,
+        /// radius of the random starting spheres
+        #[arg(long, default_value_t = 2.5)]
+        r_start: f64,
+        /// schedules sampled in the first round
+        #[arg(long, default_value_t = 27)]
+        candidates: usize,
+        /// iterations of a pilot anneal in the first round
+        #[arg(long, default_value_t = 1000)]
+        budget: usize,
+        /// 1/eta of the candidates survive a round, the budget grows eta times
+        #[arg(long, default_value_t = 3)]
+        eta: usize,
+        /// pilot anneals per candidate, scored by the mean final energy
+        #[arg(long, default_value_t = 2)]
+        repeats: usize,
+    },
+    /// the same starting structure annealed with every combination of it_max and p on top of
+    /// --config, `runs` seeds each; writes quench.dat with E/N and defect counts per cooling rate
+    Quench {
+        /// starting structure file or the name of a reference structure (C20, C24, C36, C60, C70),
+        /// a random one of n atoms (generated once with --seed) if not given
+        file: Option<PathBuf>,
+        #[arg(short, long, default_value_t = 60)]
+        n: usize,
+        /// radius of the random starting sphere
+        #[arg(long, default_value_t = 2.5)]
+        r_start: f64,
+        /// start from a random fullerene cage (spiral_cage) instead of random points
+        #[arg(long)]
+        cage: bool,
+        /// comma separated iteration counts of the anneals
+        #[arg(long, value_delimiter = ',', default_value = "10000,30000,100000,300000")]
+        it_max: Vec<usize>,
+        /// comma separated exponents of the power law schedule
+        #[arg(long, value_delimiter = ',', default_value = "2")]
+        p: Vec<f64>,
+        /// seeds per cooling rate
+        #[arg(long, default_value_t = 3)]
+        runs: u64,
+    },
+    /// minima hopping from a structure: MD escapes from the current minimum and relaxations, the MD
+    /// temperature and the acceptance threshold adapted to the minima visited; writes the lowest
+    /// minimum as best.xyz and the energies of the distinct minima in the order found as minima.dat
+    Hop {
+        /// starting structure file or the name of a reference structure (C20, C24, C36, C60, C70),
+        /// a random one of n atoms if not given
+        file: Option<PathBuf>,
+        #[arg(short, long, default_value_t = 60)]
+        n: usize,
+        /// radius of the random starting sphere
+        #[arg(long, default_value_t = 2.5)]
+        r_start: f64,
+        /// start from a random fullerene cage (spiral_cage) instead of random points
+        #[arg(long)]
+        cage: bool,
+        #[arg(long, default_value_t = 50)]
+        hops: usize,
+        /// initial MD kinetic temperature [eV]
+        #[arg(long, default_value_t = 0.1)]
+        kt: f64,
+        /// initial acceptance threshold of a new minimum [eV]
+        #[arg(long, default_value_t = 0.5)]
+        e_diff: f64,
+    },
+    /// umbrella sampling along a reaction coordinate of a structure, windows evenly spaced on
+    /// [from, to]; writes the samples of every window and the WHAM free energy profile
+    Umbrella {
+        /// starting structure file or the name of a reference structure (C20, C24, C36, C60, C70)
+        file: PathBuf,
+        /// collective variable: radius, asphericity, volume, area, sphericity, pentagons, coord<c>,
+        /// ring<k>, q<l> or w<l>
+        #[arg(long, default_value = "radius")]
+        coordinate: String,
+        #[arg(long)]
+        from: f64,
+        #[arg(long)]
+        to: f64,
+        #[arg(long, default_value_t = 10)]
+        windows: usize,
+        /// force constant of the windows [eV per squared unit of the coordinate]
+        #[arg(long, default_value_t = 10.)]
+        k: f64,
+        /// temperature of the sampling [K]
+        #[arg(long, default_value_t = 1500.)]
+        temperature: f64,
+        /// largest cartesian displacement of an atom move [A]
+        #[arg(long, default_value_t = 0.05)]
+        step: f64,
+        #[arg(long, default_value_t = 200)]
+        equilibration: usize,
+        #[arg(long, default_value_t = 2000)]
+        sweeps: usize,
+        /// bins of the free energy profile
+        #[arg(long, default_value_t = 50)]
+        bins: usize,
+    },
+    /// trajectory file (any readable format) as a multi-frame extended XYZ with per-atom coordination
+    /// plus movie.py, an OVITO script rendering it into movie.gif
+    Movie {
+        file: PathBuf,
+    },
+    /// side by side comparison of finished run directories: final E/N table and best structure per
+    /// run as report.csv, overlaid E(it) curves and E/N per run as a gnuplot script
+    Report {
+        #[arg(required = true)]
+        dirs: Vec<PathBuf>,
+    },
+    /// energy histograms of the temperature windows of an anneal log and multiple histogram
+    /// reweighting into <E>(T) and C_v(T) (thermodynamics.dat); log with a small --log-step
+    Reweight {
+        log: PathBuf,
+        /// temperature windows, consecutive rows of the log each
+        #[arg(long, default_value_t = 20)]
+        windows: usize,
+        #[arg(long, default_value_t = 50)]
+        bins: usize,
+        /// temperatures of the reweighted curves, evenly spaced between the coldest and hottest window
+        #[arg(long, default_value_t = 200)]
+        points: usize,
+    },
+    /// energy autocorrelation time, effective sample size and error of the mean of the temperature
+    /// windows of an anneal log (autocorrelation.dat), with warnings for unreliable windows
+    Autocorrelation {
+        log: PathBuf,
+        /// temperature windows, consecutive rows of the log each
+        #[arg(long, default_value_t = 20)]
+        windows: usize,
+    },
+    /// energy, coordination, rings, PCF, ADF and virial stress of a structure or trajectory file
+    Analyze {
+        file: PathBuf,
+        /// atoms of the per-atom outputs, a selection expression such as "coord<3" (see selection.rs)
+        #[arg(long, value_parser = Selection::parse)]
+        select: Option<Selection>,
+    },
+    /// slices, subsamples, concatenates and converts trajectories: the frames start:stop:step of
+    /// every input in turn, written in the format of the output extension
+    Traj {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// output name in the run directory: .xyz, .extxyz (with per-atom energies), .dat ("x y z"
+        /// blocks) or a chemfiles format
+        #[arg(short, long, default_value = "trajectory.extxyz")]
+        out: String,
+        /// frames of every input as a python slice start:stop:step (stop excluded, negative from the end)
+        #[arg(long, value_parser = traj::parse_slice, default_value = "::")]
+        frames: Slice,
+        /// only the atoms of a selection expression (see selection.rs), renumbered
+        #[arg(long, value_parser = Selection::parse)]
+        select: Option<Selection>,
+    },
+    /// bond graph of a structure with coordination and per-atom energy, for networkx, Gephi or graphviz
+    Graph {
+        /// structure file or the name of a reference structure (C20, C24, C36, C60, C70)
+        file: PathBuf,
+        /// output name in the run directory, GraphML (.graphml) or DOT (.dot, .gv) by the extension
+        #[arg(long, default_value = "bonds.graphml")]
+        out: String,
+        /// only the atoms of a selection expression such as "r>3.5" (see selection.rs), renumbered
+        #[arg(long, value_parser = Selection::parse)]
+        select: Option<Selection>,
+    },
+    /// Schlegel diagram of a closed cage as SVG, pentagons shaded
+    Schlegel {
+        /// structure file or the name of a reference structure (C20, C24, C36, C60, C70)
+        file: PathBuf,
+        /// output name in the run directory
+        #[arg(long, default_value = "schlegel.svg")]
+        out: String,
+    },
+    /// normal modes of a (relaxed) structure grouped into degenerate sets, with the IR and Raman
+    /// active ones from the point group, for comparing with measured spectra
+    Modes {
+        /// structure file or the name of a reference structure (C20, C24, C36, C60, C70)
+        file: PathBuf,
+        /// isotope substitution as atom:mass, e.g. 0:13.003 (repeatable)
+        #[arg(long = "isotope", value_parser = vibrations::parse_isotope)]
+        isotopes: Vec<(usize, f64)>,
+        /// largest wavenumber difference within a degenerate set [cm^-1]
+        #[arg(long, default_value_t = 1.)]
+        degeneracy_tol: f64,
+        /// largest distance between an atom and the image of another under a symmetry operation [A]
+        #[arg(long, default_value_t = 1e-2)]
+        symmetry_tol: f64,
+        /// temperature of the harmonic free energies [K]
+        #[arg(long, default_value_t = 298.15)]
+        temperature: f64,
+    },
+    /// FIRE relaxation of a structure to the nearest minimum with the configured potential, printing
+    /// the energy before and after
+    Relax {
+        /// structure file or the name of a reference structure (C20, C24, C36, C60, C70)
+        file: PathBuf,
+        #[arg(long, default_value_t = 1e-3)]
+        f_tol: f64,
+        #[arg(long, default_value_t = 10_000)]
+        max_steps: usize,
+        /// write the relaxed structure to this path instead of a run directory: .xyz, .extxyz (with
+        /// per-atom energies), .dat ("x y z") or a chemfiles format
+        #[arg(short, long)]
+        out: Option<String>,
+    },
+    /// single point energy of a structure with the configured potential: total, breakdown into the
+    /// terms and per-atom energies (0.5*V_i) with the coordination, printed; with --gpu (or gpu on in
+    /// the config) the total on the GPU as well
+    Energy {
+        /// structure file or the name of a reference structure (C20, C24, C36, C60, C70)
+        file: PathBuf,
+    },
+    /// RMSD, displacements, energy difference and bond graph isomorphism of two structures
+    Compare {
+        /// structure files or names of reference structures (C20, C24, C36, C60, C70)
+        a: PathBuf,
+        b: PathBuf,
+    },
+    /// climbing image nudged elastic band between two relaxed structures with the same atom
+    /// ordering; writes the relaxed path as path.extxyz and the energies along it as neb.dat, and
+    /// prints the barrier
+    Neb {
+        /// end points, structure files or names of reference structures (C20, C24, C36, C60, C70)
+        a: PathBuf,
+        b: PathBuf,
+        /// inner images of the band
+        #[arg(long, default_value_t = 7)]
+        images: usize,
+        /// spring constant [eV/A^2]
+        #[arg(long, default_value_t = 5.)]
+        k: f64,
+        #[arg(long, default_value_t = 0.05)]
+        f_tol: f64,
+        #[arg(long, default_value_t = 1000)]
+        max_steps: usize,
+        /// plain NEB, the highest image does not climb
+        #[arg(long)]
+        no_climb: bool,
+    },
+    /// cross-check energies against LAMMPS, running it on the structures or reading existing logs
+    Validate {
+        /// structure files or names of reference structures (C20, C24, C36, C60, C70)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// LAMMPS executable
+        #[arg(long, default_value = "lmp")]
+        lammps: String,
+        #[arg(long, default_value = "rebo")]
+        pair_style: String,
+        #[arg(long, default_value = "* * CH.rebo C")]
+        pair_coeff: String,
+        /// LAMMPS logs of the same structures, one per file, instead of running LAMMPS
+        #[arg(long, num_args = 1..)]
+        logs: Vec<PathBuf>,
+        /// energy difference per atom [eV] reported as a discrepancy
+        #[arg(long, default_value_t = 1e-3)]
+        tol: f64,
+    },
+    /// random starting structure of n atoms on a sphere
+    Generate {
+        #[arg(short, long, default_value_t = 60)]
+        n: usize,
+        #[arg(short, long, default_value_t = 2.5)]
+        r: f64,
+        /// a random fullerene cage (spiral_cage) instead of random points; r is not used
+        #[arg(long)]
+        cage: bool,
+    },
+    /// window with interactive anneals: schedule sliders, start/pause/resume, live E/N plot and a
+    /// 3D view of the structure; --config sets the initial schedule and the potential
+    #[cfg(feature = "gui")]
+    Gui,
+    /// results database written with --db: the entries ordered by E/N, or one of them exported
+    #[cfg(feature = "rusqlite")]
+    Db {
+        database: PathBuf,
+        /// only structures of n atoms
+        #[arg(long)]
+        n: Option<usize>,
+        /// only this isomer, as printed by the runs (C40:38, C60[1,7,9,...])
+        #[arg(long)]
+        isomer: Option<String>,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// write the structure of the entry with this id to --out and print its config lines
+        #[arg(long)]
+        export: Option<i64>,
+        /// path of the exported structure: .xyz, .extxyz, .dat or a chemfiles format
+        #[arg(short, long, default_value = "structure.xyz")]
+        out: String,
+    },
+}
+
+impl Cli {
+    fn schedule(&self) -> Result<Schedule, String> {
+        let schedule = match &self.config {
+            Some(path) => Schedule::from_config(&path.to_string_lossy())?,
+            None => Schedule::default(),
+        };
+        #[cfg(feature = "gpu")]
+        let schedule = Schedule { gpu: schedule.gpu || self.gpu, ..schedule };
+        Ok(schedule)
+    }
+
+    fn potential(&self) -> Result<Potential, String> {
+        match &self.config {
+            Some(path) => Potential::from_config(&path.to_string_lossy()),
+            None => Ok(Potential::default()),
+        }
+    }
+
+    // first structure of a file or a reference structure, with the potential of the config
+    fn read_structure(&self, path: &Path) -> Result<Fuleren, String> {
+        let mut f = match Fuleren::reference(&path.to_string_lossy()) {
+            Some(f) if !path.exists() => f,
+            _ => Fuleren::frames_from_file(&path.to_string_lossy())?
+                     .into_iter()
+                     .next()
+                     .ok_or(format!("{}: no structure found", path.display()))?,
+        };
+        f.set_potential(self.potential()?)?;
+        if path.exists() {
+            Metadata::from_file(&path.to_string_lossy())?.check(&path.to_string_lossy(), &f.potential);
+        }
+        f.energy_calc();
+        Ok(f)
+    }
+
+    // saves f as run_dir/<stem>.xyz or, with --extxyz, run_dir/<stem>.extxyz (--format: any chemfiles format),
+    // with the seed and the iteration reached in the metadata header
+    fn save_structure(&self, f: &mut Fuleren, run_dir: &RunDirectory, stem: &str, iteration: Option<usize>) -> Result<(), String> {
+        #[cfg(feature = "chemfiles")]
+        if let Some(ext) = &self.format {
+            return f.save_chemfiles(&run_dir.file(&format!("{}.{}", stem, ext)));
+        }
+        f.energy_calc();
+        let meta = Metadata { seed: self.seed, iteration, ..Metadata::of(f) };
+        if self.extxyz { f.save_extxyz(&run_dir.file(&format!("{}.extxyz", stem)), &meta) }
+        else { f.save_pos_xyz(&run_dir.file(&format!("{}.xyz", stem)), &meta) }
+            .map_err(|e| e.to_string())
+    }
+
+    // when the anneal logs are written and movie frames recorded
+    fn cadence(&self) -> Cadence {
+        let log = self.log_windows.map_or(Every::Iterations(self.log_step), Every::TemperatureWindows);
+        let frames = match (self.frame_windows, self.frame_step) {
+            (Some(k), _) => Every::TemperatureWindows(k),
+            (None, Some(step)) => Every::Iterations(step),
+            (None, None) => log,
+        };
+        Cadence { burn_in: self.burn_in, log, frames }
+    }
+
+    // the --observe variables, checked against the sizes of the runs
+    fn observables(&self, sizes: RangeInclusive<usize>) -> Result<Vec<Box<dyn CollectiveVariable>>, String> {
+        let observables = self.observe.iter().map(|name| cv::from_name(name)).collect::<Result<Vec<_>, _>>()?;
+        for cv in &observables {
+            sizes.clone().try_for_each(|n| cv.check_size(n))?;
+        }
+        Ok(observables)
+    }
+
+    // the --archive structure archive
+    fn archive(&self) -> Option<Archive> {
+        self.archive.as_ref().map(|dir| Archive { size: self.archive_size, rmsd_tol: self.archive_rmsd,
+                                                 ..Archive::new(&dir.to_string_lossy()) })
+    }
+
+    // the --db results database, opened
+    #[cfg(feature = "rusqlite")]
+    fn store(&self) -> Result<Option<ResultStore>, String> {
+        self.db.as_ref().map(|path| ResultStore::open(&path.to_string_lossy())).transpose()
+    }
+
+    fn run_dir(&self, prefix: &str) -> Result<RunDirectory, String> {
+        match &self.name {
+            Some(name) => RunDirectory::named(&self.out_dir, name),
+            None => RunDirectory::create(&self.out_dir, prefix),
+        }.map_err(|e| format!("cannot create run directory in {}: {}", self.out_dir.display(), e))
+    }
+}
+
+// starting structure of n atoms: a random spiral cage, or random points on a sphere of radius r
+fn start_structure(n: usize, r: f64, cage: bool) -> Result<Fuleren, String> {
+    if cage {
+        return Fuleren::spiral_cage(n)
+    }
+    let mut f = Fuleren::new(n);
+    f.randomize_on_sphere(r);
+    Ok(f)
+}
+
+// resolved schedule and potential saved with the run, so it can be repeated with --config <run dir>/config.txt
+fn save_schedule(run_dir: &RunDirectory, schedule: &Schedule, potential: &Potential) -> Result<(), String> {
+    run_dir.save_config(&(schedule.to_config() + &potential.to_config())).map_err(|e| e.to_string())?;
+    if schedule.table.is_some() {
+        schedule.save_table(&run_dir.file("schedule.dat")).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// autocorrelation.dat of the anneal log with warnings for unreliable windows; logs too short for
+// DIAGNOSTIC_WINDOWS windows are skipped
+fn diagnose_log(log: &str, run_dir: &RunDirectory, log_step: usize) -> Result<(), String> {
+    match reweighting::windows_from_log(log, DIAGNOSTIC_WINDOWS) {
+        Ok(windows) => {
+            let diagnostics = autocorrelation::diagnose(&windows);
+            autocorrelation::save_diagnostics(&diagnostics, &run_dir.file("autocorrelation.dat")).map_err(|e| e.to_string())?;
+            autocorrelation::warn_diagnostics(&diagnostics, log_step);
+        }
+        Err(e) => tracing::debug!("no autocorrelation diagnostics: {}", e),
+    }
+    Ok(())
+}
+
+const DIAGNOSTIC_WINDOWS: usize = 10;
+
+// trajectory of the movie in the run directory
+const MOVIE_TRAJECTORY: &str = "trajectory.extxyz";
+
+/// entry point of the LAB7 binary
+pub fn main() {
+    let cli = Cli::parse();
+    // log level from RUST_LOG, e.g. RUST_LOG=debug for per-sweep details
+    let profiler = logging::init(cli.profile);
+    if let Some(seed) = cli.seed {
+        seed_rng(seed);
+    }
+    if let Some(n) = cli.explain {
+        explain::explain_moves(n);
+    }
+
+    if let Err(e) = execute(&cli) {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+
+    if let Some(profiler) = profiler {
+        profiler.report();
+    }
+}
+
+/// runs the selected subcommand
+pub fn execute(cli: &Cli) -> Result<(), String> {
+    match &cli.command {
+        &Command::Run { n, r_start, cage } => {
+            let schedule = cli.schedule()?;
+            let potential = cli.potential()?;
+            let observables = cli.observables(n..=n)?;
+            let run_dir = cli.run_dir("run")?;
+            save_schedule(&run_dir, &schedule, &potential)?;
+            let columns = anneal_columns(&observables);
+            let mut log = ObservableWriter::create(&run_dir.file(&format!("anneal_N{}.log", n)),
+                                                   &columns.iter().map(|c| c.as_str()).collect::<Vec<_>>())
+                                           .map_err(|e| e.to_string())?;
+
+            let mut f = start_structure(n, r_start, cage)?;
+            f.set_potential(potential)?;
+            f.energy_calc();
+            // calibrated here rather than in the anneal, so the final temperature is reported right
+            let schedule = schedule.calibrated(&f);
+            let mut frames = match cli.movie {
+                true => Some(FrameWriter::create(&run_dir.file(MOVIE_TRAJECTORY)).map_err(|e| e.to_string())?),
+                false => None,
+            };
+            let triggers = cli.snapshot.iter().map(|t| Trigger::parse(t)).collect::<Result<Vec<_>, _>>()?;
+            let mut snapshots = match triggers.is_empty() {
+                false => Some(Snapshots::create(&run_dir.file("snapshots.extxyz"), triggers).map_err(|e| e.to_string())?),
+                true => None,
+            };
+            let it = f.anneal_observed(&schedule, &mut log, &cli.cadence(), &observables, frames.as_mut(), snapshots.as_mut())
+                      .map_err(|e| e.to_string())?;
+            if let Some(snapshots) = &snapshots {
+                info!(snapshots = snapshots.taken(), "snapshots taken");
+            }
+            if frames.is_some() {
+                movie::save_ovito_script(&run_dir.file("movie.py"), MOVIE_TRAJECTORY, "movie.gif").map_err(|e| e.to_string())?;
+            }
+            f.energy_calc();
+            drop(log);
+            diagnose_log(&run_dir.file(&format!("anneal_N{}.log", n)), &run_dir, cli.log_step)?;
+
+            save_xy(&f.pcf_r(), &f.pcf(), ["r[A]", "g(r)"], &run_dir.file(&format!("pcf_N{}.dat", n))).map_err(|e| e.to_string())?;
+            let rings = f.ring_energies();
+            ring_strain::save_rings(&rings, &run_dir.file(&format!("rings_N{}.dat", n))).map_err(|e| e.to_string())?;
+            cli.save_structure(&mut f, &run_dir, &format!("atoms_N{}", n), Some(it))?;
+            if let Some(rank) = cli.archive().map(|archive| archive.add(&f, &run_dir.dir())).transpose()?.flatten() {
+                info!(n, rank, "structure archived");
+            }
+            #[cfg(feature = "rusqlite")]
+            if let Some(store) = cli.store()? {
+                store.insert(&f, cli.seed, &(schedule.to_config() + &f.potential.to_config()), &run_dir.dir())?;
+            }
+            run_dir.finish().map_err(|e| e.to_string())?;
+            let strain = ring_strain::ring_strain(&rings);
+            info!(n, it, T_final = %schedule.temperature(it), E = f.E, E_per_atom = f.E/n as f64, r_mean = f.mean_r(),
+                  pentagons = strain.pentagons, pentagon_strain = strain.pentagon_strain,
+                  isomer = f.isomer().map_or("-".to_string(), |i| i.to_string()), dir = %run_dir.dir(), "anneal finished");
+        }
+        &Command::Sweep { n_min, n_max, runs, gather_timeout, free_energy } => {
+            let group = ProcessGroup::from_env();
+            if group.size > 1 && cli.name.is_none() {
+                return Err("multi-process sweeps need --name, so all ranks share the run directory".to_string());
+            }
+            let schedule = cli.schedule()?;
+            let potential = cli.potential()?;
+            let observables = cli.observables(n_min..=n_max)?;
+            let run_dir = cli.run_dir("sweep")?;
+            if group.rank == 0 {
+                save_schedule(&run_dir, &schedule, &potential)?;
+            }
+
+            let mut runner = ParallelRunner::new(&run_dir.dir());
+            if let Some(threads) = cli.threads {
+                runner.threads = threads;
+            }
+            runner.cadence = cli.cadence();
+            runner.extxyz = cli.extxyz;
+            runner.archive = cli.archive();
+            #[cfg(feature = "rusqlite")]
+            { runner.store = cli.store()?; }
+            runner.potential = potential;
+            runner.observables = observables;
+            let seed = cli.seed.unwrap_or(0);
+            let seeds: Vec<u64> = (seed..seed + runs).collect();
+            runner.add_grid(n_min..=n_max, &seeds, &schedule);
+
+            if group.size > 1 {
+                runner.run_distributed(&group, Duration::from_secs(gather_timeout)).map_err(|e| e.to_string())?;
+            }
+            else {
+                runner.run().map_err(|e| e.to_string())?;
+            }
+            if group.rank == 0 {
+                // from summary.dat, which holds the jobs of all ranks
+                let points = runner::ensemble(&runner::read_summary(&run_dir.file("summary.dat"))?);
+                runner::save_ensemble(&points, &run_dir.dir()).map_err(|e| e.to_string())?;
+                let isomers = runner::read_isomers(&run_dir.file("summary.dat"))?;
+                let ranking = match free_energy {
+                    Some(t) => Some((runner::rank_isomers(&isomers, &run_dir.dir(), &runner.potential, t)?, t)),
+                    None => None,
+                };
+                runner::save_isomers(&isomers, ranking.as_ref().map(|(r, t)| (r.as_slice(), *t)), &run_dir.dir())
+                    .map_err(|e| e.to_string())?;
+                run_dir.finish().map_err(|e| e.to_string())?;
+            }
+        }
+        Command::Sensitivity { n, parameters, grid, lhs, runs, it_max } => {
+            let (config, config_path) = match &cli.config {
+                Some(path) => (std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?,
+                               path.to_string_lossy().into_owned()),
+                None => (String::new(), "config".to_string()),
+            };
+            let seed = cli.seed.unwrap_or(0);
+            let sweep = SensitivitySweep { n: *n,
+                                           parameters: parameters.clone(),
+                                           sampling: lhs.map_or(Sampling::Grid(*grid), Sampling::LatinHypercube),
+                                           seeds: (seed..seed + runs).collect(),
+                                           it_max: *it_max,
+                                           config,
+                                           config_path };
+            let run_dir = cli.run_dir("sensitivity")?;
+            run_dir.save_config(&sweep.config).map_err(|e| e.to_string())?;
+
+            let mut runner = ParallelRunner::new(&run_dir.dir());
+            if let Some(threads) = cli.threads {
+                runner.threads = threads;
+            }
+            runner.cadence = cli.cadence();
+            runner.extxyz = cli.extxyz;
+            runner.archive = cli.archive();
+            #[cfg(feature = "rusqlite")]
+            { runner.store = cli.store()?; }
+            sweep.run(&mut runner)?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        Command::Tune { n, r_start, candidates, budget, eta, repeats } => {
+            if *eta < 2 {
+                return Err(format!("eta must be at least 2, got {}", eta));
+            }
+            let potential = cli.potential()?;
+            potential.check_atoms(*n)?;
+            let search = ScheduleSearch { n: *n, r_start: *r_start, candidates: *candidates, budget: *budget, eta: *eta,
+                                          repeats: *repeats, potential: potential.clone(), ..ScheduleSearch::default() };
+            let (schedule, e) = search.run();
+            let run_dir = cli.run_dir("tune")?;
+            save_schedule(&run_dir, &schedule, &potential)?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+
+            print!("{}", schedule.to_config());
+            println!("mean final E: {:.5} ({:.5} per atom)", e, e/ *n as f64);
+        }
+        Command::Quench { file, n, r_start, cage, it_max, p, runs } => {
+            let mut start = match file {
+                Some(file) => cli.read_structure(file)?,
+                None => start_structure(*n, *r_start, *cage)?,
+            };
+            start.set_potential(cli.potential()?)?;
+            let potential = start.potential.clone();
+            let seed = cli.seed.unwrap_or(0);
+            let study = QuenchStudy { start, schedule: cli.schedule()?, it_max: it_max.clone(), p: p.clone(),
+                                      seeds: (seed..seed + runs).collect() };
+            let run_dir = cli.run_dir("quench")?;
+            save_schedule(&run_dir, &study.schedule, &potential)?;
+            cli.save_structure(&mut study.start.clone(), &run_dir, "start", None)?;
+
+            let mut runner = ParallelRunner::new(&run_dir.dir());
+            if let Some(threads) = cli.threads {
+                runner.threads = threads;
+            }
+            runner.cadence = cli.cadence();
+            runner.extxyz = cli.extxyz;
+            runner.archive = cli.archive();
+            #[cfg(feature = "rusqlite")]
+            { runner.store = cli.store()?; }
+            study.run(&mut runner)?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        Command::Hop { file, n, r_start, cage, hops, kt, e_diff } => {
+            let mut f = match file {
+                Some(file) => cli.read_structure(file)?,
+                None => start_structure(*n, *r_start, *cage)?,
+            };
+            f.set_potential(cli.potential()?)?;
+            f.energy_calc();
+            let run_dir = cli.run_dir("hop")?;
+            cli.save_structure(&mut f.clone(), &run_dir, "start", None)?;
+
+            let mut hopping = MinimaHopping { kt: *kt, e_diff: *e_diff, ..MinimaHopping::default() };
+            let mut best = hopping.run(&mut f, *hops);
+            cli.save_structure(&mut best, &run_dir, "best", None)?;
+            let minima = Array1::from_vec(hopping.history.clone());
+            save_xy(&Array1::from_iter(0..minima.len()), &minima, ["minimum", "E"], &run_dir.file("minima.dat"))
+                .map_err(|e| e.to_string())?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+
+            println!("distinct minima: {}, final kT: {:.4}, E_diff: {:.4}", minima.len(), hopping.kt, hopping.e_diff);
+            let defects = best.coordination().iter().filter(|&&c| c != 3).count();
+            println!("best E: {:.5} ({:.5} per atom), defects: {}", best.E, best.E/best.size() as f64, defects);
+        }
+        Command::Umbrella { file, coordinate, from, to, windows, k, temperature, step, equilibration, sweeps, bins } => {
+            let start = cli.read_structure(file)?;
+            let beta = Temperature(*temperature).beta().0;
+            let sampling = UmbrellaSampling { coordinate: cv::from_name(coordinate)?,
+                                              windows: UmbrellaSampling::evenly_spaced(*from, *to, *windows, *k),
+                                              beta,
+                                              step: *step,
+                                              equilibration: *equilibration,
+                                              sweeps: *sweeps,
+                                              sample_every: 1,
+                                              threads: cli.threads.unwrap_or_else(|| std::thread::available_parallelism()
+                                                                                         .map(|n| n.get()).unwrap_or(1)),
+                                              seed: cli.seed.unwrap_or(0) };
+            let run_dir = cli.run_dir("umbrella")?;
+            let samples = sampling.run(&start, &run_dir.dir()).map_err(|e| e.to_string())?;
+
+            let profile = umbrella::wham(&sampling.windows, &samples, beta, *bins, 1e-7, 100_000);
+            umbrella::save_profile(&profile, &run_dir.file("free_energy.dat")).map_err(|e| e.to_string())?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+            info!(windows, bins = profile.len(), dir = %run_dir.dir(), "umbrella sampling finished");
+        }
+        Command::Movie { file } => {
+            let potential = cli.potential()?;
+            let run_dir = cli.run_dir("movie")?;
+            let mut frames = FrameWriter::create(&run_dir.file(MOVIE_TRAJECTORY)).map_err(|e| e.to_string())?;
+            for (n, mut f) in Fuleren::frames_from_file(&file.to_string_lossy())?.into_iter().enumerate() {
+                f.set_potential(potential.clone())?;
+                f.energy_calc();
+                frames.write(&f, &format!("frame={}", n)).map_err(|e| e.to_string())?;
+            }
+            movie::save_ovito_script(&run_dir.file("movie.py"), MOVIE_TRAJECTORY, "movie.gif").map_err(|e| e.to_string())?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+            info!(frames = frames.frames, dir = %run_dir.dir(), "movie written, render with ovitos movie.py");
+        }
+        Command::Report { dirs } => {
+            let run_dir = cli.run_dir("report")?;
+            report::report(dirs, &run_dir.dir(), &cli.potential()?)?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        Command::Reweight { log, windows, bins, points } => {
+            let windows = reweighting::windows_from_log(&log.to_string_lossy(), *windows)?;
+            let run_dir = cli.run_dir("reweight")?;
+            reweighting::save_histograms(&windows, *bins, &run_dir.file("histograms.dat")).map_err(|e| e.to_string())?;
+
+            let temperatures = windows.iter().map(|w| Beta(w.beta).temperature().0);
+            let t_min = temperatures.clone().fold(f64::INFINITY, f64::min);
+            let t_max = temperatures.fold(f64::NEG_INFINITY, f64::max);
+            let betas: Vec<f64> = (0..*points).map(|k| t_min + (t_max - t_min)*k as f64/(points.max(&2) - 1) as f64)
+                                              .map(|t| Temperature(t).beta().0)
+                                              .collect();
+            autocorrelation::warn_diagnostics(&autocorrelation::diagnose(&windows), cli.log_step);
+            let curve = reweighting::multihistogram(&windows, &betas, 1e-8, 10_000);
+            reweighting::save_thermodynamics(&curve, &run_dir.file("thermodynamics.dat")).map_err(|e| e.to_string())?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+            info!(windows = windows.len(), dir = %run_dir.dir(), "reweighting finished");
+        }
+        Command::Autocorrelation { log, windows } => {
+            let windows = reweighting::windows_from_log(&log.to_string_lossy(), *windows)?;
+            let run_dir = cli.run_dir("autocorrelation")?;
+            let diagnostics = autocorrelation::diagnose(&windows);
+            autocorrelation::save_diagnostics(&diagnostics, &run_dir.file("autocorrelation.dat")).map_err(|e| e.to_string())?;
+            autocorrelation::warn_diagnostics(&diagnostics, cli.log_step);
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        Command::Analyze { file, select } => {
+            let run_dir = cli.run_dir("analyze")?;
+            analysis::analyze(&file.to_string_lossy(), &run_dir.dir(), &cli.potential()?, select.as_ref())?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        Command::Relax { file, f_tol, max_steps, out } => {
+            let mut f = cli.read_structure(file)?;
+            let (e_before, f_before) = (f.E, relax::max_force(&f.forces()));
+            let steps = f.relax(*f_tol, *max_steps);
+            f.energy_calc();
+            let f_after = relax::max_force(&f.forces());
+            match out {
+                Some(out) => {
+                    let mut sink = FrameSink::create(out)?;
+                    sink.write(&f, &format!("source=\"{}\" potential={} E={:.6}", file.display(), f.potential.name(), f.E))?;
+                    sink.finish()?;
+                }
+                None => {
+                    let run_dir = cli.run_dir("relax")?;
+                    cli.save_structure(&mut f, &run_dir, "relaxed", None)?;
+                    run_dir.finish().map_err(|e| e.to_string())?;
+                }
+            }
+            let n = f.size() as f64;
+            println!("potential: {}", f.potential.name());
+            println!("E before: {:.5} ({:.5} per atom), max force: {:.2e}", e_before, e_before/n, f_before);
+            println!("E after:  {:.5} ({:.5} per atom), max force: {:.2e}", f.E, f.E/n, f_after);
+            println!("dE: {:.5}, steps: {}{}", f.E - e_before, steps,
+                     if f_after > *f_tol { " (not converged)" } else { "" });
+        }
+        Command::Energy { file } => {
+            let mut f = cli.read_structure(file)?;
+            let breakdown = f.energy_calc_detailed();
+            println!("potential: {}", f.potential.name());
+            println!("atoms: {}, E/N: {:.5}", f.size(), f.E/f.size() as f64);
+            println!("{}", breakdown);
+            #[cfg(feature = "gpu")]
+            if cli.schedule()?.gpu {
+                let kernel = crate::GpuEnergy::new(&f.potential.brenner).ok_or("no usable GPU adapter found")?;
+                let e = f.clone().energy_calc_gpu(&kernel)?;
+                println!("{:<18} {:>14.5} (CPU - GPU: {:.2e})", "total on the GPU", e, f.clone().energy_calc() - e);
+            }
+            println!();
+            println!("{:<6} {:<2} {:>14} {:>5}", "atom", "el", "E_i", "coord");
+            for (i, (element, c)) in f.species().iter().zip(f.coordination()).enumerate() {
+                println!("{:<6} {:<2} {:>14.6} {:>5}", i, element.symbol(), 0.5*f._vi(i), c);
+            }
+        }
+        Command::Traj { files, out, frames, select } => {
+            let inputs: Vec<String> = files.iter().map(|f| f.to_string_lossy().into_owned()).collect();
+            let run_dir = cli.run_dir("traj")?;
+            let written = traj::edit(&inputs, *frames, select.as_ref(), &cli.potential()?, &run_dir.file(out))?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+            println!("{} frames written to {}", written, run_dir.file(out));
+        }
+        Command::Graph { file, out, select } => {
+            let mut f = cli.read_structure(file)?;
+            if let Some(selection) = select {
+                f = f.subset(&f.select(selection));
+            }
+            let run_dir = cli.run_dir("graph")?;
+            f.save_graph(&run_dir.file(out))?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        Command::Schlegel { file, out } => {
+            let f = cli.read_structure(file)?;
+            let run_dir = cli.run_dir("schlegel")?;
+            f.save_schlegel_svg(&run_dir.file(out))?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        Command::Modes { file, isotopes, degeneracy_tol, symmetry_tol, temperature } => {
+            let mut f = cli.read_structure(file)?;
+            let mut masses = f.masses();
+            for &(i, m) in isotopes {
+                *masses.get_mut(i).ok_or_else(|| format!("isotope on atom {}, the structure has {} atoms", i, f.size()))? = m;
+            }
+            let modes = f.normal_modes_with_masses(masses);
+            if modes.n_imaginary() > 0 {
+                warn!("{} imaginary modes, the structure is not a minimum (relax it first)", modes.n_imaginary());
+            }
+            let operations = f.symmetry_operations(&modes.masses.to_vec(), *symmetry_tol);
+            let sets = modes.activity(&operations, *degeneracy_tol);
+
+            let run_dir = cli.run_dir("modes")?;
+            vibrations::save_modes(&modes, &sets, operations.len(), *temperature, &run_dir.file("modes.dat"))
+                .map_err(|e| e.to_string())?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+            println!("point group order: {}, mode sets: {}, IR active: {}, Raman active: {}", operations.len(), sets.len(),
+                     sets.iter().filter(|s| s.ir).count(), sets.iter().filter(|s| s.raman).count());
+            println!("F(T = {} K): {:.5} (E0 {:.5})", temperature, modes.free_energy(*temperature), modes.e0);
+        }
+        Command::Compare { a, b } => {
+            let comparison = compare::compare(&mut cli.read_structure(a)?, &mut cli.read_structure(b)?)?;
+            print!("{}", comparison);
+        }
+        Command::Neb { a, b, images, k, f_tol, max_steps, no_climb } => {
+            let (fa, fb) = (cli.read_structure(a)?, cli.read_structure(b)?);
+            if fa.size() != fb.size() {
+                return Err(format!("NEB end points must have the same number of atoms, {} has {} and {} has {}",
+                                   a.display(), fa.size(), b.display(), fb.size()));
+            }
+            let mut neb = Neb::new(&fa, &fb, *images);
+            neb.k = *k;
+            neb.climbing = !*no_climb;
+            let steps = neb.run(*f_tol, *max_steps);
+            let e = neb.energies();
+
+            let run_dir = cli.run_dir("neb")?;
+            let mut frames = FrameWriter::create(&run_dir.file("path.extxyz")).map_err(|e| e.to_string())?;
+            for (m, image) in neb.images.iter().enumerate() {
+                frames.write(image, &format!("image={}", m)).map_err(|e| e.to_string())?;
+            }
+            let de = Array1::from_iter(e.iter().map(|em| em - e[0]));
+            save_xy(&Array1::from_iter(0..e.len()), &de, ["image", "E-E_a"], &run_dir.file("neb.dat"))
+                .map_err(|e| e.to_string())?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+
+            println!("{:<6} {:<14} {:<12}", "image", "E", "E-E_a");
+            for (m, (e, de)) in e.iter().zip(de.iter()).enumerate() {
+                println!("{:<6} {:<14.6} {:<12.6}", m, e, de);
+            }
+            let barrier = neb.barrier();
+            println!("barrier: {:.5} forward, {:.5} backward, steps: {}{}", barrier, barrier - de[de.len() - 1], steps,
+                     if steps >= *max_steps { " (not converged)" } else { "" });
+        }
+        Command::Validate { files, lammps, pair_style, pair_coeff, logs, tol } => {
+            if !logs.is_empty() && logs.len() != files.len() {
+                return Err(format!("{} structures but {} logs", files.len(), logs.len()));
+            }
+            let check = LammpsCheck { binary: lammps.clone(), pair_style: pair_style.clone(),
+                                      pair_coeff: pair_coeff.clone(), tolerance: *tol };
+            let run_dir = cli.run_dir("validate")?;
+
+            println!("{:<24} {:<6} {:<14} {:<14} {:<12}", "structure", "N", "E", "E_lammps", "dE/N");
+            let mut mismatches = 0;
+            for (i, file) in files.iter().enumerate() {
+                let mut f = cli.read_structure(file)?;
+                let v = match logs.get(i) {
+                    Some(log) => check.compare_log(&mut f, &log.to_string_lossy())?,
+                    None => check.run(&mut f, &run_dir.file(&format!("structure_{}", i)))?,
+                };
+                let ok = check.agrees(&v);
+                println!("{:<24} {:<6} {:<14.6} {:<14.6} {:<12.3e} {}", file.display(), v.n, v.e, v.e_lammps,
+                         v.de_per_atom(), if ok { "ok" } else { "MISMATCH" });
+                if !ok {
+                    warn!(structure = %file.display(), dE_per_atom = v.de_per_atom(), "energies disagree");
+                    mismatches += 1;
+                }
+            }
+            run_dir.finish().map_err(|e| e.to_string())?;
+            if mismatches > 0 {
+                return Err(format!("{} of {} structures disagree with LAMMPS", mismatches, files.len()));
+            }
+        }
+        &Command::Generate { n, r, cage } => {
+            let mut f = start_structure(n, r, cage)?;
+            let run_dir = cli.run_dir("generate")?;
+            cli.save_structure(&mut f, &run_dir, &format!("{}_N{}", if cage { "cage" } else { "random" }, n), None)?;
+            run_dir.finish().map_err(|e| e.to_string())?;
+        }
+        #[cfg(feature = "gui")]
+        Command::Gui => crate::gui::run(cli.schedule()?, cli.potential()?, cli.seed.unwrap_or(0))?,
+        #[cfg(feature = "rusqlite")]
+        Command::Db { database, n, isomer, limit, export, out } => {
+            let database = database.to_string_lossy();
+            let store = ResultStore::open(&database)?;
+            match export {
+                Some(id) => {
+                    let (mut f, parameters) = store.structure(*id)?;
+                    f.set_potential(Potential::from_config_str(&parameters, &database)?)?;
+                    f.energy_calc();
+                    let mut sink = FrameSink::create(out)?;
+                    sink.write(&f, &format!("source=\"{}\" id={} E={:.6}", database, id, f.E))?;
+                    sink.finish()?;
+                    print!("{}", parameters);
+                }
+                None => {
+                    let query = Query { n: *n, isomer: isomer.clone(), limit: Some(*limit) };
+                    println!("{:<6} {:<12} {:<6} {:<10} {:<14} {:<10} {:<20} source",
+                             "id", "time", "N", "seed", "E", "E/N", "isomer");
+                    for entry in store.query(&query)? {
+                        println!("{}", entry);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use crate::analysis::_shortest_path;
+use crate::{Fuleren, R1};
+
+/// local environment of an atom from common-neighbor analysis of the bond graph (bonds are pairs
+/// inside R1). In an sp2 network no two bonded atoms share a neighbour (CNA signature (0,0,0) for
+/// every bond) and the three faces around an atom tell the cage signatures apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+    Pentagon,       // sp2, faces 5-6-6 (every atom of an isolated-pentagon fullerene)
+    Hexagon,        // sp2, faces 6-6-6 (graphene-like)
+    FusedPentagons, // sp2, two or three pentagons meet
+    RingDefect,     // sp2, a face of 3, 4 or 7+ atoms (Stone-Wales defects, ...)
+    Undercoordinated,
+    Overcoordinated,
+    Disordered,     // 3 bonds, but shared neighbours or open faces
+}
+
+impl Environment {
+    pub const ALL: [Environment; 7] = [Environment::Pentagon, Environment::Hexagon, Environment::FusedPentagons,
+                                       Environment::RingDefect, Environment::Undercoordinated,
+                                       Environment::Overcoordinated, Environment::Disordered];
+
+    /// short label for per-atom columns
+    pub fn label(&self) -> &'static str {
+        match self {
+            Environment::Pentagon => "566",
+            Environment::Hexagon => "666",
+            Environment::FusedPentagons => "55x",
+            Environment::RingDefect => "ring",
+            Environment::Undercoordinated => "under",
+            Environment::Overcoordinated => "over",
+            Environment::Disordered => "other",
+        }
+    }
+}
+
+// largest face looked for around an atom
+const MAX_FACE: usize = 8;
+
+impl Fuleren {
+    /// environment of every atom
+    pub fn cna(&self) -> Vec<Environment> {
+        let bonds = self.bond_graph(R1);
+        (0..self.size).map(|i| {
+            match bonds[i].len() {
+                0..=2 => return Environment::Undercoordinated,
+                3 => {}
+                _ => return Environment::Overcoordinated,
+            }
+            if bonds[i].iter().any(|&j| _signature(&bonds, i, j).0 > 0) {
+                return Environment::Disordered
+            }
+
+            // smallest ring through every bond angle j-i-k
+            let mut faces = Vec::with_capacity(3);
+            for (n, &j) in bonds[i].iter().enumerate() {
+                for &k in &bonds[i][n+1..] {
+                    match _shortest_path(&bonds, j, k, i, MAX_FACE - 1) {
+                        Some(path) => faces.push(path.len() + 1),
+                        None => return Environment::Disordered,
+                    }
+                }
+            }
+            faces.sort_unstable();
+            match faces[..] {
+                [5, 6, 6] => Environment::Pentagon,
+                [6, 6, 6] => Environment::Hexagon,
+                _ if faces.iter().all(|&s| s == 5 || s == 6) => Environment::FusedPentagons,
+                _ => Environment::RingDefect,
+            }
+        }).collect()
+    }
+}
+
+// CNA signature of the bond i-j: common neighbours, bonds among them, longest chain of those bonds
+fn _signature(bonds: &[Vec<usize>], i: usize, j: usize) -> (usize, usize, usize) {
+    let common: Vec<usize> = bonds[i].iter().copied().filter(|k| bonds[j].contains(k)).collect();
+    let links: Vec<(usize, usize)> = common.iter()
+                                           .enumerate()
+                                           .flat_map(|(n, &a)| common[n+1..].iter().map(move |&b| (a, b)))
+                                           .filter(|&(a, b)| bonds[a].contains(&b))
+                                           .collect();
+    let longest = (0..links.len()).map(|start| _longest_chain(&links, start, &mut vec![false; links.len()]))
+                                  .max()
+                                  .unwrap_or(0);
+    (common.len(), links.len(), longest)
+}
+
+// bonds in the longest chain of links starting with links[k] (links sharing an atom are connected)
+fn _longest_chain(links: &[(usize, usize)], k: usize, used: &mut Vec<bool>) -> usize {
+    used[k] = true;
+    let (a, b) = links[k];
+    let mut best = 0;
+    for m in 0..links.len() {
+        let (c, d) = links[m];
+        if !used[m] && (a == c || a == d || b == c || b == d) {
+            best = best.max(_longest_chain(links, m, used));
+        }
+    }
+    used[k] = false;
+    best + 1
+}
@@ -0,0 +1,96 @@
+//! uniform atom moves on all cores: the atoms are colored so that two atoms of a color are
+//! farther apart than twice the interaction range plus twice the largest step. A move then cannot
+//! change the energy terms of another move of its color, so the moves of a color are proposed and
+//! accepted concurrently with the same outcome as one after the other and detailed balance holds
+//! as in the serial sweep. Pays off for large shells; a cage not much larger than the conflict
+//! distance gets a color per atom and runs serially
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::sphere_cells::{SphereCells, CELLS_MIN_SIZE};
+use crate::{rng, seed_rng, Fuleren, Point6};
+
+impl Fuleren {
+    /// atoms grouped into independent sets by greedy coloring in index order; atoms of a set are
+    /// farther apart than r_conflict
+    pub fn independent_sets(&self, r_conflict: f64) -> Vec<Vec<usize>> {
+        let cells = (self.size >= CELLS_MIN_SIZE).then(|| SphereCells::build(self, r_conflict));
+        let mut color = vec![usize::MAX; self.size];
+        let mut sets: Vec<Vec<usize>> = Vec::new();
+
+        for i in 0..self.size {
+            let neighbours = match &cells {
+                Some(cells) => self.neighbours_in_cells(cells, i, r_conflict),
+                None => (0..self.size).filter(|&j| j != i && self._r_ij(i, j) <= r_conflict).collect(),
+            };
+            // uncolored neighbours (later atoms) have usize::MAX
+            let used: Vec<usize> = neighbours.iter().map(|&j| color[j]).collect();
+            let c = (0..).find(|c| !used.contains(c)).unwrap();
+            if c == sets.len() {
+                sets.push(Vec::new());
+            }
+            sets[c].push(i);
+            color[i] = c;
+        }
+        sets
+    }
+
+    /// one sweep of uniform atom moves (atom i at beta[i], none for the fixed ones), the atoms of
+    /// every independent set split among `threads` workers; returns the number of accepted moves.
+    /// The workers draw from generators seeded by the calling thread's, so a seeded run is
+    /// reproducible for a fixed number of threads
+    pub fn colored_sweep(&mut self, beta: &[f64], fixed: &[bool], threads: usize) -> usize {
+        let sets = self.independent_sets(2.*self.potential.range() + 2.*self.max_step());
+        let threads = threads.max(1).min(sets.iter().map(|s| s.len()).max().unwrap_or(1));
+        // the workers' copies are kept in step with self after every color
+        let mut workers = vec![self.clone(); threads];
+        let mut accepted = 0;
+
+        for set in &sets {
+            let set: Vec<usize> = set.iter().copied().filter(|&i| !fixed[i]).collect();
+            if set.is_empty() { continue }
+            let moves: Vec<(usize, Point6)> = if set.len() == 1 || threads == 1 {
+                set.iter().filter_map(|&i| workers[0].colored_move(i, beta[i]).map(|p| (i, p))).collect()
+            }
+            else {
+                let chunk = set.len().div_ceil(threads);
+                let seeds: Vec<u64> = (0..threads).map(|_| rng().gen()).collect();
+                std::thread::scope(|s| {
+                    let handles: Vec<_> = workers.iter_mut().zip(set.chunks(chunk)).zip(seeds).map(|((w, atoms), seed)| {
+                        s.spawn(move || {
+                            seed_rng(seed);
+                            atoms.iter().filter_map(|&i| w.colored_move(i, beta[i]).map(|p| (i, p))).collect::<Vec<_>>()
+                        })
+                    }).collect();
+                    handles.into_iter().flat_map(|h| h.join().expect("sweep worker panicked")).collect()
+                })
+            };
+
+            accepted += moves.len();
+            for (i, p) in moves {
+                for w in workers.iter_mut() {
+                    w.positions[i] = p.clone();
+                }
+                self.positions[i] = p;
+            }
+        }
+        accepted
+    }
+
+    // largest displacement of a uniform atom move with the current step sizes: |dr| <= r w_r and
+    // the direction turns by at most |dtheta| + |dphi| <= PI w_theta + 2 PI w_phi
+    fn max_step(&self) -> f64 {
+        let r_max = self.positions.iter().map(|p| p.r()).fold(0., f64::max);
+        let s = &self.steps;
+        r_max*(s.w_r + (1. + s.w_r)*(PI*s.w_theta + 2.*PI*s.w_phi))
+    }
+
+    // uniform move of atom i with the acceptance of random_atom_shift; returns the new position if
+    // accepted, the structure itself is left as it was
+    fn colored_move(&mut self, i: usize, beta: f64) -> Option<Point6> {
+        let new = self._propose_shift(i);
+        let dv = self._shift_dvi(i, &new)?;
+        (rng().gen::<f64>() <= (-beta*dv).exp()).then_some(new)
+    }
+}
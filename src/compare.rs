@@ -0,0 +1,185 @@
+use std::f64::consts::PI;
+
+use ndarray::prelude::*;
+
+use crate::bonds::isomorphic;
+use crate::vibrations::jacobi_eigen;
+use crate::{Fuleren, R1};
+
+/// difference between two structures with the same atom numbering
+pub struct Comparison {
+    pub rmsd: f64,               // after optimal superposition
+    pub displacements: Vec<f64>, // per atom, after optimal superposition
+    pub de: f64,                 // E_b - E_a
+    pub isomorphic: bool,        // bond graphs are the same up to relabelling
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "RMSD: {:.5}", self.rmsd)?;
+        writeln!(f, "dE: {:.5}", self.de)?;
+        writeln!(f, "isomorphic bond graphs: {}", self.isomorphic)?;
+        writeln!(f, "{:<6} {:<10}", "atom", "dr")?;
+        for (i, d) in self.displacements.iter().enumerate() {
+            writeln!(f, "{:<6} {:<10.5}", i, d)?;
+        }
+        Ok(())
+    }
+}
+
+/// compares b against a; the structures must have the same number of atoms
+pub fn compare(a: &mut Fuleren, b: &mut Fuleren) -> Result<Comparison, String> {
+    if a.size != b.size {
+        return Err(format!("different number of atoms: {} and {}", a.size, b.size));
+    }
+    let de = b.energy_calc() - a.energy_calc();
+
+    let xa = centered(a);
+    let xb = centered(b);
+    let aligned = xa.dot(&optimal_rotation(&xa, &xb).t());
+
+    let displacements: Vec<f64> = (&aligned - &xb).rows()
+                                                  .into_iter()
+                                                  .map(|d| d.dot(&d).sqrt())
+                                                  .collect();
+    let rmsd = (displacements.iter().map(|d| d*d).sum::<f64>()/a.size as f64).sqrt();
+
+    Ok(Comparison { rmsd, displacements, de, isomorphic: isomorphic(&a.bond_graph(R1), &b.bond_graph(R1)) })
+}
+
+/// RMSD [A] after optimal superposition of structures whose atom numbering is unrelated (an anneal
+/// against a reference isomer): iterative closest point alignment from a fixed set of starting
+/// rotations, then the optimal one-to-one assignment of the atoms at the best alignment and a
+/// final superposition. xa and xb are centered (see centered) and of the same size
+pub(crate) fn unlabelled_rmsd(xa: &Array2<f64>, xb: &Array2<f64>) -> f64 {
+    let n = xa.nrows();
+    let d2 = |a: ArrayView1<f64>, b: ArrayView1<f64>| (&a - &b).mapv(|d| d*d).sum();
+
+    let mut best = (f64::INFINITY, Array2::eye(3));
+    for q in start_rotations() {
+        let mut rotation = quaternion_matrix(q);
+        let mut last = f64::INFINITY;
+        for _ in 0..ICP_ITERATIONS {
+            let aligned = xa.dot(&rotation.t());
+            let nearest: Vec<usize> = aligned.rows().into_iter().map(|p| {
+                (0..n).min_by(|&i, &j| d2(p, xb.row(i)).total_cmp(&d2(p, xb.row(j)))).unwrap()
+            }).collect();
+            let msd = aligned.rows().into_iter().zip(&nearest).map(|(p, &j)| d2(p, xb.row(j))).sum::<f64>()/n as f64;
+            rotation = optimal_rotation(xa, &xb.select(Axis(0), &nearest));
+            if last - msd < 1e-10 { break }
+            last = msd;
+        }
+        if last < best.0 {
+            best = (last, rotation);
+        }
+    }
+
+    let aligned = xa.dot(&best.1.t());
+    let cost = Array2::from_shape_fn((n, n), |(i, j)| d2(aligned.row(i), xb.row(j)));
+    let matched = xb.select(Axis(0), &assignment(&cost));
+    let aligned = xa.dot(&optimal_rotation(xa, &matched).t());
+    ((&aligned - &matched).mapv(|d| d*d).sum()/n as f64).sqrt()
+}
+
+const ICP_ITERATIONS: usize = 30;
+
+// identity and rotations by k*30 degrees (k = 1..5) about 32 axes spread over the sphere (Fibonacci
+// lattice), dense enough for the closest point iteration to find the global alignment of cages
+fn start_rotations() -> Vec<[f64; 4]> {
+    let golden = PI*(3. - 5f64.sqrt());
+    let mut rotations = vec![[1., 0., 0., 0.]];
+    for k in 0..32 {
+        let z = 1. - (k as f64 + 0.5)/16.;
+        let (s, phi) = ((1. - z*z).sqrt(), golden*k as f64);
+        for m in 1..6 {
+            let half = PI*m as f64/12.;
+            let (c, w) = (half.cos(), half.sin());
+            rotations.push([c, w*s*phi.cos(), w*s*phi.sin(), w*z]);
+        }
+    }
+    rotations
+}
+
+// minimum cost one-to-one assignment, row i to column assigned[i] (Hungarian method, O(n^3))
+fn assignment(cost: &Array2<f64>) -> Vec<usize> {
+    let n = cost.nrows();
+    let (mut u, mut v) = (vec![0.; n + 1], vec![0.; n + 1]);
+    let (mut row_of, mut way) = (vec![0usize; n + 1], vec![0usize; n + 1]);
+    for i in 1..=n {
+        row_of[0] = i;
+        let mut j0 = 0;
+        let mut min_v = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[j0] = true;
+            let i0 = row_of[j0];
+            let (mut delta, mut j1) = (f64::INFINITY, 0);
+            for j in (1..=n).filter(|&j| !used[j]) {
+                let reduced = cost[[i0 - 1, j - 1]] - u[i0] - v[j];
+                if reduced < min_v[j] {
+                    min_v[j] = reduced;
+                    way[j] = j0;
+                }
+                if min_v[j] < delta {
+                    delta = min_v[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_v[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if row_of[j0] == 0 { break }
+        }
+        loop {
+            let j1 = way[j0];
+            row_of[j0] = row_of[j1];
+            j0 = j1;
+            if j0 == 0 { break }
+        }
+    }
+    let mut assigned = vec![0; n];
+    for j in 1..=n {
+        assigned[row_of[j] - 1] = j - 1;
+    }
+    assigned
+}
+
+// cartesian coordinates with the center of mass at the origin
+pub(crate) fn centered(f: &Fuleren) -> Array2<f64> {
+    let c = f.center_of_mass();
+    let mut xyz = f.xyz_array();
+    for mut row in xyz.rows_mut() {
+        row -= &aview1(&c);
+    }
+    xyz
+}
+
+// rotation R minimizing sum |R a_i - b_i|^2 for centered a, b; quaternion method (Horn 1987)
+fn optimal_rotation(a: &Array2<f64>, b: &Array2<f64>) -> Array2<f64> {
+    let s = a.t().dot(b);
+    let (xx, xy, xz) = (s[[0, 0]], s[[0, 1]], s[[0, 2]]);
+    let (yx, yy, yz) = (s[[1, 0]], s[[1, 1]], s[[1, 2]]);
+    let (zx, zy, zz) = (s[[2, 0]], s[[2, 1]], s[[2, 2]]);
+
+    let n = arr2(&[[xx + yy + zz, yz - zy, zx - xz, xy - yx],
+                   [yz - zy, xx - yy - zz, xy + yx, zx + xz],
+                   [zx - xz, xy + yx, -xx + yy - zz, yz + zy],
+                   [xy - yx, zx + xz, yz + zy, -xx - yy + zz]]);
+    // quaternion of the rotation is the eigenvector of the largest eigenvalue
+    let (_, vectors) = jacobi_eigen(n);
+    let q = vectors.column(3);
+    quaternion_matrix([q[0], q[1], q[2], q[3]])
+}
+
+// rotation matrix of the unit quaternion q0 + q1 i + q2 j + q3 k
+fn quaternion_matrix([q0, q1, q2, q3]: [f64; 4]) -> Array2<f64> {
+    arr2(&[[q0*q0 + q1*q1 - q2*q2 - q3*q3, 2.*(q1*q2 - q0*q3), 2.*(q1*q3 + q0*q2)],
+           [2.*(q1*q2 + q0*q3), q0*q0 - q1*q1 + q2*q2 - q3*q3, 2.*(q2*q3 - q0*q1)],
+           [2.*(q1*q3 - q0*q2), 2.*(q2*q3 + q0*q1), q0*q0 - q1*q1 - q2*q2 + q3*q3]])
+}
@@ -0,0 +1,44 @@
+//! configurational temperature, kB T_conf = <|grad U|^2>/<laplacian U> (Rugh; Butler et al. 1998):
+//! an estimate of the temperature from the positions alone, which agrees with the imposed one
+//! only if the sampling has equilibrated the structure at it
+use crate::units::{Temperature, KB};
+use crate::{Fuleren, Point6};
+
+// displacement of the second differences [A]
+const H: f64 = 1e-4;
+
+impl Fuleren {
+    /// |grad U|^2 [eV^2/A^2] and the laplacian of U [eV/A^2], from central differences of the
+    /// local energy of every atom and cartesian direction
+    pub fn gradient_and_laplacian(&self) -> (f64, f64) {
+        let mut work = self.clone();
+        let (mut grad2, mut laplacian) = (0., 0.);
+
+        for i in 0..self.size {
+            let local = work._neighbourhood(i, self.potential.range());
+            let old = work.positions[i].clone();
+            let e0 = work._e_local(&local);
+            for c in 0..3 {
+                let mut xyz = old.xyz();
+                xyz[c] += H;
+                work.positions[i] = Point6::from_cartesian(&xyz);
+                let e_plus = work._e_local(&local);
+                xyz[c] -= 2.*H;
+                work.positions[i] = Point6::from_cartesian(&xyz);
+                let e_minus = work._e_local(&local);
+
+                grad2 += ((e_plus - e_minus)/(2.*H)).powi(2);
+                laplacian += (e_plus + e_minus - 2.*e0)/(H*H);
+            }
+            work.positions[i] = old;
+        }
+        (grad2, laplacian)
+    }
+
+    /// single structure estimate |grad U|^2/(kB laplacian U), noisy: over a window of samples
+    /// the ratio of the averaged sums is the estimator. None for a non-positive laplacian
+    pub fn configurational_temperature(&self) -> Option<Temperature> {
+        let (grad2, laplacian) = self.gradient_and_laplacian();
+        (laplacian > 0.).then(|| Temperature(grad2/(KB*laplacian)))
+    }
+}
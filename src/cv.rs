@@ -0,0 +1,289 @@
+//! collective variables: scalar functions of the atom positions with their gradients, shared by
+//! biased sampling (umbrella windows) and the observable columns of the anneal logs
+use ndarray::prelude::*;
+
+use crate::compare::{centered, unlabelled_rmsd};
+use crate::{Fuleren, Point6};
+
+/// a collective variable s(x_1, ..., x_N)
+pub trait CollectiveVariable: Send + Sync {
+    /// column name in logs, also accepted by from_name
+    fn name(&self) -> String;
+
+    fn value(&self, f: &Fuleren) -> f64;
+
+    /// an error if the variable is not defined for structures of n atoms
+    fn check_size(&self, _n: usize) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// ds/dx of every atom (rows) and cartesian component (columns); central differences unless
+    /// the variable knows better
+    fn gradient(&self, f: &Fuleren) -> Array2<f64> {
+        let h = 1e-5;
+        let mut work = f.clone();
+        let mut grad = Array2::<f64>::zeros((f.size, 3));
+        for i in 0..f.size {
+            let old = work.positions[i].clone();
+            let xyz = [old.x(), old.y(), old.z()];
+            for c in 0..3 {
+                let mut shifted = xyz;
+                shifted[c] = xyz[c] + h;
+                work.positions[i] = Point6::from_cartesian(&shifted);
+                let s_plus = self.value(&work);
+                shifted[c] = xyz[c] - h;
+                work.positions[i] = Point6::from_cartesian(&shifted);
+                let s_minus = self.value(&work);
+                grad[[i, c]] = (s_plus - s_minus)/(2.*h);
+            }
+            work.positions[i] = old;
+        }
+        grad
+    }
+}
+
+/// collective variable by name: "radius", "asphericity", "volume", "area", "sphericity" (of the
+/// convex hull), "t_conf" (configurational temperature), "coord<c>" (atoms with c bonds),
+/// "ring<k>" (k-membered rings, "pentagons" and "hexagons" for k = 5, 6), "q<l>" or "w<l>" (Steinhardt
+/// Q_l and W_l), "rmsd:<reference>" (RMSD to a reference isomer such as C60 or to the structure of a file)
+pub fn from_name(name: &str) -> Result<Box<dyn CollectiveVariable>, String> {
+    let number = |prefix: &str| name.strip_prefix(prefix).and_then(|n| n.parse::<usize>().ok());
+    match name {
+        "radius" => Ok(Box::new(MeanRadius)),
+        "asphericity" => Ok(Box::new(Asphericity)),
+        "pentagons" => Ok(Box::new(RingCount(5))),
+        "hexagons" => Ok(Box::new(RingCount(6))),
+        "volume" => Ok(Box::new(Volume)),
+        "area" => Ok(Box::new(Area)),
+        "sphericity" => Ok(Box::new(Sphericity)),
+        "t_conf" => Ok(Box::new(ConfigurationalTemperature)),
+        _ => {
+            if let Some(reference) = name.strip_prefix("rmsd:") { Ok(Box::new(Rmsd::new(reference)?)) }
+            else if let Some(c) = number("coord") { Ok(Box::new(CoordinationCount(c))) }
+            else if let Some(k) = number("ring").filter(|&k| k >= 3) { Ok(Box::new(RingCount(k))) }
+            else if let Some(l) = number("q") { Ok(Box::new(Steinhardt(l))) }
+            else if let Some(l) = number("w") { Ok(Box::new(SteinhardtW(l))) }
+            else {
+                Err(format!("unknown collective variable {}, expected radius, asphericity, volume, area, \
+                             sphericity, t_conf, pentagons, hexagons, coord<c>, ring<k>, q<l>, w<l> or \
+                             rmsd:<reference>", name))
+            }
+        }
+    }
+}
+
+/// mean distance of the atoms from the center of mass [A]
+pub struct MeanRadius;
+
+impl CollectiveVariable for MeanRadius {
+    fn name(&self) -> String {
+        "radius".to_string()
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        let c = f.center_of_mass();
+        f.positions.iter()
+                   .map(|p| ((p.x() - c[0]).powi(2) + (p.y() - c[1]).powi(2) + (p.z() - c[2]).powi(2)).sqrt())
+                   .sum::<f64>()/f.size as f64
+    }
+
+    // (u_i - <u>)/N with u_i the unit vector from the center of mass to atom i
+    fn gradient(&self, f: &Fuleren) -> Array2<f64> {
+        let c = f.center_of_mass();
+        let n = f.size as f64;
+        let mut u = Array2::<f64>::zeros((f.size, 3));
+        for (i, p) in f.positions.iter().enumerate() {
+            let d = [p.x() - c[0], p.y() - c[1], p.z() - c[2]];
+            let r = (d[0]*d[0] + d[1]*d[1] + d[2]*d[2]).sqrt();
+            if r > 0. {
+                for k in 0..3 {
+                    u[[i, k]] = d[k]/r;
+                }
+            }
+        }
+        let mean = u.mean_axis(Axis(0)).unwrap();
+        (u - &mean)/n
+    }
+}
+
+/// relative shape anisotropy of the gyration tensor, 0 for a sphere and 1 for a line
+pub struct Asphericity;
+
+impl CollectiveVariable for Asphericity {
+    fn name(&self) -> String {
+        "asphericity".to_string()
+    }
+
+    // kappa^2 = 1 - 3 I_2/I_1^2 from the invariants of the gyration tensor, no eigenvalues needed
+    fn value(&self, f: &Fuleren) -> f64 {
+        let c = f.center_of_mass();
+        let mut g = [[0.; 3]; 3];
+        for p in f.positions.iter() {
+            let r = [p.x() - c[0], p.y() - c[1], p.z() - c[2]];
+            for a in 0..3 {
+                for b in 0..3 {
+                    g[a][b] += r[a]*r[b]/f.size as f64;
+                }
+            }
+        }
+        let i1 = g[0][0] + g[1][1] + g[2][2];
+        let i2 = g[0][0]*g[1][1] + g[1][1]*g[2][2] + g[2][2]*g[0][0]
+                 - g[0][1].powi(2) - g[1][2].powi(2) - g[0][2].powi(2);
+        if i1 > 0. { 1. - 3.*i2/i1.powi(2) } else { 0. }
+    }
+}
+
+/// number of atoms with exactly c bonds (pairs inside R1); piecewise constant, zero gradient
+pub struct CoordinationCount(pub usize);
+
+impl CollectiveVariable for CoordinationCount {
+    fn name(&self) -> String {
+        format!("coord{}", self.0)
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.coordination().iter().filter(|&&c| c == self.0).count() as f64
+    }
+
+    fn gradient(&self, f: &Fuleren) -> Array2<f64> {
+        Array2::zeros((f.size, 3))
+    }
+}
+
+/// number of k-membered rings (see ring_statistics); piecewise constant, zero gradient
+pub struct RingCount(pub usize);
+
+impl CollectiveVariable for RingCount {
+    fn name(&self) -> String {
+        format!("ring{}", self.0)
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.ring_statistics(self.0)[self.0] as f64
+    }
+
+    fn gradient(&self, f: &Fuleren) -> Array2<f64> {
+        Array2::zeros((f.size, 3))
+    }
+}
+
+/// global Steinhardt bond order Q_l (see bond_order); the gradient is numerical and ignores
+/// bonds crossing R1
+pub struct Steinhardt(pub usize);
+
+impl CollectiveVariable for Steinhardt {
+    fn name(&self) -> String {
+        format!("q{}", self.0)
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.bond_order(self.0).q
+    }
+}
+
+/// global normalized Steinhardt invariant W_l (see bond_order)
+pub struct SteinhardtW(pub usize);
+
+impl CollectiveVariable for SteinhardtW {
+    fn name(&self) -> String {
+        format!("w{}", self.0)
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.bond_order(self.0).w
+    }
+}
+
+/// enclosed volume of the convex hull [A^3]
+pub struct Volume;
+
+impl CollectiveVariable for Volume {
+    fn name(&self) -> String {
+        "volume".to_string()
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.hull().volume
+    }
+}
+
+/// surface area of the convex hull [A^2]
+pub struct Area;
+
+impl CollectiveVariable for Area {
+    fn name(&self) -> String {
+        "area".to_string()
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.hull().area
+    }
+}
+
+/// sphericity of the convex hull (see Hull::sphericity)
+pub struct Sphericity;
+
+impl CollectiveVariable for Sphericity {
+    fn name(&self) -> String {
+        "sphericity".to_string()
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.hull().sphericity()
+    }
+}
+
+/// configurational temperature of the structure [K] (see configurational), NaN for a non-positive
+/// laplacian; logged next to T it shows whether the sampling keeps up with the schedule. Single
+/// structure values are noisy, compare window averages
+pub struct ConfigurationalTemperature;
+
+impl CollectiveVariable for ConfigurationalTemperature {
+    fn name(&self) -> String {
+        "t_conf".to_string()
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        f.configurational_temperature().map_or(f64::NAN, |t| t.0)
+    }
+}
+
+/// RMSD [A] to a reference structure after optimal superposition and atom assignment (see
+/// unlabelled_rmsd), the distance of an anneal from its target isomer; NaN for a structure with
+/// another number of atoms, which check_size rejects up front. The gradient is numerical and expensive, meant for logging
+pub struct Rmsd {
+    reference: String,
+    xyz: Array2<f64>,
+}
+
+impl Rmsd {
+    /// reference by name (see Fuleren::reference) or the last frame of a structure file
+    pub fn new(reference: &str) -> Result<Rmsd, String> {
+        let f = match Fuleren::reference(reference) {
+            Some(f) => f,
+            None => Fuleren::frames_from_file(reference)?.pop()
+                                                         .ok_or_else(|| format!("{}: no structure", reference))?,
+        };
+        Ok(Rmsd { reference: reference.to_string(), xyz: centered(&f) })
+    }
+}
+
+impl CollectiveVariable for Rmsd {
+    fn name(&self) -> String {
+        format!("rmsd:{}", self.reference)
+    }
+
+    fn value(&self, f: &Fuleren) -> f64 {
+        if f.size != self.xyz.nrows() {
+            return f64::NAN
+        }
+        unlabelled_rmsd(&centered(f), &self.xyz)
+    }
+
+    fn check_size(&self, n: usize) -> Result<(), String> {
+        if n != self.xyz.nrows() {
+            return Err(format!("{}: the reference has {} atoms, the run {}", self.name(), self.xyz.nrows(), n))
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+//! results store in an SQLite database (rusqlite feature): every finished run inserts its final
+//! structure (extended XYZ text) with the energy, seed, schedule and potential (the config lines
+//! of the run) and the isomer, so the output of many run directories can be searched in one
+//! place. Several processes may write to the same file, SQLite serializes them
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+
+use crate::Fuleren;
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    n INTEGER NOT NULL,
+    seed INTEGER,
+    energy REAL NOT NULL,
+    e_per_atom REAL NOT NULL,
+    isomer TEXT,
+    source TEXT NOT NULL,
+    parameters TEXT NOT NULL,
+    structure TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS runs_n ON runs (n, e_per_atom);";
+
+/// a database of finished runs, shared by the worker threads of a runner
+pub struct ResultStore {
+    connection: Mutex<Connection>,
+    path: String,
+}
+
+/// a row of a query, everything but the parameters and the structure
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub id: i64,
+    pub time: i64, // [s] since the epoch
+    pub n: usize,
+    pub seed: Option<u64>,
+    pub energy: f64,
+    pub isomer: Option<String>, // None unless the structure is a closed cage
+    pub source: String,         // run directory or structure file the run was saved to
+}
+
+/// filters of a query, the entries come ordered by E/N
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub n: Option<usize>,
+    pub isomer: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl ResultStore {
+    /// opens the database at path, created with its table if missing
+    pub fn open(path: &str) -> Result<ResultStore, String> {
+        let err = |e: rusqlite::Error| format!("cannot open database {}: {}", path, e);
+        let connection = Connection::open(path).map_err(err)?;
+        connection.busy_timeout(Duration::from_secs(60)).map_err(err)?;
+        connection.execute_batch(SCHEMA).map_err(err)?;
+        Ok(ResultStore { connection: Mutex::new(connection), path: path.to_string() })
+    }
+
+    fn err(&self, e: rusqlite::Error) -> String {
+        format!("database {}: {}", self.path, e)
+    }
+
+    /// inserts the final structure of a run (E up to date) with its config lines; returns the id
+    pub fn insert(&self, f: &Fuleren, seed: Option<u64>, parameters: &str, source: &str) -> Result<i64, String> {
+        let mut structure = Vec::new();
+        f.write_extxyz(&mut structure, &format!("seed={}", seed.map_or("-".to_string(), |s| s.to_string())))
+         .map_err(|e| e.to_string())?;
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+        let connection = self.connection.lock().unwrap();
+        connection.execute("INSERT INTO runs (time, n, seed, energy, e_per_atom, isomer, source, parameters, structure) \
+                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                           params![time, f.size() as i64, seed.map(|s| s as i64), f.E, f.E/f.size() as f64,
+                                   f.isomer().map(|i| i.to_string()), source, parameters,
+                                   String::from_utf8_lossy(&structure)])
+                  .map_err(|e| self.err(e))?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    /// entries matching the query, lowest E/N first
+    pub fn query(&self, query: &Query) -> Result<Vec<Entry>, String> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(n) = query.n {
+            conditions.push("n = ?");
+            values.push(Value::Integer(n as i64));
+        }
+        if let Some(isomer) = &query.isomer {
+            conditions.push("isomer = ?");
+            values.push(Value::Text(isomer.clone()));
+        }
+        let mut sql = "SELECT id, time, n, seed, energy, isomer, source FROM runs".to_string();
+        if !conditions.is_empty() {
+            sql += &format!(" WHERE {}", conditions.join(" AND "));
+        }
+        sql += " ORDER BY e_per_atom, id";
+        if let Some(limit) = query.limit {
+            sql += &format!(" LIMIT {}", limit);
+        }
+
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(&sql).map_err(|e| self.err(e))?;
+        let rows = statement.query_map(params_from_iter(values), |row| {
+            Ok(Entry { id: row.get(0)?, time: row.get(1)?, n: row.get::<_, i64>(2)? as usize,
+                       seed: row.get::<_, Option<i64>>(3)?.map(|s| s as u64), energy: row.get(4)?,
+                       isomer: row.get(5)?, source: row.get(6)? })
+        }).map_err(|e| self.err(e))?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| self.err(e))
+    }
+
+    /// structure and config lines of the entry id
+    pub fn structure(&self, id: i64) -> Result<(Fuleren, String), String> {
+        let connection = self.connection.lock().unwrap();
+        let (structure, parameters): (String, String) =
+            connection.query_row("SELECT structure, parameters FROM runs WHERE id = ?1", params![id],
+                                 |row| Ok((row.get(0)?, row.get(1)?)))
+                      .map_err(|e| self.err(e))?;
+        let f = Fuleren::frames_from_str(&structure, &format!("{}#{}", self.path, id))?
+            .pop()
+            .ok_or_else(|| format!("database {}: entry {} has no structure", self.path, id))?;
+        Ok((f, parameters))
+    }
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<6} {:<12} {:<6} {:<10} {:<14.5} {:<10.5} {:<20} {}",
+               self.id, self.time, self.n, self.seed.map_or("-".to_string(), |s| s.to_string()), self.energy,
+               self.energy/self.n as f64, self.isomer.as_deref().unwrap_or("-"), self.source)
+    }
+}
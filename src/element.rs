@@ -0,0 +1,56 @@
+//! atom species: the element of every atom and its mass. The Brenner potential of this crate is
+//! the carbon one and treats every atom as carbon; the other elements are carried through the
+//! structure files and enter the dynamics, normal modes and center of mass through their masses
+use std::fmt;
+
+use crate::M_C;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Element {
+    H,
+    B,
+    #[default]
+    C,
+    N,
+    O,
+    Si,
+}
+
+impl Element {
+    pub const ALL: [Element; 6] = [Element::H, Element::B, Element::C, Element::N, Element::O, Element::Si];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Element::H => "H",
+            Element::B => "B",
+            Element::C => "C",
+            Element::N => "N",
+            Element::O => "O",
+            Element::Si => "Si",
+        }
+    }
+
+    /// standard atomic weight [amu]
+    pub fn mass(&self) -> f64 {
+        match self {
+            Element::H => 1.008,
+            Element::B => 10.81,
+            Element::C => M_C,
+            Element::N => 14.007,
+            Element::O => 15.999,
+            Element::Si => 28.085,
+        }
+    }
+
+    /// element by its symbol (case insensitive), None for an unknown one
+    pub fn from_symbol(symbol: &str) -> Option<Element> {
+        Element::ALL.into_iter().find(|e| e.symbol().eq_ignore_ascii_case(symbol))
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
@@ -0,0 +1,59 @@
+//! teaching output: the first moves of random_atom_shift on the current thread printed step by
+//! step, the proposal, the energy change, the Metropolis acceptance probability and the decision
+use std::cell::Cell;
+
+use crate::units::Beta;
+use crate::Point6;
+
+thread_local! {
+    // moves still to be explained on this thread, and the number of the next one
+    static REMAINING: Cell<usize> = const { Cell::new(0) };
+    static COUNT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// explains the next n atom moves of the current thread
+pub fn explain_moves(n: usize) {
+    REMAINING.with(|r| r.set(n));
+    COUNT.with(|c| c.set(0));
+}
+
+// true while there are moves left to explain
+pub(crate) fn active() -> bool {
+    REMAINING.with(|r| r.get() > 0)
+}
+
+// decision on a proposed move, None for a hard core rejection (no energy evaluated)
+pub(crate) struct Metropolis {
+    pub de: f64,
+    pub p_acc: f64,
+    pub u: f64,
+}
+
+// prints the move of atom i from old to new at beta and counts it
+pub(crate) fn explain(i: usize, old: &Point6, new: &Point6, beta: f64, decision: Option<Metropolis>) {
+    let n = COUNT.with(|c| { c.set(c.get() + 1); c.get() });
+    REMAINING.with(|r| r.set(r.get() - 1));
+
+    let d = ((new.x() - old.x()).powi(2) + (new.y() - old.y()).powi(2) + (new.z() - old.z()).powi(2)).sqrt();
+    println!("move {}: atom {} at beta = {} (T = {})", n, i, Beta(beta), Beta(beta).temperature());
+    println!("  proposal: r {:.4} -> {:.4} A, phi {:.4} -> {:.4}, theta {:.4} -> {:.4}, displacement {:.4} A",
+             old.r(), new.r(), old.phi(), new.phi(), old.theta(), new.theta(), d);
+    match decision {
+        None => println!("  closer than the hard core to another atom: rejected without evaluating the energy"),
+        Some(Metropolis { de, p_acc, u }) => {
+            println!("  dE = V_i(new) - V_i(old) = {:.5} eV", de);
+            if de <= 0. {
+                println!("  energy decreases: p_acc = 1");
+            }
+            else {
+                println!("  energy increases: p_acc = exp(-beta*dE) = exp({:.4}) = {:.4}", -beta*de, p_acc);
+            }
+            let accepted = u <= p_acc;
+            println!("  random u = {:.4} {} p_acc: {}", u, if accepted { "<=" } else { ">" },
+                     if accepted { "accepted" } else { "rejected, the atom goes back" });
+        }
+    }
+    if !active() {
+        println!("(end of the explained moves, the anneal continues silently)");
+    }
+}
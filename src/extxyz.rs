@@ -0,0 +1,146 @@
+use std::io::{self, Write};
+
+use tracing::warn;
+
+use crate::metadata::Metadata;
+use crate::utilities::write_atomic;
+use crate::potential::Potential;
+use crate::{parse_xyz, Element, Fuleren, Point6, StepSizes, R_CORE};
+
+impl Fuleren {
+    /// saves as extended XYZ: species, positions, per-atom energy (0.5*V_i, summing to E),
+    /// coordination, CNA environment label and atom index; the total energy and the metadata
+    /// (energy recomputed, the rest as given) go into the comment line
+    pub fn save_extxyz(&mut self, path: &str, meta: &Metadata) -> io::Result<()> {
+        self.energy_calc();
+        write_atomic(path, |f| self.write_extxyz(f, &meta.to_info()))
+    }
+
+    /// one frame in the save_extxyz format, `info` ("key=value ...") added to the comment line;
+    /// expects E to be up to date
+    pub fn write_extxyz<W: Write>(&self, f: &mut W, info: &str) -> io::Result<()> {
+        let coordination = self.coordination();
+        let cna = self.cna();
+
+        writeln!(f, "{}", self.size)?;
+        writeln!(f, "Properties=species:S:1:pos:R:3:energy:R:1:coordination:I:1:cna:S:1:id:I:1 energy={:.8} pbc=\"F F F\"{}{}",
+                 self.E, if info.is_empty() { "" } else { " " }, info)?;
+        for (i, p) in self.positions.iter().enumerate() {
+            writeln!(f, "{:<2} {:>14.8} {:>14.8} {:>14.8} {:>14.8} {} {} {}", self.species[i].symbol(), p.x(), p.y(), p.z(),
+                     0.5*self._vi(i), coordination[i], cna[i].label(), i)?;
+        }
+        Ok(())
+    }
+}
+
+/// reads all frames of an extended XYZ file (ASE convention); positions are taken from the `pos`
+/// property and elements from `species`, carbon without one (plain XYZ, without Properties=, is
+/// read as species:S:1:pos:R:3). An `id` property
+/// restores the atom order of the writer, in case another tool reordered the lines. Clusters are
+/// not periodic, so a Lattice is checked but otherwise ignored
+pub fn read_extxyz(content: &str, path: &str) -> Result<Vec<Fuleren>, String> {
+    let mut lines = content.lines().enumerate().peekable();
+    let mut frames = Vec::new();
+
+    while let Some((n, line)) = lines.next() {
+        if line.trim().is_empty() { continue }
+        let err = |n: usize, what: &str| format!("{}:{}: {}", path, n + 1, what);
+
+        let size: usize = line.trim().parse().map_err(|_| err(n, "expected the number of atoms"))?;
+        let (n_comment, comment) = lines.next().ok_or_else(|| err(n, "missing comment line"))?;
+
+        let mut pos_column = 1;
+        let mut species_column = Some(0);
+        let mut id_column = None;
+        for (key, value) in key_values(comment) {
+            match key.as_str() {
+                "Lattice" => {
+                    if value.split_whitespace().filter(|x| x.parse::<f64>().is_ok()).count() != 9 {
+                        return Err(err(n_comment, "Lattice needs 9 numbers"));
+                    }
+                    warn!(path, line = n_comment + 1, "periodic cell ignored, the structure is treated as a cluster");
+                }
+                "Properties" => {
+                    pos_column = property_offset(&value, "pos", "R", 3).ok_or_else(|| err(n_comment, "no pos:R:3 in Properties"))?;
+                    species_column = property_offset(&value, "species", "S", 1);
+                    id_column = property_offset(&value, "id", "I", 1);
+                }
+                _ => {}
+            }
+        }
+
+        let mut positions = Vec::with_capacity(size);
+        let mut species = Vec::with_capacity(size);
+        let mut ids = Vec::with_capacity(size);
+        for _ in 0..size {
+            let (n_atom, atom) = lines.next().ok_or_else(|| err(n_comment, "fewer atoms than declared"))?;
+            let cols: Vec<&str> = atom.split_whitespace().collect();
+            let xyz = cols.get(pos_column..pos_column + 3)
+                          .and_then(|c| parse_xyz(c.iter().copied()))
+                          .ok_or_else(|| err(n_atom, "cannot read the position"))?;
+            positions.push(Point6::from_cartesian(&xyz));
+            species.push(match species_column {
+                Some(c) => cols.get(c).and_then(|s| Element::from_symbol(s)).ok_or_else(|| err(n_atom, "unknown element"))?,
+                None => Element::C,
+            });
+            if let Some(c) = id_column {
+                ids.push(cols.get(c).and_then(|x| x.parse::<usize>().ok()).ok_or_else(|| err(n_atom, "cannot read the id"))?);
+            }
+        }
+        if id_column.is_some() {
+            let mut order: Vec<usize> = (0..size).collect();
+            order.sort_by_key(|&k| ids[k]);
+            if order.iter().enumerate().any(|(i, &k)| ids[k] != i) {
+                return Err(err(n_comment, "ids are not a permutation of 0..N"));
+            }
+            positions = order.iter().map(|&k| positions[k].clone()).collect();
+            species = order.iter().map(|&k| species[k]).collect();
+        }
+
+        frames.push(Fuleren { size, species, E: 0., r_core: R_CORE, steps: StepSizes::default(),
+                              potential: Potential::default(), positions: positions.into_iter().collect() });
+    }
+    Ok(frames)
+}
+
+// key=value pairs of the comment line; values may be "quoted", keys without a value are flags
+pub(crate) fn key_values(comment: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut chars = comment.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() { break }
+
+        let key: String = std::iter::from_fn(|| chars.next_if(|&c| c != '=' && !c.is_whitespace())).collect();
+        let mut value = String::from("T");
+        if chars.next_if_eq(&'=').is_some() {
+            value = if chars.next_if_eq(&'"').is_some() {
+                let v = std::iter::from_fn(|| chars.next_if(|&c| c != '"')).collect();
+                chars.next();
+                v
+            }
+            else {
+                std::iter::from_fn(|| chars.next_if(|&c| !c.is_whitespace())).collect()
+            };
+        }
+        pairs.push((key, value));
+    }
+    pairs
+}
+
+// first column of a property of the given type and width in a Properties=name:type:cols:...
+// description
+fn property_offset(properties: &str, name: &str, kind: &str, width: usize) -> Option<usize> {
+    let fields: Vec<&str> = properties.split(':').collect();
+    let mut column = 0;
+    for p in fields.chunks(3) {
+        let [p_name, p_kind, cols] = p else { return None };
+        let cols: usize = cols.parse().ok()?;
+        if *p_name == name {
+            return (*p_kind == kind && cols == width).then_some(column);
+        }
+        column += cols;
+    }
+    None
+}
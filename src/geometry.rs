@@ -0,0 +1,62 @@
+use ndarray::prelude::*;
+
+use crate::vibrations::jacobi_eigen;
+use crate::{Fuleren, Point6};
+
+impl Fuleren {
+    /// mass weighted center, the geometric one for a single element
+    pub fn center_of_mass(&self) -> [f64; 3] {
+        let m_total = self.species.iter().map(|e| e.mass()).sum::<f64>();
+        let mut c = [0.; 3];
+        for (p, e) in self.positions.iter().zip(&self.species) {
+            let w = e.mass()/m_total;
+            c[0] += w*p.x();
+            c[1] += w*p.y();
+            c[2] += w*p.z();
+        }
+        c
+    }
+
+    /// moves the center of mass to the origin; spherical coordinates (and mean_r, pcf)
+    /// assume the cluster sits there, which drifts during free 3D moves
+    pub fn recenter(&mut self) {
+        let c = self.center_of_mass();
+        for i in 0..self.size {
+            let p = &self.positions[i];
+            self.positions[i] = Point6::from_cartesian(&[p.x() - c[0], p.y() - c[1], p.z() - c[2]]);
+        }
+    }
+
+    /// recenters and rotates the cluster so its principal axes of inertia lie along x, y, z
+    /// (smallest moment along x)
+    pub fn align_principal_axes(&mut self) {
+        self.recenter();
+
+        let mut inertia = Array2::<f64>::zeros((3, 3));
+        for (p, e) in self.positions.iter().zip(&self.species) {
+            let r = [p.x(), p.y(), p.z()];
+            let r2 = r.iter().map(|x| x.powi(2)).sum::<f64>();
+            for a in 0..3 {
+                for b in 0..3 {
+                    inertia[[a, b]] += e.mass()*(if a == b { r2 } else { 0. } - r[a]*r[b]);
+                }
+            }
+        }
+        let (_, mut axes) = jacobi_eigen(inertia);
+
+        // keep it a proper rotation
+        if det3(&axes) < 0. {
+            axes.column_mut(2).mapv_inplace(|x| -x);
+        }
+
+        // new coordinates are projections on the principal axes
+        let xyz = self.xyz_array().dot(&axes);
+        self.set_xyz_array(&xyz);
+    }
+}
+
+pub fn det3(m: &Array2<f64>) -> f64 {
+    m[[0, 0]]*(m[[1, 1]]*m[[2, 2]] - m[[1, 2]]*m[[2, 1]])
+        - m[[0, 1]]*(m[[1, 0]]*m[[2, 2]] - m[[1, 2]]*m[[2, 0]])
+        + m[[0, 2]]*(m[[1, 0]]*m[[2, 1]] - m[[1, 1]]*m[[2, 0]])
+}
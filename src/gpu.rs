@@ -0,0 +1,281 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ndarray::prelude::*;
+use wgpu::util::DeviceExt;
+
+use crate::potential::{Brenner, Potential};
+use crate::Fuleren;
+
+// one invocation per (configuration, atom): configuration 0 is the structure itself,
+// configuration 1 + 6*i + 2*d + s has atom i shifted by +-h along axis d, which gives
+// the finite difference forces from a single dispatch
+const SHADER: &str = r#"
+struct Params {
+    n: u32,
+    n_configs: u32,
+    h: f32,
+    pad: f32,
+}
+
+@group(0) @binding(0) var<storage, read> pos: array<vec4<f32>>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+const PI: f32 = 3.14159265358979;
+
+fn position(p: u32, c: u32) -> vec3<f32> {
+    var r = pos[p].xyz;
+    if (c > 0u) {
+        let k = c - 1u;
+        if (p == k / 6u) {
+            let d = (k % 6u) / 2u;
+            r[d] = r[d] + select(-params.h, params.h, k % 2u == 0u);
+        }
+    }
+    return r;
+}
+
+fn f_cut(r: f32) -> f32 {
+    if (r <= R1) { return 1.0; }
+    if (r <= R2) { return 0.5*(1.0 + cos((r - R1)/(R2 - R1)*PI)); }
+    return 0.0;
+}
+
+fn v_r(r: f32) -> f32 {
+    return DE/(S - 1.0) * exp(-sqrt(2.0*S) * LAMBDA * (r - R0));
+}
+
+fn v_a(r: f32) -> f32 {
+    return DE*S/(S - 1.0) * exp(-sqrt(2.0/S) * LAMBDA * (r - R0));
+}
+
+fn g(cos_ijk: f32) -> f32 {
+    if (cos_ijk > 0.0) { return 20.0; }
+    return A0*(1.0 + C0*C0/(D0*D0) - C0*C0/(D0*D0 + (1.0 + cos_ijk)*(1.0 + cos_ijk)));
+}
+
+fn b(i: u32, j: u32, c: u32) -> f32 {
+    let ri = position(i, c);
+    let rij = position(j, c) - ri;
+    var ksi = 0.0;
+    for (var k = 0u; k < params.n; k++) {
+        if (k == i || k == j) { continue; }
+        let rik = position(k, c) - ri;
+        let fc = f_cut(length(rik));
+        if (fc > 0.0) {
+            ksi += fc*g(dot(rij, rik)/(length(rij)*length(rik)));
+        }
+    }
+    return pow(1.0 + ksi, -DEL);
+}
+
+@compute @workgroup_size(64)
+fn atom_energy(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let i = gid.x;
+    let c = gid.y;
+    if (i >= params.n) { return; }
+
+    let ri = position(i, c);
+    var vi = 0.0;
+    for (var j = 0u; j < params.n; j++) {
+        if (j == i) { continue; }
+        let r = length(position(j, c) - ri);
+        let fc = f_cut(r);
+        if (fc > 0.0) {
+            vi += fc*(v_r(r) - 0.5*(b(i, j, c) + b(j, i, c))*v_a(r));
+        }
+    }
+    out[c*params.n + i] = vi;
+}
+"#;
+
+// potential parameters are taken from the Brenner constants of the CPU kernel so both stay in sync
+fn shader_source(b: &Brenner) -> String {
+    format!("const R0: f32 = {:?};\nconst R1: f32 = {:?};\nconst R2: f32 = {:?};\nconst DE: f32 = {:?};\n\
+             const S: f32 = {:?};\nconst LAMBDA: f32 = {:?};\nconst DEL: f32 = {:?};\n\
+             const A0: f32 = {:?};\nconst C0: f32 = {:?};\nconst D0: f32 = {:?};\n{}",
+            b.r0, b.r1, b.r2, b.de, b.s, b.lambda, b.delta, b.a0, b.c0, b.d0, SHADER)
+}
+
+/// Brenner energy and forces evaluated on the GPU, in single precision
+pub struct GpuEnergy {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    brenner: Brenner, // constants compiled into the shader
+}
+
+impl GpuEnergy {
+    /// kernel for the given constants (usually f.potential.brenner); None if no usable adapter is found
+    pub fn new(brenner: &Brenner) -> Option<GpuEnergy> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("brenner"),
+            source: wgpu::ShaderSource::Wgsl(shader_source(brenner).into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("atom_energy"),
+            layout: None,
+            module: &module,
+            entry_point: Some("atom_energy"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Some(GpuEnergy { device, queue, pipeline, brenner: brenner.clone() })
+    }
+
+    /// error for a potential the kernel cannot evaluate: tabulated V_R, V_A or g, or other
+    /// Brenner constants than the kernel was built with
+    pub fn check(&self, potential: &Potential) -> Result<(), String> {
+        if potential.tables.is_some() {
+            return Err("the GPU kernel has the analytic V_R, V_A and g built in, potential tables are not supported".to_string())
+        }
+        if potential.brenner != self.brenner {
+            return Err("the GPU kernel was built for other Brenner constants than the potential has".to_string())
+        }
+        Ok(())
+    }
+
+    /// per-atom energies V_i; an error if the device is lost
+    pub fn atom_energies(&self, f: &Fuleren) -> Result<Vec<f64>, String> {
+        Ok(self.run(f, 1, 0.)?.iter().map(|&v| v as f64).collect())
+    }
+
+    pub fn energy(&self, f: &Fuleren) -> Result<f64, String> {
+        Ok(0.5*self.atom_energies(f)?.iter().sum::<f64>())
+    }
+
+    /// forces from central differences; h is large (1e-3 A) because of the f32 kernel.
+    /// An error for more atoms than a single dispatch covers or if the device is lost
+    pub fn forces(&self, f: &Fuleren) -> Result<Array2<f64>, String> {
+        let h = 1e-3;
+        let n = f.size;
+        let v = self.run(f, 6*n + 1, h as f32)?;
+
+        let mut forces = Array2::<f64>::zeros((n, 3));
+        for i in 0..n {
+            for d in 0..3 {
+                let plus = &v[(1 + 6*i + 2*d)*n..(2 + 6*i + 2*d)*n];
+                let minus = &v[(2 + 6*i + 2*d)*n..(3 + 6*i + 2*d)*n];
+                let de = 0.5*plus.iter().zip(minus).map(|(&p, &m)| p as f64 - m as f64).sum::<f64>();
+                forces[[i, d]] = -de/(2.*h);
+            }
+        }
+        Ok(forces)
+    }
+
+    // dispatches the kernel over n_configs configurations, returns V_i of all of them
+    fn run(&self, f: &Fuleren, n_configs: usize, h: f32) -> Result<Vec<f32>, String> {
+        if n_configs > 65535 {
+            return Err(format!("{} configurations of {} atoms are too many for a single GPU dispatch", n_configs, f.size))
+        }
+
+        let pos: Vec<f32> = f.positions.iter()
+                             .flat_map(|p| [p.x() as f32, p.y() as f32, p.z() as f32, 0.])
+                             .collect();
+        let params: [u32; 4] = [f.size as u32, n_configs as u32, h.to_bits(), 0];
+        let out_size = (n_configs*f.size*std::mem::size_of::<f32>()) as u64;
+
+        let pos_buf = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("positions"),
+            contents: bytemuck::cast_slice(&pos),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let params_buf = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: bytemuck::cast_slice(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let out_buf = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("energies"),
+            size: out_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size: out_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: pos_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: params_buf.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: out_buf.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(f.size.div_ceil(64) as u32, n_configs as u32, 1);
+        }
+        encoder.copy_buffer_to_buffer(&out_buf, 0, &staging, 0, out_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        self.device.poll(wgpu::PollType::wait_indefinitely()).map_err(|e| format!("GPU device lost: {}", e))?;
+        let data = slice.get_mapped_range();
+        Ok(bytemuck::cast_slice::<u8, f32>(&data).to_vec())
+    }
+}
+
+impl Fuleren {
+    /// energy_calc on the GPU; the dispersion, torsion, Coulomb, shell and wall terms, if any, are
+    /// added on the CPU. An error for a potential with tables (see GpuEnergy::check)
+    pub fn energy_calc_gpu(&mut self, gpu: &GpuEnergy) -> Result<f64, String> {
+        gpu.check(&self.potential)?;
+        self.E = gpu.energy(self)? + self.dispersion_energy() + self.torsion_energy() + self.coulomb_energy()
+                 + self.shell_energy() + self.wall_energy();
+        Ok(self.E)
+    }
+}
+
+thread_local! {
+    // kernel energy_calc of the current thread runs on, see KernelGuard
+    static KERNEL: RefCell<Option<Rc<GpuEnergy>>> = const { RefCell::new(None) };
+}
+
+/// energy_calc of the current thread on the GPU while alive (an anneal with gpu on), the kernel
+/// before it back on drop
+pub(crate) struct KernelGuard(Option<Rc<GpuEnergy>>);
+
+impl KernelGuard {
+    /// a kernel for the potential of f; an error without a usable adapter or for a potential the
+    /// kernel cannot evaluate
+    pub(crate) fn install(f: &Fuleren) -> Result<KernelGuard, String> {
+        let kernel = GpuEnergy::new(&f.potential.brenner).ok_or("no usable GPU adapter found")?;
+        kernel.check(&f.potential)?;
+        Ok(KernelGuard(KERNEL.with(|k| k.replace(Some(Rc::new(kernel))))))
+    }
+}
+
+impl Drop for KernelGuard {
+    fn drop(&mut self) {
+        KERNEL.with(|k| *k.borrow_mut() = self.0.take());
+    }
+}
+
+// energy_calc_gpu on the kernel of the current thread, None without one or if it fails (after
+// a warning), so that the energy is computed on the CPU
+pub(crate) fn thread_energy(f: &mut Fuleren) -> Option<f64> {
+    let kernel = KERNEL.with(|k| k.borrow().clone())?;
+    match f.energy_calc_gpu(&kernel) {
+        Ok(e) => Some(e),
+        Err(e) => {
+            tracing::warn!(error = %e, "GPU energy failed, computed on the CPU");
+            None
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! bond graph export (bonds are pairs inside R1) for graph tools: GraphML for networkx and Gephi,
+//! DOT for graphviz. Nodes carry the coordination, per-atom energy (0.5*V_i) and position
+use std::io::{self, Write};
+
+use crate::utilities::write_atomic;
+use crate::{Fuleren, R1};
+
+impl Fuleren {
+    /// saves the bond graph as GraphML or DOT, chosen by the extension (.graphml, .dot or .gv);
+    /// expects E to be up to date
+    pub fn save_graph(&self, path: &str) -> Result<(), String> {
+        let result = match path.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()).as_deref() {
+            Some("graphml") => write_atomic(path, |f| self.write_graphml(f)),
+            Some("dot") | Some("gv") => write_atomic(path, |f| self.write_dot(f)),
+            _ => return Err(format!("{}: unknown graph format, expected .graphml, .dot or .gv", path)),
+        };
+        result.map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+
+    pub fn write_graphml<W: Write>(&self, f: &mut W) -> io::Result<()> {
+        let bonds = self.bond_graph(R1);
+        writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(f, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+        for (key, name, kind) in [("d0", "coordination", "int"), ("d1", "energy", "double"),
+                                  ("d2", "x", "double"), ("d3", "y", "double"), ("d4", "z", "double")] {
+            writeln!(f, r#"  <key id="{}" for="node" attr.name="{}" attr.type="{}"/>"#, key, name, kind)?;
+        }
+        writeln!(f, r#"  <key id="d5" for="edge" attr.name="length" attr.type="double"/>"#)?;
+        writeln!(f, r#"  <graph id="N{}" edgedefault="undirected">"#, self.size)?;
+        for (i, p) in self.positions.iter().enumerate() {
+            writeln!(f, r#"    <node id="n{}"><data key="d0">{}</data><data key="d1">{:.8}</data><data key="d2">{:.8}</data><data key="d3">{:.8}</data><data key="d4">{:.8}</data></node>"#,
+                     i, bonds[i].len(), 0.5*self._vi(i), p.x(), p.y(), p.z())?;
+        }
+        for (i, j) in edges(&bonds) {
+            writeln!(f, r#"    <edge source="n{}" target="n{}"><data key="d5">{:.6}</data></edge>"#, i, j, self._r_ij(i, j))?;
+        }
+        writeln!(f, "  </graph>")?;
+        writeln!(f, "</graphml>")
+    }
+
+    pub fn write_dot<W: Write>(&self, f: &mut W) -> io::Result<()> {
+        let bonds = self.bond_graph(R1);
+        writeln!(f, "graph N{} {{", self.size)?;
+        for (i, neighbours) in bonds.iter().enumerate() {
+            writeln!(f, "  {} [coordination={}, energy={:.8}];", i, neighbours.len(), 0.5*self._vi(i))?;
+        }
+        for (i, j) in edges(&bonds) {
+            writeln!(f, "  {} -- {} [length={:.6}];", i, j, self._r_ij(i, j))?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+// every bond once, i < j
+fn edges(bonds: &[Vec<usize>]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    bonds.iter().enumerate().flat_map(|(i, b)| b.iter().filter(move |&&j| j > i).map(move |&j| (i, j)))
+}
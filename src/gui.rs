@@ -0,0 +1,190 @@
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use eframe::egui;
+use egui_plot::{Line, Plot, PlotPoints};
+
+use crate::anneal::{Control, Schedule};
+use crate::potential::Potential;
+use crate::{seed_rng, Fuleren};
+
+// iterations between updates of the window
+const REFRESH: usize = 50;
+
+// state shared by the window and the anneal thread
+#[derive(Default)]
+struct Shared {
+    energies: Vec<[f64; 2]>, // (it, E/N)
+    positions: Vec<[f64; 3]>,
+    it: usize,
+    beta: f64,
+    running: bool,
+    paused: bool,
+    stop: bool,
+}
+
+/// interactive anneals: the schedule is set with sliders between runs, a run can be paused,
+/// resumed and stopped, E/N and the structure are drawn while it runs
+struct App {
+    n: usize,
+    r_start: f64,
+    cage: bool,
+    seed: u64,
+    schedule: Schedule,
+    potential: Potential,
+    shared: Arc<Mutex<Shared>>,
+    worker: Option<JoinHandle<()>>,
+    error: Option<String>,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl App {
+    fn start(&mut self, ctx: &egui::Context) {
+        let mut f = if self.cage {
+            match Fuleren::spiral_cage(self.n) {
+                Ok(f) => f,
+                Err(e) => { self.error = Some(e); return }
+            }
+        }
+        else {
+            let mut f = Fuleren::new(self.n);
+            f.randomize_on_sphere(self.r_start);
+            f
+        };
+        if let Err(e) = f.set_potential(self.potential.clone()) { self.error = Some(e); return }
+        f.energy_calc();
+        self.error = None;
+        *self.shared.lock().unwrap() = Shared { running: true, positions: cartesian(&f), ..Default::default() };
+
+        let (shared, schedule, seed, ctx) = (self.shared.clone(), self.schedule.clone(), self.seed, ctx.clone());
+        self.worker = Some(thread::spawn(move || {
+            seed_rng(seed);
+            f.anneal_with(&schedule, |f, step| {
+                loop {
+                    let s = shared.lock().unwrap();
+                    if s.stop { return Control::Stop }
+                    if !s.paused { break }
+                    drop(s);
+                    thread::sleep(Duration::from_millis(20));
+                }
+                if step.it % REFRESH == 0 {
+                    let mut s = shared.lock().unwrap();
+                    s.energies.push([step.it as f64, f.E/f.size as f64]);
+                    s.positions = cartesian(f);
+                    (s.it, s.beta) = (step.it, step.beta);
+                    ctx.request_repaint();
+                }
+                Control::Continue
+            });
+            let mut s = shared.lock().unwrap();
+            s.positions = cartesian(&f);
+            s.running = false;
+            ctx.request_repaint();
+        }));
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        let running = self.shared.lock().unwrap().running;
+
+        ui.add_enabled_ui(!running, |ui| {
+            ui.add(egui::Slider::new(&mut self.n, 20..=100).text("N"));
+            ui.checkbox(&mut self.cage, "start from a spiral cage");
+            ui.add_enabled(!self.cage, egui::Slider::new(&mut self.r_start, 1.0..=5.0).text("r_start [A]"));
+            ui.add(egui::DragValue::new(&mut self.seed).prefix("seed "));
+            ui.separator();
+            ui.add(egui::Slider::new(&mut self.schedule.beta_min, 0.1..=10.).logarithmic(true).text("beta_min"));
+            ui.add(egui::Slider::new(&mut self.schedule.beta_max, 1.0..=1000.).logarithmic(true).text("beta_max"));
+            ui.add(egui::Slider::new(&mut self.schedule.p, 0.5..=5.).text("p"));
+            ui.add(egui::Slider::new(&mut self.schedule.it_max, 1000..=1_000_000).logarithmic(true).text("it_max"));
+            ui.label(format!("T: {:.0} K to {:.0} K", self.schedule.temperature(0).0,
+                             self.schedule.temperature(self.schedule.it_max).0));
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!running, egui::Button::new("Start")).clicked() {
+                // the previous run has ended, only collect its thread
+                if let Some(worker) = self.worker.take() { worker.join().ok(); }
+                self.start(ui.ctx());
+            }
+            let mut s = self.shared.lock().unwrap();
+            let label = if s.paused { "Resume" } else { "Pause" };
+            if ui.add_enabled(running, egui::Button::new(label)).clicked() {
+                s.paused = !s.paused;
+            }
+            if ui.add_enabled(running, egui::Button::new("Stop")).clicked() {
+                s.stop = true;
+            }
+        });
+
+        let s = self.shared.lock().unwrap();
+        ui.label(format!("it: {}  beta: {:.3}", s.it, s.beta));
+        if let Some(&[_, e]) = s.energies.last() {
+            ui.label(format!("E/N: {:.5} eV", e));
+        }
+        if let Some(e) = &self.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+
+    // positions rotated by yaw and pitch and projected onto the xy plane; drag to rotate
+    fn point_cloud(&mut self, ui: &mut egui::Ui) {
+        let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::drag());
+        let delta = response.drag_delta();
+        self.yaw += delta.x*0.01;
+        self.pitch += delta.y*0.01;
+
+        let rect = response.rect;
+        let positions = self.shared.lock().unwrap().positions.clone();
+        let extent = positions.iter().map(|p| p.iter().map(|x| x*x).sum::<f64>().sqrt()).fold(1., f64::max) as f32;
+        let scale = 0.45*rect.width().min(rect.height())/extent;
+        let (sy, cy, sp, cp) = (self.yaw.sin(), self.yaw.cos(), self.pitch.sin(), self.pitch.cos());
+
+        let mut projected: Vec<(f32, egui::Pos2)> = positions.iter().map(|p| {
+            let (x, y, z) = (p[0] as f32, p[1] as f32, p[2] as f32);
+            let (x, z) = (cy*x + sy*z, -sy*x + cy*z);
+            let (y, z) = (cp*y - sp*z, sp*y + cp*z);
+            (z, rect.center() + egui::vec2(x, -y)*scale)
+        }).collect();
+        // back to front, near atoms drawn over far ones
+        projected.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (z, pos) in projected {
+            let shade = (150. + 100.*z/extent).clamp(50., 255.) as u8;
+            painter.circle_filled(pos, 0.08*scale, egui::Color32::from_gray(shade));
+        }
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("controls").show(ctx, |ui| self.controls(ui));
+        egui::TopBottomPanel::bottom("energy").resizable(true).default_height(200.).show(ctx, |ui| {
+            let points = PlotPoints::from(self.shared.lock().unwrap().energies.clone());
+            Plot::new("E/N").x_axis_label("it").y_axis_label("E/N [eV]")
+                            .show(ui, |plot| plot.line(Line::new(points)));
+        });
+        egui::CentralPanel::default().show(ctx, |ui| self.point_cloud(ui));
+    }
+}
+
+// closing the window stops a running anneal
+impl Drop for App {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().stop = true;
+        if let Some(worker) = self.worker.take() { worker.join().ok(); }
+    }
+}
+
+fn cartesian(f: &Fuleren) -> Vec<[f64; 3]> {
+    f.positions.iter().map(|p| [p.x(), p.y(), p.z()]).collect()
+}
+
+/// opens the window with the schedule and potential as the initial settings; blocks until it is closed
+pub fn run(schedule: Schedule, potential: Potential, seed: u64) -> Result<(), String> {
+    let app = App { n: 60, r_start: 2.5, cage: false, seed, schedule: Schedule { calibration: None, ..schedule },
+                    potential, shared: Arc::default(), worker: None, error: None, yaw: 0., pitch: 0. };
+    eframe::run_native("LAB7", eframe::NativeOptions::default(), Box::new(|_cc| Ok(Box::new(app))))
+        .map_err(|e| e.to_string())
+}
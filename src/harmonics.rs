@@ -0,0 +1,64 @@
+//! spherical harmonics Y_lm(theta, phi), theta the polar angle; complex values as (re, im)
+use std::f64::consts::PI;
+
+/// associated Legendre function P_l^m(x), 0 <= m <= l, |x| <= 1, with the Condon-Shortley phase
+pub fn legendre(l: usize, m: usize, x: f64) -> f64 {
+    // P_m^m from the closed form, then upwards in l
+    let mut pmm = 1.;
+    let s = ((1. - x)*(1. + x)).max(0.).sqrt();
+    let mut odd = 1.;
+    for _ in 0..m {
+        pmm *= -odd*s;
+        odd += 2.;
+    }
+    if l == m {
+        return pmm
+    }
+    let mut pmm1 = x*(2*m + 1) as f64*pmm;
+    for ll in m + 2..=l {
+        let pll = (x*(2*ll - 1) as f64*pmm1 - (ll + m - 1) as f64*pmm)/(ll - m) as f64;
+        pmm = pmm1;
+        pmm1 = pll;
+    }
+    pmm1
+}
+
+/// Y_lm(theta, phi) for -l <= m <= l
+pub fn spherical_harmonic(l: usize, m: i64, theta: f64, phi: f64) -> (f64, f64) {
+    let ma = m.unsigned_abs() as usize;
+    // (l-|m|)!/(l+|m|)! as a product, the factorials overflow for the larger l
+    let ratio = (l - ma + 1..=l + ma).fold(1., |r, k| r/k as f64);
+    let norm = ((2*l + 1) as f64/(4.*PI)*ratio).sqrt()*legendre(l, ma, theta.cos());
+    let (re, im) = (norm*(ma as f64*phi).cos(), norm*(ma as f64*phi).sin());
+
+    // Y_l,-m = (-1)^m conj(Y_lm)
+    if m >= 0 { (re, im) }
+    else if ma.is_multiple_of(2) { (re, -im) }
+    else { (-re, im) }
+}
+
+/// polar and azimuthal angle of a vector
+pub fn angles(v: &[f64; 3]) -> (f64, f64) {
+    let r = (v[0]*v[0] + v[1]*v[1] + v[2]*v[2]).sqrt();
+    ((v[2]/r).clamp(-1., 1.).acos(), v[1].atan2(v[0]))
+}
+
+/// Wigner 3j symbol (j1 j2 j3; m1 m2 m3) from the Racah formula
+pub fn wigner_3j(j1: i64, j2: i64, j3: i64, m1: i64, m2: i64, m3: i64) -> f64 {
+    if m1 + m2 + m3 != 0 || j3 < (j1 - j2).abs() || j3 > j1 + j2 || m1.abs() > j1 || m2.abs() > j2 || m3.abs() > j3 {
+        return 0.
+    }
+    let fact = |n: i64| (1..=n).fold(1., |f, k| f*k as f64);
+    let triangle = fact(j1 + j2 - j3)*fact(j1 - j2 + j3)*fact(-j1 + j2 + j3)/fact(j1 + j2 + j3 + 1);
+    let front = (triangle*fact(j1 + m1)*fact(j1 - m1)*fact(j2 + m2)*fact(j2 - m2)*fact(j3 + m3)*fact(j3 - m3)).sqrt();
+
+    // all factorial arguments non-negative
+    let k_min = 0.max(j2 - j3 - m1).max(j1 - j3 + m2);
+    let k_max = (j1 + j2 - j3).min(j1 - m1).min(j2 + m2);
+    let sum = (k_min..=k_max).map(|k| {
+        let sign = if k % 2 == 0 { 1. } else { -1. };
+        sign/(fact(k)*fact(j3 - j2 + k + m1)*fact(j3 - j1 + k - m2)*fact(j1 + j2 - j3 - k)*fact(j1 - k - m1)*fact(j2 - k + m2))
+    }).sum::<f64>();
+    let sign = if (j1 - j2 - m3).rem_euclid(2) == 0 { 1. } else { -1. };
+    sign*front*sum
+}
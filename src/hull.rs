@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::f64::consts::PI;
+
+use crate::Fuleren;
+
+/// convex hull of the atoms: surface area [A^2] and enclosed volume [A^3]. Once the cage closes
+/// the volume stops growing and levels off, open flakes and blobs stay below it
+#[derive(Debug, Clone, Copy)]
+pub struct Hull {
+    pub area: f64,
+    pub volume: f64,
+}
+
+impl Hull {
+    /// area of the sphere of the same volume over the hull area, 1 for a sphere
+    pub fn sphericity(&self) -> f64 {
+        if self.area > 0. { PI.powf(1./3.)*(6.*self.volume).powf(2./3.)/self.area } else { 0. }
+    }
+}
+
+impl Fuleren {
+    pub fn hull(&self) -> Hull {
+        let points: Vec<[f64; 3]> = self.positions.iter().map(|p| [p.x(), p.y(), p.z()]).collect();
+        let faces = convex_hull(&points);
+
+        let o = self.center_of_mass();
+        let (mut area, mut volume) = (0., 0.);
+        for &[a, b, c] in faces.iter() {
+            let (a, b, c) = (sub(&points[a], &o), sub(&points[b], &o), sub(&points[c], &o));
+            area += 0.5*norm(&cross(&sub(&b, &a), &sub(&c, &a)));
+            volume += dot(&a, &cross(&b, &c))/6.;
+        }
+        Hull { area, volume }
+    }
+}
+
+/// triangles of the convex hull, counter-clockwise seen from outside; empty for fewer than 4
+/// points or if all of them lie in a plane
+pub fn convex_hull(points: &[[f64; 3]]) -> Vec<[usize; 3]> {
+    let eps = 1e-9;
+    let Some(tetrahedron) = _initial_tetrahedron(points, eps) else { return Vec::new() };
+    let [i0, i1, i2, i3] = tetrahedron;
+
+    // orient the first faces outwards, away from the 4th vertex
+    let mut faces: Vec<[usize; 3]> = Vec::new();
+    for (face, opposite) in [([i0, i1, i2], i3), ([i0, i1, i3], i2), ([i0, i2, i3], i1), ([i1, i2, i3], i0)] {
+        if _height(points, &face, &points[opposite]) > 0. {
+            faces.push([face[0], face[2], face[1]]);
+        }
+        else {
+            faces.push(face);
+        }
+    }
+
+    for p in (0..points.len()).filter(|p| !tetrahedron.contains(p)) {
+        let visible: Vec<bool> = faces.iter().map(|f| _height(points, f, &points[p]) > eps).collect();
+        if !visible.contains(&true) { continue }
+
+        // horizon: edges of visible faces whose other face is hidden, kept in their orientation
+        let edges: HashSet<(usize, usize)> = faces.iter()
+                                                  .zip(visible.iter())
+                                                  .filter(|(_, &v)| v)
+                                                  .flat_map(|(f, _)| (0..3).map(move |e| (f[e], f[(e + 1)%3])))
+                                                  .collect();
+        let mut horizon: Vec<(usize, usize)> = edges.iter().copied().filter(|&(a, b)| !edges.contains(&(b, a))).collect();
+        horizon.sort_unstable(); // same faces in the same order on every run
+
+        let mut kept: Vec<[usize; 3]> = faces.iter().zip(visible.iter()).filter(|(_, &v)| !v).map(|(f, _)| *f).collect();
+        kept.extend(horizon.into_iter().map(|(a, b)| [a, b, p]));
+        faces = kept;
+    }
+    faces
+}
+
+// signed distance (times twice the face area) of point p above the plane of the face
+fn _height(points: &[[f64; 3]], face: &[usize; 3], p: &[f64; 3]) -> f64 {
+    let (a, b, c) = (&points[face[0]], &points[face[1]], &points[face[2]]);
+    dot(&cross(&sub(b, a), &sub(c, a)), &sub(p, a))
+}
+
+// four points spanning a volume: the farthest pair, the farthest point from their line and from their plane
+fn _initial_tetrahedron(points: &[[f64; 3]], eps: f64) -> Option<[usize; 4]> {
+    if points.len() < 4 {
+        return None
+    }
+    let farthest = |score: &dyn Fn(&[f64; 3]) -> f64| {
+        (0..points.len()).max_by(|&a, &b| score(&points[a]).total_cmp(&score(&points[b]))).unwrap()
+    };
+    let i0 = 0;
+    let i1 = farthest(&|p| norm(&sub(p, &points[i0])));
+    let line = sub(&points[i1], &points[i0]);
+    let i2 = farthest(&|p| norm(&cross(&line, &sub(p, &points[i0]))));
+    let normal = cross(&line, &sub(&points[i2], &points[i0]));
+    let i3 = farthest(&|p| dot(&normal, &sub(p, &points[i0])).abs());
+
+    if dot(&normal, &sub(&points[i3], &points[i0])).abs() <= eps || norm(&normal) <= eps {
+        return None
+    }
+    Some([i0, i1, i2, i3])
+}
+
+fn sub(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[1]*b[2] - a[2]*b[1], a[2]*b[0] - a[0]*b[2], a[0]*b[1] - a[1]*b[0]]
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+
+fn norm(a: &[f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
@@ -0,0 +1,201 @@
+//! fullerene isomer identification by the face spiral (Fowler and Manolopoulos, An Atlas of
+//! Fullerenes): the faces of a closed cage are unrolled in a spiral from every start face, second
+//! face and sense, and the lexicographically smallest list of pentagon positions is the canonical
+//! spiral. The Atlas numbers the isomers of a size in the order of their canonical spirals, found
+//! here by winding up all spirals of that size (see spiral_cage)
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::cage::windup;
+use crate::{Fuleren, R1};
+
+/// largest cage numbered by enumerating its isomers (C50 takes some seconds, the time grows
+/// steeply with the size); larger cages are identified by the canonical spiral only
+pub const ISOMER_ID_MAX_ATOMS: usize = 50;
+
+// canonical spirals of all isomers of the sizes enumerated so far, in Atlas order
+static CATALOGUE: Mutex<Vec<(usize, Vec<Vec<usize>>)>> = Mutex::new(Vec::new());
+
+/// a closed cage: the pentagon positions in its canonical spiral (from 1) and the isomer number
+/// of the Atlas, None above ISOMER_ID_MAX_ATOMS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Isomer {
+    pub n: usize,
+    pub spiral: Vec<usize>,
+    pub id: Option<usize>,
+}
+
+/// C40:38, or C60[1,7,9,11,13,15,18,20,22,24,26,32] (the canonical spiral) without a number
+impl fmt::Display for Isomer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "C{}:{}", self.n, id),
+            None => {
+                let spiral = self.spiral.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",");
+                write!(f, "C{}[{}]", self.n, spiral)
+            }
+        }
+    }
+}
+
+impl Fuleren {
+    /// the isomer of a closed fullerene cage, every atom with 3 bonds (pairs inside R1) and the
+    /// faces 12 pentagons and n/2 - 10 hexagons; None for anything else
+    pub fn isomer(&self) -> Option<Isomer> {
+        let bonds = self.bond_graph(R1);
+        if bonds.iter().any(|b| b.len() != 3) {
+            return None
+        }
+        let faces = self.rings(6);
+        if faces.len() != self.size/2 + 2 || faces.iter().filter(|f| f.len() == 5).count() != 12
+           || faces.iter().any(|f| f.len() < 5) {
+            return None
+        }
+
+        // faces sharing a bond are neighbours in the dual
+        let shares_bond = |a: &[usize], b: &[usize]| {
+            let common: Vec<usize> = a.iter().copied().filter(|i| b.contains(i)).collect();
+            common.len() == 2 && bonds[common[0]].contains(&common[1])
+        };
+        let dual: Vec<Vec<usize>> = (0..faces.len())
+            .map(|a| (0..faces.len()).filter(|&b| b != a && shares_bond(&faces[a], &faces[b])).collect())
+            .collect();
+        if dual.iter().zip(&faces).any(|(d, f)| d.len() != f.len()) {
+            return None
+        }
+
+        let spiral = canonical_spiral(&dual)?;
+        let id = if self.size <= ISOMER_ID_MAX_ATOMS { isomer_id(self.size, &spiral) } else { None };
+        Some(Isomer { n: self.size, spiral, id })
+    }
+}
+
+// number of the canonical spiral among the isomers of n atoms, enumerated on first use
+fn isomer_id(n: usize, spiral: &[usize]) -> Option<usize> {
+    let mut catalogue = CATALOGUE.lock().unwrap_or_else(|e| e.into_inner());
+    let k = match catalogue.iter().position(|(m, _)| *m == n) {
+        Some(k) => k,
+        None => {
+            catalogue.push((n, enumerate(n)));
+            catalogue.len() - 1
+        }
+    };
+    catalogue[k].1.iter().position(|s| s == spiral).map(|k| k + 1)
+}
+
+// canonical spirals of all isomers of n atoms in lexicographic order: every choice of the 12
+// pentagon positions that winds up and is the canonical spiral of its cage. The canonical spiral
+// starts at a pentagon, so the first position is fixed
+fn enumerate(n: usize) -> Vec<Vec<usize>> {
+    let n_faces = n/2 + 2;
+    let mut isomers = Vec::new();
+    let mut p: Vec<usize> = (0..12).collect();
+    loop {
+        let mut spiral = vec![6; n_faces];
+        p.iter().for_each(|&k| spiral[k] = 5);
+        let mut last = 11;
+        match windup(&spiral) {
+            Ok(dual) => {
+                let positions: Vec<usize> = p.iter().map(|k| k + 1).collect();
+                if canonical_spiral(&dual).as_ref() == Some(&positions) {
+                    isomers.push(positions);
+                }
+            }
+            // every spiral with the same faces up to the failing one fails too: only the
+            // pentagons up to it are advanced
+            Err(k) => last = p.iter().rposition(|&q| q <= k).unwrap_or(0),
+        }
+        let Some(k) = (1..=last).rev().find(|&k| p[k] < n_faces - 12 + k) else { break };
+        p[k] += 1;
+        for m in k + 1..12 {
+            p[m] = p[m - 1] + 1;
+        }
+    }
+    isomers
+}
+
+// smallest pentagon positions (from 1) over the spirals of every start, second face and sense
+fn canonical_spiral(dual: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let rotation = rotation(dual)?;
+    let mut best: Option<Vec<usize>> = None;
+    for f1 in 0..rotation.len() {
+        for &f2 in &rotation[f1] {
+            for reverse in [false, true] {
+                let Some(order) = spiral(&rotation, f1, f2, reverse) else { continue };
+                let pentagons: Vec<usize> = order.iter()
+                                                 .enumerate()
+                                                 .filter(|&(_, &f)| rotation[f].len() == 5)
+                                                 .map(|(k, _)| k + 1)
+                                                 .collect();
+                if best.as_ref().is_none_or(|b| pentagons < *b) {
+                    best = Some(pentagons);
+                }
+            }
+        }
+    }
+    best
+}
+
+// neighbours of every face of the dual in cyclic order, all in the same sense: if h follows g
+// around f, f follows h around g. None unless the neighbours of every face form one cycle
+fn rotation(dual: &[Vec<usize>]) -> Option<Vec<Vec<usize>>> {
+    let adjacent = |a: usize, b: usize| dual[a].contains(&b);
+    // the neighbours of f in the cycle starting with g, h
+    let walk = |f: usize, g: usize, h: usize| -> Option<Vec<usize>> {
+        let mut cycle = vec![g, h];
+        while cycle.len() < dual[f].len() {
+            let last = *cycle.last()?;
+            cycle.push(dual[f].iter().copied().find(|&x| adjacent(last, x) && !cycle.contains(&x))?);
+        }
+        adjacent(*cycle.last()?, g).then_some(cycle)
+    };
+
+    let mut cycles: Vec<Option<Vec<usize>>> = vec![None; dual.len()];
+    let g = *dual.first()?.first()?;
+    cycles[0] = Some(walk(0, g, dual[0].iter().copied().find(|&h| adjacent(g, h))?)?);
+    let mut queue = VecDeque::from([0]);
+    while let Some(f) = queue.pop_front() {
+        let cycle = cycles[f].clone()?;
+        for (k, &g) in cycle.iter().enumerate() {
+            let h = cycle[(k + 1) % cycle.len()];
+            match &cycles[g] {
+                None => {
+                    cycles[g] = Some(walk(g, h, f)?);
+                    queue.push_back(g);
+                }
+                Some(c) => {
+                    let m = c.iter().position(|&x| x == h)?;
+                    if c[(m + 1) % c.len()] != f { return None }
+                }
+            }
+        }
+    }
+    cycles.into_iter().collect()
+}
+
+// faces in the order of the spiral starting with f1, f2 (None if it gets stuck): every next face
+// follows the last one around the earliest face with neighbours left
+fn spiral(rotation: &[Vec<usize>], f1: usize, f2: usize, reverse: bool) -> Option<Vec<usize>> {
+    let turn = |f: usize, g: usize| {
+        let c = &rotation[f];
+        let m = c.iter().position(|&x| x == g)?;
+        Some(if reverse { c[(m + c.len() - 1) % c.len()] } else { c[(m + 1) % c.len()] })
+    };
+    let mut used = vec![false; rotation.len()];
+    let mut order = vec![f1, f2];
+    used[f1] = true;
+    used[f2] = true;
+    let mut open = 0;
+    while order.len() < rotation.len() {
+        while rotation[order[open]].iter().all(|&g| used[g]) {
+            open += 1;
+            if open == order.len() { return None }
+        }
+        let next = turn(order[open], *order.last()?)?;
+        if used[next] { return None }
+        used[next] = true;
+        order.push(next);
+    }
+    Some(order)
+}
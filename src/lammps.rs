@@ -0,0 +1,136 @@
+//! cross-check of the energies against LAMMPS: a structure is exported as a LAMMPS data file and
+//! evaluated with `run 0`, or the energy is read from an existing LAMMPS log. The pair style is up
+//! to the user; LAMMPS does not ship the Brenner (1990) parameter set I used here, so with the
+//! default REBO (2002) differences of a few tenths of eV/atom are expected and only a potential
+//! file transcribing the same parameters should agree within the tolerance
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+
+use crate::utilities::write_atomic;
+use crate::{Element, Fuleren};
+
+const VACUUM: f64 = 10.; // distance from the atoms to the walls of the non-periodic box [A]
+
+/// how LAMMPS is run
+pub struct LammpsCheck {
+    pub binary: String,     // LAMMPS executable
+    pub pair_style: String, // e.g. "rebo" or "tersoff"
+    pub pair_coeff: String, // e.g. "* * CH.rebo C"
+    pub tolerance: f64,     // |dE| per atom [eV] above which the energies disagree
+}
+
+impl Default for LammpsCheck {
+    fn default() -> Self {
+        LammpsCheck { binary: "lmp".to_string(),
+                      pair_style: "rebo".to_string(),
+                      pair_coeff: "* * CH.rebo C".to_string(),
+                      tolerance: 1e-3 }
+    }
+}
+
+/// energy of one structure here and in LAMMPS
+pub struct Validation {
+    pub n: usize,
+    pub e: f64,
+    pub e_lammps: f64,
+}
+
+impl Validation {
+    /// (E_lammps - E)/N [eV]
+    pub fn de_per_atom(&self) -> f64 {
+        (self.e_lammps - self.e)/self.n as f64
+    }
+}
+
+impl LammpsCheck {
+    /// writes structure.data and in.validate into work_dir, runs LAMMPS there and compares the
+    /// potential energy of the log with energy_calc
+    pub fn run(&self, f: &mut Fuleren, work_dir: &str) -> Result<Validation, String> {
+        let dir = Path::new(work_dir);
+        f.save_lammps_data(&dir.join("structure.data").to_string_lossy()).map_err(|e| e.to_string())?;
+        write_atomic(&dir.join("in.validate").to_string_lossy(), |out| {
+            writeln!(out, "units metal")?;
+            writeln!(out, "atom_style atomic")?;
+            writeln!(out, "boundary f f f")?;
+            writeln!(out, "read_data structure.data")?;
+            writeln!(out, "pair_style {}", self.pair_style)?;
+            writeln!(out, "pair_coeff {}", self.pair_coeff)?;
+            writeln!(out, "thermo_style custom step pe")?;
+            writeln!(out, "run 0")
+        }).map_err(|e| e.to_string())?;
+
+        let status = Command::new(&self.binary).args(["-in", "in.validate", "-log", "log.lammps", "-screen", "none"])
+                                               .current_dir(dir)
+                                               .status()
+                                               .map_err(|e| format!("cannot run {}: {}", self.binary, e))?;
+        if !status.success() {
+            return Err(format!("{} failed ({}), see {}", self.binary, status, dir.join("log.lammps").display()));
+        }
+        self.compare_log(f, &dir.join("log.lammps").to_string_lossy())
+    }
+
+    /// compares energy_calc with the potential energy of an existing LAMMPS log of the same structure
+    pub fn compare_log(&self, f: &mut Fuleren, log: &str) -> Result<Validation, String> {
+        Ok(Validation { n: f.size, e: f.energy_calc(), e_lammps: energy_from_log(log)? })
+    }
+
+    pub fn agrees(&self, v: &Validation) -> bool {
+        v.de_per_atom().abs() <= self.tolerance
+    }
+}
+
+impl Fuleren {
+    /// saves as a LAMMPS data file (atom_style atomic, metal units) in a box with VACUUM around the
+    /// atoms; one atom type per element, numbered in order of appearance
+    pub fn save_lammps_data(&self, path: &str) -> io::Result<()> {
+        let half = self.positions.iter()
+                                 .map(|p| p.x().abs().max(p.y().abs()).max(p.z().abs()))
+                                 .fold(0., f64::max) + VACUUM;
+        let mut types: Vec<Element> = Vec::new();
+        for &e in &self.species {
+            if !types.contains(&e) { types.push(e); }
+        }
+
+        write_atomic(path, |out| {
+            writeln!(out, "LAMMPS data file written by LAB7\n")?;
+            writeln!(out, "{} atoms", self.size)?;
+            writeln!(out, "{} atom types\n", types.len())?;
+            for axis in ["x", "y", "z"] {
+                writeln!(out, "{:.6} {:.6} {}lo {}hi", -half, half, axis, axis)?;
+            }
+            writeln!(out, "\nMasses\n")?;
+            for (t, e) in types.iter().enumerate() {
+                writeln!(out, "{} {} # {}", t + 1, e.mass(), e)?;
+            }
+            writeln!(out, "\nAtoms # atomic\n")?;
+            for (i, p) in self.positions.iter().enumerate() {
+                let t = types.iter().position(|&e| e == self.species[i]).unwrap_or(0) + 1;
+                writeln!(out, "{} {} {:.10} {:.10} {:.10}", i + 1, t, p.x(), p.y(), p.z())?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// potential energy [eV] of the last thermo output of a LAMMPS log (PotEng column)
+pub fn energy_from_log(path: &str) -> Result<f64, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+
+    let mut energy = None;
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let header: Vec<&str> = line.split_whitespace().collect();
+        if header.first() != Some(&"Step") { continue }
+        let Some(column) = header.iter().position(|&h| h == "PotEng") else { continue };
+
+        // thermo rows until the "Loop time" line
+        for row in lines.by_ref() {
+            match row.split_whitespace().nth(column).and_then(|x| x.parse::<f64>().ok()) {
+                Some(e) => energy = Some(e),
+                None => break,
+            }
+        }
+    }
+    energy.ok_or(format!("{}: no thermo output with PotEng", path))
+}
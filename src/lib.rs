@@ -0,0 +1,925 @@
+use std::{io::{Write, self}, f64::consts::PI, cell::RefCell};
+use ndarray::{prelude::*, AssignElem, Zip};
+use rand::{prelude::*, rngs::StdRng};
+use num_traits::Float;
+use tracing::warn;
+
+use crate::bond_cache::BondCache;
+use crate::scratch::{KernelFloat, Scratch};
+use crate::utilities::write_atomic;
+
+mod utilities;
+mod point;
+mod element;
+pub use element::Element;
+mod vibrations;
+mod symmetry;
+mod selection;
+pub use selection::Selection;
+mod md;
+mod relax;
+mod minima_hopping;
+mod neb;
+pub mod anneal;
+pub mod simulation;
+mod calibration;
+mod shell_release;
+mod watchdog;
+mod meta_anneal;
+mod runner;
+mod sensitivity;
+mod quench;
+mod umbrella;
+mod cv;
+mod harmonics;
+mod order;
+mod cna;
+mod hull;
+mod shape;
+mod radial;
+mod configurational;
+mod stress;
+mod ring_strain;
+mod graph_export;
+mod isomer;
+mod schlegel;
+mod movie;
+mod snapshots;
+mod report;
+mod reweighting;
+mod autocorrelation;
+mod profile;
+mod logging;
+mod geometry;
+mod breakdown;
+mod bonds;
+mod cage;
+mod bond_cache;
+mod scratch;
+mod colored_sweep;
+mod speculative;
+mod explain;
+mod sphere_cells;
+mod compare;
+mod archive;
+mod analysis;
+pub mod cli;
+mod run_dir;
+mod observables;
+mod extxyz;
+mod trajectory;
+mod traj;
+mod metadata;
+mod reference;
+mod lammps;
+pub mod units;
+mod potential;
+pub use potential::{Brenner, Potential};
+mod tables;
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "gpu")]
+pub use gpu::GpuEnergy;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "capi")]
+#[allow(private_interfaces)] // Fuleren is opaque to C
+pub mod capi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "chemfiles")]
+mod chemfiles_io;
+#[cfg(feature = "gui")]
+mod gui;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "rusqlite")]
+mod database;
+#[cfg(test)]
+mod tests;
+
+//################# params ###################
+// defaults of the Brenner constants, a config can override them (see potential::Brenner)
+const R0: f64 = 1.315;
+const R1: f64 = 1.7;
+const R2: f64 = 2.0;
+const DE: f64 = 6.325;
+const S: f64 = 1.29;
+const LAMBDA: f64 = 1.5;
+const DEL: f64 = 0.80469;
+const A0: f64 = 0.011304;
+const C0: f64 = 19.;
+const D0: f64 = 2.5;
+const M_C: f64 = 12.011; // carbon mass [amu]
+const R_CORE: f64 = 0.5; // default hard core distance, moves closer than that are rejected
+const PCF_BINS: usize = 100;
+const PCF_RANGE: f64 = 2.5; // pcf up to 2.5 mean radii
+// ##############################
+type VectorFloat = Array1<f64>;
+
+// floating point type of the energy kernel; f32 halves the memory traffic for large N
+#[cfg(not(feature = "f32"))]
+type Real = f64;
+#[cfg(feature = "f32")]
+type Real = f32;
+
+// neighbours of a center atom within the cutoff in kernel type T: displacement vectors (rows of
+// d, flattened), distances and cutoff weights, so the bond angles of all of them are computed at
+// once. Refilled in place (see scratch), center None while empty
+struct Shell<T> {
+    center: Option<usize>,
+    atoms: Vec<usize>,
+    d: Vec<T>,
+    r: Vec<T>,
+    fc: Vec<T>,
+}
+
+impl<T> Shell<T> {
+    fn new() -> Shell<T> {
+        Shell { center: None, atoms: Vec::new(), d: Vec::new(), r: Vec::new(), fc: Vec::new() }
+    }
+
+    fn clear(&mut self) {
+        self.center = None;
+        self.atoms.clear();
+        self.d.clear();
+        self.r.clear();
+        self.fc.clear();
+    }
+}
+
+// ############# structs and implementations
+use point::Point6;
+
+type Point6Array = Array1<Point6>;
+
+#[allow(non_snake_case)] // E as in the formulas
+#[derive( Debug, Clone)]
+pub struct Fuleren {
+    positions: Point6Array,
+    species: Vec<Element>, // element of every atom, carbon unless read otherwise
+    size: usize,
+    E: f64,
+    r_core: f64,
+    steps: StepSizes,
+    potential: Potential,
+}
+
+// relative step sizes of the random moves
+#[derive( Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct StepSizes {
+    w_r: f64,
+    w_phi: f64,
+    w_theta: f64,
+    w_all: f64, // global radius move
+}
+
+impl Default for StepSizes {
+    fn default() -> Self {
+        StepSizes { w_r: 1e-4, w_phi: 0.05, w_theta: 0.05, w_all: 1e-4 }
+    }
+}
+
+impl Fuleren {
+    // constructors
+    fn new(size: usize) -> Fuleren {
+        Fuleren { positions: Point6Array::from_elem(size, Point6::new()),
+                  species: vec![Element::C; size],
+                  size,
+                  E: 0.,
+                  r_core: R_CORE,
+                  steps: StepSizes::default(),
+                  potential: Potential::default() }
+    }
+    
+
+    // methods
+    fn randomize_on_sphere(&mut self, r: f64) {
+        let phi_distr = rand::distributions::Uniform::new_inclusive(0., 2.*PI);
+        let theta_distr = rand::distributions::Uniform::new_inclusive(0., PI);
+        let mut rng = rng();
+
+        self.positions.iter_mut()
+                      .for_each(|point| 
+                                point.assign_elem(Point6::from_spherical(&[r, 
+                                                                            rng.sample(phi_distr), 
+                                                                            rng.sample(theta_distr)]) ));
+    }
+
+    // single atom Metropolis move; the bond orders of V_i before and after come from the cache of
+    // the running sweep, which is kept up to date
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn random_atom_shift(&mut self, i: usize, beta: f64, cache: &mut BondCache<Real>) -> bool {
+        let mut rng = rng();
+        let distr = rand::distributions::Uniform::<f64>::new_inclusive(0., 1.);
+        // change rates
+        let w_r = self.steps.w_r;
+        let w_phi = self.steps.w_phi;
+        let w_theta = self.steps.w_theta;
+
+        let u1 = rng.sample(distr);
+        let u2 = rng.sample(distr);
+        let u3 = rng.sample(distr);
+
+        // let mut atom = &mut self.positions[i];
+
+        //save old values, assign new; the whole point is kept so a rejection restores it exactly
+        let atom_old = self.positions[i].clone();
+        
+        #[cfg_attr(not(feature = "f32"), allow(clippy::unnecessary_cast))] // Real is f32 with the f32 feature
+        let v_old = self._vi_cached(i, Some(cache)) as f64;
+        
+        let r_new = self.positions[i].r() + self.positions[i].r()*(2.*u1 - 1.) * w_r;
+        let phi_new = self.positions[i].phi() + self.positions[i].phi()*(2.*u2 - 1.) * w_phi;
+        let theta_new = self.positions[i].theta() + self.positions[i].theta()*(2.*u3 - 1.) * w_theta;
+
+        // from_spherical brings the angles back into range and updates x, y, z with them
+        self.positions[i] = Point6::from_spherical(&[r_new, phi_new, theta_new]);
+
+        // hard core rejection, the potential is not defined for coinciding atoms
+        if self._overlap_with(i, &self.positions[i]).is_some() {
+            if explain::active() {
+                explain::explain(i, &atom_old, &self.positions[i], beta, None);
+            }
+            self.positions[i] = atom_old;
+            return false
+        }
+
+        cache.moved(self, i, &atom_old, &self.positions[i]);
+        #[cfg_attr(not(feature = "f32"), allow(clippy::unnecessary_cast))] // Real is f32 with the f32 feature
+        let v_new = self._vi_cached(i, Some(cache)) as f64;
+        let de = v_new - v_old - self._one_body_excess(&atom_old, &self.positions[i]);
+
+        let _exp = (-beta*de).exp();
+        let p_acc = if _exp < 1. { _exp} else { 1.}; // possibly redundand if
+
+        let u4 = rng.sample(distr);
+        if explain::active() {
+            let decision = explain::Metropolis { de, p_acc, u: u4 };
+            explain::explain(i, &atom_old, &self.positions[i], beta, Some(decision));
+        }
+        if u4 <= p_acc {
+            true
+        }
+        else {
+            let atom_new = std::mem::replace(&mut self.positions[i], atom_old);
+            cache.moved(self, i, &atom_new, &self.positions[i]);
+            false
+        }
+    }
+
+    // position of a uniform move of atom i (the proposal of random_atom_shift), nothing applied
+    fn _propose_shift(&self, i: usize) -> Point6 {
+        let mut rng = rng();
+        let old = &self.positions[i];
+        let r = old.r() + old.r()*(2.*rng.gen::<f64>() - 1.)*self.steps.w_r;
+        let phi = old.phi() + old.phi()*(2.*rng.gen::<f64>() - 1.)*self.steps.w_phi;
+        let theta = old.theta() + old.theta()*(2.*rng.gen::<f64>() - 1.)*self.steps.w_theta;
+        Point6::from_spherical(&[r, phi, theta])
+    }
+
+    // energy change when atom i moves to new, from the change of V_i (None for a move into the
+    // hard core), the structure is left as it was
+    fn _shift_dvi(&mut self, i: usize, new: &Point6) -> Option<f64> {
+        if self._overlap_with(i, new).is_some() {
+            return None
+        }
+        let v_old = self._vi(i);
+        let old = std::mem::replace(&mut self.positions[i], new.clone());
+        let v_new = self._vi(i);
+        self.positions[i] = old;
+        Some(v_new - v_old - self._one_body_excess(&self.positions[i], new))
+    }
+
+    // the change of V_i counts the pair terms of atom i whole, as E does, but also the one-body
+    // terms, which enter E with 1/2; this is the half too much when atom i moves from old to new
+    fn _one_body_excess(&self, old: &Point6, new: &Point6) -> f64 {
+        let one_body = |r: f64| self.potential.shell.as_ref().map_or(0., |shell| shell.v_i(r))
+                                + self.potential.wall.as_ref().map_or(0., |wall| wall.v_i(r));
+        0.5*(one_body(new.r()) - one_body(old.r()))
+    }
+
+    // smart Monte Carlo move: displacement a*beta*F_i plus gaussian noise of variance 2a,
+    // acceptance corrected for the asymmetric proposal
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn smart_atom_shift(&mut self, i: usize, beta: f64, a: f64) -> bool {
+        let mut rng = rng();
+        let distr = rand::distributions::Uniform::<f64>::new_inclusive(0., 1.);
+
+        let old = self.positions[i].clone();
+        let xyz_old = [old.x(), old.y(), old.z()];
+        let f_old = self._force_on(i);
+
+        let mut xyz_new = [0.; 3];
+        for c in 0..3 {
+            xyz_new[c] = xyz_old[c] + a*beta*f_old[c] + (2.*a).sqrt()*gauss(&mut rng);
+        }
+        let new = Point6::from_cartesian(&xyz_new);
+        if self._overlap_with(i, &new).is_some() {
+            return false
+        }
+        let de = self._local_shift(i, new);
+        let f_new = self._force_on(i);
+
+        // log of forward and backward proposal densities
+        let mut q_fwd = 0.;
+        let mut q_bwd = 0.;
+        for c in 0..3 {
+            q_fwd -= (xyz_new[c] - xyz_old[c] - a*beta*f_old[c]).powi(2)/(4.*a);
+            q_bwd -= (xyz_old[c] - xyz_new[c] - a*beta*f_new[c]).powi(2)/(4.*a);
+        }
+
+        let _exp = (-beta*de + q_bwd - q_fwd).exp();
+        let p_acc = if _exp < 1. { _exp} else { 1.};
+
+        let u = rng.sample(distr);
+        if u <= p_acc {
+            true
+        }
+        else {
+            self.positions[i] = old;
+            false
+        }
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn random_global_r_shift(&mut self, beta: f64) -> bool {
+        let mut rng = rng();
+        let distr = rand::distributions::Uniform::<f64>::new_inclusive(0., 1.);
+        
+        let e_old = self.energy_calc();
+
+        //rate of change
+        let w_all = self.steps.w_all;
+
+        // scaling radius of all atoms (and x,y,z with it), angles stay; a rejection scales back,
+        // so no copy of the old positions is needed
+        let u1 = rng.sample(distr);
+        let r_change = 1. + w_all*(2.*u1 - 1.);
+        self.positions.iter_mut().for_each(|atom| atom.scale(r_change));
+
+        if r_change < 1. && self._any_overlap().is_some() {
+            self.positions.iter_mut().for_each(|atom| atom.scale(1./r_change));
+            return false
+        }
+
+        let e_new = self.energy_calc();
+
+        let _exp = (-beta*(e_new - e_old)).exp();
+        let p_acc = if _exp < 1. { _exp} else { 1.}; 
+
+        let u2 = rng.sample(distr);
+        if u2 <= p_acc {
+            true //since every atom is already updated
+        }
+        else {
+            self.positions.iter_mut().for_each(|atom| atom.scale(1./r_change));
+            self.E = e_old;
+            false
+        }
+    }
+
+    /// recomputes the total energy [eV] from the positions, stored for energy()
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn energy_calc(&mut self) -> f64 {
+        #[cfg(feature = "gpu")]
+        if let Some(e) = gpu::thread_energy(self) {
+            return e
+        }
+
+        let e = 0.5 * (0..self.size)
+                    .map(|i| self._vi(i))
+                    .sum::<f64>();
+        if !e.is_finite() {
+            warn!(E = e, "non-finite energy");
+        }
+        
+        self.E = e;
+        e
+    }
+
+    fn forces(&self) -> Array2<f64> {
+        let mut forces = Array2::<f64>::zeros((self.size, 3));
+        let mut work = self.clone();
+
+        for i in 0..self.size {
+            let f_i = work._force_on(i);
+            for c in 0..3 {
+                forces[[i, c]] = f_i[c];
+            }
+        }
+        forces
+    }
+
+    // force on atom i from central differences of the energy; only atoms within 2*R2 of i
+    // (or the range of the added terms) feel the displacement of i, so the local sum is enough
+    fn _force_on(&mut self, i: usize) -> [f64; 3] {
+        let h = 1e-5;
+        let local = self._neighbourhood(i, self.potential.range());
+        let old = self.positions[i].clone();
+        let xyz_old = [old.x(), old.y(), old.z()];
+        let mut f = [0.; 3];
+
+        for c in 0..3 {
+            let mut xyz = xyz_old;
+            xyz[c] = xyz_old[c] + h;
+            self.positions[i] = Point6::from_cartesian(&xyz);
+            let e_plus = self._e_local(&local);
+
+            xyz[c] = xyz_old[c] - h;
+            self.positions[i] = Point6::from_cartesian(&xyz);
+            let e_minus = self._e_local(&local);
+
+            f[c] = -(e_plus - e_minus)/(2.*h);
+        }
+        self.positions[i] = old;
+        f
+    }
+
+    // moves atom i to new position and returns the exact energy change
+    fn _local_shift(&mut self, i: usize, new: Point6) -> f64 {
+        let old = std::mem::replace(&mut self.positions[i], new);
+        let mut local = self._neighbourhood(i, self.potential.range());
+
+        let new = std::mem::replace(&mut self.positions[i], old);
+        local.extend(self._neighbourhood(i, self.potential.range()));
+        local.sort_unstable();
+        local.dedup();
+
+        let e_old = self._e_local(&local);
+        self.positions[i] = new;
+        self._e_local(&local) - e_old
+    }
+
+    // some atom j != i closer than r_core to point p (a position proposed for atom i)
+    fn _overlap_with(&self, i: usize, p: &Point6) -> Option<usize> {
+        (0..self.size).filter(|&j| j != i)
+                      .find(|&j| {
+                          let q = &self.positions[j];
+                          (q.x() - p.x()).powi(2) + (q.y() - p.y()).powi(2) + (q.z() - p.z()).powi(2) < self.r_core.powi(2)
+                      })
+    }
+
+    // first pair of atoms closer than r_core
+    fn _any_overlap(&self) -> Option<(usize, usize)> {
+        (0..self.size).find_map(|i| self._overlap_with(i, &self.positions[i]).map(|j| (i, j)))
+    }
+
+    // indices of atoms closer than r_cut to atom i (i included)
+    fn _neighbourhood(&self, i: usize, r_cut: f64) -> Vec<usize> {
+        (0..self.size).filter(|&j| j == i || self._r_ij(i, j) <= r_cut)
+                      .collect()
+    }
+
+    // part of the total energy coming from the given atoms
+    fn _e_local(&self, atoms: &[usize]) -> f64 {
+        0.5 * atoms.iter().map(|&a| self._vi(a)).sum::<f64>()
+    }
+
+    #[cfg_attr(not(feature = "f32"), allow(clippy::unnecessary_cast))] // Real is f32 with the f32 feature
+    fn _vi(&self, i:usize) -> f64 {
+        self._vi_t::<Real>(i) as f64
+    }
+
+    // energy kernel in floating point type T; Real unless asked for explicitly
+    fn _vi_t<T: KernelFloat>(&self, i:usize) -> T {
+        self._vi_cached(i, None)
+    }
+
+    // _vi_t taking the bond orders from the cache where present and adding the computed ones
+    #[tracing::instrument(level = "trace", name = "_vi", skip_all)]
+    fn _vi_cached<T: KernelFloat>(&self, i:usize, mut cache: Option<&mut BondCache<T>>) -> T {
+        let mut vi = T::zero();
+        let r2 = cst::<T>(self.potential.brenner.r2);
+        let half = cst::<T>(0.5);
+        let lj = self.potential.lj.as_ref();
+        let coulomb = self.potential.coulomb.as_ref();
+
+        T::with_scratch(|scratch| {
+            let Scratch { near, shell_i, shell_j, bonded_i, bonded_j } = scratch;
+            // every atom within 2*R2 of i holds the bonded neighbours of the bonded neighbours of i;
+            // the bond orders of all pairs of i come from shells built out of these, on a cache miss
+            near.clear();
+            near.extend((0..self.size).filter(|&k| _mod_arr(&self._vec_ij::<T>(i, k)) <= r2 + r2));
+            shell_i.clear();
+            shell_j.clear();
+
+            // create enumerate iterator with i != j 
+            let iter = self.positions.iter()
+                            .enumerate()
+                            .filter(|(j,_)| *j != i);
+            
+            for (j, _) in iter { // possible: create closure f_cut istead of this ifs
+                let r_ij = _mod_arr(&self._vec_ij::<T>(i, j)); 
+                if r_ij == T::zero() {
+                    warn!(i, j, "atoms at zero distance");
+                }
+                debug_assert!(r_ij > T::zero(), "atoms {} and {} overlap", i, j);
+
+                if r_ij <= r2 {
+                    let b = half*(self._b_lookup(i, j, near, shell_i, &mut cache)
+                                  + self._b_lookup(j, i, near, shell_j, &mut cache));
+                    vi = vi + self.potential.brenner.cutoff(r_ij)*(self._repulsive(r_ij) - b*self._attractive(r_ij))
+                }
+                else if let Some(lj) = lj {
+                    // pair term, counted whole here since E sums V_i with 1/2
+                    vi = vi + lj.pair(r_ij, self.potential.brenner.r2)
+                }
+                if let Some(coulomb) = coulomb {
+                    let qq = coulomb.q(i, self.size)*coulomb.q(j, self.size);
+                    vi = vi + coulomb.pair(cst::<T>(qq), r_ij)
+                }
+            }
+            if let Some(torsion) = &self.potential.torsion {
+                vi = vi + self._torsion_on::<T>(i, torsion, bonded_i, bonded_j);
+            }
+            if let Some(shell) = &self.potential.shell {
+                vi = vi + shell.v_i(cst::<T>(self.positions[i].r()));
+            }
+            if let Some(wall) = &self.potential.wall {
+                vi = vi + wall.v_i(cst::<T>(self.positions[i].r()));
+            }
+            vi
+        })
+    }
+
+    fn _b_ij<T: Float>(&self,i:usize, j:usize) -> T {
+        (T::one() + self._ksi_ij::<T>(i, j)).powf(-cst::<T>(self.potential.brenner.delta))
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn _ksi_ij<T: Float>(&self, i: usize, j: usize) -> T {
+        let mut shell = Shell::new();
+        self._fill_shell::<T>(&mut shell, i, (0..self.size).filter(|&k| k != i), cst(self.potential.brenner.r2));
+        self._ksi_shell(&shell, j)
+    }
+
+    // shell of atom i from the atoms among candidates within r_cut
+    fn _fill_shell<T: Float>(&self, shell: &mut Shell<T>, i: usize, candidates: impl Iterator<Item = usize>, r_cut: T) {
+        shell.clear();
+        shell.center = Some(i);
+        for k in candidates {
+            let v = self._vec_ij::<T>(i, k);
+            let r = _mod_arr(&v);
+            if r <= r_cut {
+                shell.atoms.push(k);
+                shell.d.extend_from_slice(&v);
+                shell.r.push(r);
+                shell.fc.push(self.potential.brenner.cutoff(r));
+            }
+        }
+    }
+
+    // ksi_ij with i the center of the shell: the bond angles to all other shell atoms at once
+    fn _ksi_shell<T: Float>(&self, shell: &Shell<T>, j: usize) -> T {
+        let center = shell.center.expect("empty shell");
+        let d_j = self._vec_ij::<T>(center, j);
+        let r_j = _mod_arr(&d_j);
+        let d = ArrayView2::from_shape((shell.atoms.len(), 3), &shell.d).unwrap();
+        // k = j is no neighbour of the bond
+        Zip::from(d.rows())
+            .and(aview1(&shell.r))
+            .and(aview1(&shell.fc))
+            .and(aview1(&shell.atoms))
+            .fold(T::zero(), |ksi, d, &r, &w, &k| {
+                if k == j { return ksi }
+                ksi + w*self._g_cos((d[0]*d_j[0] + d[1]*d_j[1] + d[2]*d_j[2])/(r*r_j))
+            })
+    }
+
+    // b_ij from the cache or, on a miss, from the shell of i (filled from near unless it already
+    // holds i) and stored in the cache
+    fn _b_lookup<T: Float>(&self, i: usize, j: usize, near: &[usize], shell: &mut Shell<T>,
+                           cache: &mut Option<&mut BondCache<T>>) -> T {
+        if let Some(b) = cache.as_ref().and_then(|c| c.get(i, j)) {
+            return b
+        }
+        if shell.center != Some(i) {
+            let r2 = cst::<T>(self.potential.brenner.r2);
+            self._fill_shell(shell, i, near.iter().copied().filter(|&k| k != i), r2);
+        }
+        let b = self._b_shell(shell, j);
+        if let Some(c) = cache {
+            c.insert(i, j, b);
+        }
+        b
+    }
+
+    // bond order b_ij from the shell of i
+    fn _b_shell<T: Float>(&self, shell: &Shell<T>, j: usize) -> T {
+        (T::one() + self._ksi_shell(shell, j)).powf(-cst::<T>(self.potential.brenner.delta))
+    }
+
+    // vector from atom i to atom j in type T
+    fn _vec_ij<T: Float>(&self, i: usize, j: usize) -> [T; 3] {
+        let (pi, pj) = (&self.positions[i], &self.positions[j]);
+        [cst::<T>(pj.x()) - cst(pi.x()),
+         cst::<T>(pj.y()) - cst(pi.y()),
+         cst::<T>(pj.z()) - cst(pi.z())]
+    }
+
+    fn _r_ij(&self, i:usize, j:usize) -> f64 {
+        // let vec_i = array![self.positions[i].x(),self.positions[i].y(),self.positions[i].z()];
+        // let vec_j = array![self.positions[j].x(),self.positions[j].y(),self.positions[j].z()];
+        let vec_ij = [self.positions[j].x() - self.positions[i].x(),
+                                self.positions[j].y() - self.positions[i].y(),
+                                self.positions[j].z() - self.positions[i].z()];
+        _mod_arr(&vec_ij)
+    }
+
+    // cartesian positions as N x 3 array
+    fn xyz_array(&self) -> Array2<f64> {
+        Array2::from_shape_fn((self.size, 3), |(i, c)| match c {
+            0 => self.positions[i].x(),
+            1 => self.positions[i].y(),
+            _ => self.positions[i].z(),
+        })
+    }
+
+    fn set_xyz_array(&mut self, xyz: &Array2<f64>) {
+        for i in 0..self.size {
+            self.positions[i] = Point6::from_cartesian(&[xyz[[i, 0]], xyz[[i, 1]], xyz[[i, 2]]]);
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// copy of the cartesian positions [A], one row per atom
+    pub fn positions_xyz(&self) -> Array2<f64> {
+        self.xyz_array()
+    }
+
+    /// cartesian positions [A] in atom order
+    pub fn iter_atoms(&self) -> impl Iterator<Item = [f64; 3]> + '_ {
+        self.positions.iter().map(|p| [p.x(), p.y(), p.z()])
+    }
+
+    /// spherical coordinates [r, phi, theta] of atom i
+    pub fn spherical(&self, i: usize) -> [f64; 3] {
+        let p = &self.positions[i];
+        [p.r(), p.phi(), p.theta()]
+    }
+
+    /// moves atom i to the cartesian position xyz; the spherical coordinates follow,
+    /// energy() is stale until energy_calc
+    pub fn set_position(&mut self, i: usize, xyz: [f64; 3]) {
+        self.positions[i] = Point6::from_cartesian(&xyz);
+    }
+
+    /// moves atom i to [r, phi, theta] (any angles, normalized); the cartesian coordinates
+    /// follow, energy() is stale until energy_calc
+    pub fn set_spherical(&mut self, i: usize, rpt: [f64; 3]) {
+        self.positions[i] = Point6::from_spherical(&rpt);
+    }
+
+    /// sets all positions from an N x 3 array of cartesian coordinates and recomputes the energy
+    pub fn set_positions_xyz(&mut self, xyz: ArrayView2<f64>) -> Result<(), String> {
+        if xyz.dim() != (self.size, 3) {
+            return Err(format!("expected {} x 3 positions, got {} x {}", self.size, xyz.nrows(), xyz.ncols()));
+        }
+        self.set_xyz_array(&xyz.to_owned());
+        self.energy_calc();
+        Ok(())
+    }
+
+    /// element of every atom
+    pub fn species(&self) -> &[Element] {
+        &self.species
+    }
+
+    /// changes the element of atom i; only its mass matters to this crate, the potential treats
+    /// every atom as carbon
+    pub fn set_species(&mut self, i: usize, element: Element) {
+        self.species[i] = element;
+    }
+
+    /// mass of every atom [amu]
+    pub fn masses(&self) -> VectorFloat {
+        self.species.iter().map(|e| e.mass()).collect()
+    }
+
+    /// energy [eV] as last computed (every sweep of the anneal recomputes it)
+    pub fn energy(&self) -> f64 {
+        self.E
+    }
+
+    /// mean distance of the atoms from the origin
+    pub fn mean_r(&self) -> f64 {
+        self.positions.iter()
+                      .map(|point| point.r())
+                      .sum::<f64>()/(self.size as f64)
+    }
+
+    // angular function g of the cosine of the bond angle j-i-k
+    fn _g_cos<T: Float>(&self, cos_ijk: T) -> T {
+        if let Some(g) = self.potential.tables.as_ref().and_then(|t| t.g.as_ref()) {
+            return cst(g.eval(cos_ijk.to_f64().unwrap()))
+        }
+        let b = &self.potential.brenner;
+        let (a, c, d) = (cst::<T>(b.a0), cst::<T>(b.c0), cst::<T>(b.d0));
+        
+        // modyfication to forbid 4-atom bindings
+        if cos_ijk > T::zero() {
+            cst(20.) // experimental value
+        }
+        else {
+            a*( T::one() + c.powi(2)/d.powi(2) - c.powi(2)/( d.powi(2) + (T::one() + cos_ijk).powi(2) ) )
+        }
+
+        // a0*( 1. + c0.powi(2)/d0.powi(2) - c0.powi(2)/( d0.powi(2) + (1. + cos_ijk).powi(2) ) )
+        
+    }
+
+    fn pcf(&self) -> VectorFloat {
+        self.pcf_about(self.mean_r())
+    }
+
+    /// pair correlation function on a sphere of radius r_ref, PCF_BINS bins up to PCF_RANGE*r_ref:
+    /// every pair at distance r counts 1/(pairs * p(r) dr), where pairs = N(N-1)/2 and
+    /// p(r) = r/(2 r_ref^2) is the distance density of two random points on the sphere, so ideal
+    /// random points give 1 up to the diameter (see ideal_pcf and ideal_sphere)
+    pub fn pcf_about(&self, r_ref: f64) -> VectorFloat {
+        let m_bins: usize = PCF_BINS;
+        let mut pcf = VectorFloat::zeros(m_bins);
+        if self.size < 2 {
+            return pcf
+        }
+        let pairs = (self.size*(self.size - 1)) as f64/2.;
+        let dr = PCF_RANGE*r_ref/m_bins as f64;
+
+        for i in 0..self.size {
+            for j in (i+1)..self.size {
+                let r = self._r_ij(i, j);
+                let m = (r/dr).floor() as usize;
+                if m < m_bins {
+                    pcf[m] += 2.*r_ref.powi(2)/(pairs*r*dr);
+                }
+            }
+        }
+        pcf
+    }
+
+    /// expected pcf_about(r) of ideal random points on a sphere of radius r: 1 below the diameter,
+    /// the covered part of the bin containing it, 0 beyond
+    pub fn ideal_pcf() -> VectorFloat {
+        let dr = PCF_RANGE/PCF_BINS as f64;
+        VectorFloat::from_iter((0..PCF_BINS).map(|m| ((2. - m as f64*dr)/dr).clamp(0., 1.)))
+    }
+
+    /// n points uniformly distributed on a sphere of radius r (uniform in phi and cos theta), the
+    /// reference of pcf_about; unlike randomize_on_sphere, which is uniform in theta
+    pub fn ideal_sphere(n: usize, r: f64) -> Fuleren {
+        let mut f = Fuleren::new(n);
+        let mut rng = rng();
+        for i in 0..n {
+            let phi = 2.*PI*rng.gen::<f64>();
+            let theta = (1. - 2.*rng.gen::<f64>()).acos();
+            f.positions[i] = Point6::from_spherical(&[r, phi, theta]);
+        }
+        f
+    }
+
+    // distances of the pcf bin centers
+    fn pcf_r(&self) -> VectorFloat {
+        pcf_bins()*self.mean_r()
+    }
+
+    /// "x y z" lines in atom order after a "# N=.. energy=.. <metadata>" header line
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn save_pos_xyz(&self, path: &str, meta: &metadata::Metadata) -> io::Result<()> {
+        write_atomic(path, |f| {
+            writeln!(f, "# N={} energy={:.8} {}", self.size, meta.energy.unwrap_or(self.E), meta.to_info())?;
+            for atom in self.positions.iter() {
+                writeln!(f, "{:<10.5}\t{:<10.5}\t{:<10.5}", atom.x(), atom.y(), atom.z())?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Fuleren {
+    /// one line: N, E, E/N, mean radius and defects (atoms without exactly 3 bonds)
+    pub fn summary(&self) -> String {
+        let defects = self.coordination().iter().filter(|&&c| c != 3).count();
+        format!("Fuleren N={} E={:.5} eV E/N={:.5} eV r_mean={:.5} A defects={}",
+                self.size, self.E, self.E/self.size as f64, self.mean_r(), defects)
+    }
+
+    /// summary line followed by a table of all atoms, cartesian and spherical coordinates
+    pub fn to_table(&self) -> String {
+        let mut table = format!("{}\n{:<10.5}\t{:<10.5}\t{:<10.5}\t{:<10.5}\t{:<10.5}\t{:<10.5}\n",
+                                self.summary(), "x", "y", "z", "r", "phi", "theta");
+        for point in self.positions.iter() {
+            table += &format!("{}\n", point);
+        }
+        table
+    }
+}
+
+// the summary line; the alternate form {:#} prints the full table
+impl std::fmt::Display for Fuleren {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() { write!(f, "{}", self.to_table()) }
+        else { write!(f, "{}", self.summary()) }
+    }
+}
+
+
+
+// ####################################
+// ########### functions #############
+
+// f64 constant in the kernel type
+fn cst<T: Float>(x: f64) -> T {
+    T::from(x).unwrap()
+}
+
+fn _mod_vec(vec: &Array1<f64>) -> f64 {
+    (vec[0].powi(2) + vec[1].powi(2) + vec[2].powi(2)).sqrt()
+}
+fn _mod_arr<T: Float>(vec: &[T;3]) -> T {
+    (vec[0].powi(2) + vec[1].powi(2) + vec[2].powi(2)).sqrt()
+}
+
+// the first three columns as x y z, None if there are fewer or they are no numbers
+fn parse_xyz<'a>(mut cols: impl Iterator<Item = &'a str>) -> Option<[f64; 3]> {
+    let mut xyz = [0.; 3];
+    for c in xyz.iter_mut() {
+        *c = cols.next()?.parse().ok()?;
+    }
+    Some(xyz)
+}
+
+// normalizes any (phi, theta) to phi [0, 2*PI), theta [0, PI] without moving the point
+fn check_angles(mut phi: f64, mut theta: f64) -> (f64, f64) {
+    //theta [0, PI]; going over a pole reflects theta and turns phi by PI
+    theta = theta.rem_euclid(2.*PI);
+    if theta > PI {
+        theta = 2.*PI - theta;
+        phi += PI;
+    }
+
+    //phi [0, 2*PI); rem_euclid of a tiny negative phi rounds up to 2*PI
+    phi = phi.rem_euclid(2.*PI);
+    if phi == 2.*PI {
+        phi = 0.;
+    }
+
+    (phi, theta)
+}
+
+// every thread has its own generator, so independent jobs on different threads can be seeded separately
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// reseeds the random generator of the current thread
+fn seed_rng(seed: u64) {
+    RNG.with(|r| *r.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// handle to the thread local generator, used in place of rand::thread_rng()
+struct SimRng;
+
+fn rng() -> SimRng {
+    SimRng
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|r| r.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|r| r.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|r| r.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        RNG.with(|r| r.borrow_mut().try_fill_bytes(dest))
+    }
+}
+
+// standard normal sample (Box-Muller)
+fn gauss<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = 1. - rng.gen::<f64>(); // (0, 1], safe for ln
+    let u2: f64 = rng.gen();
+    (-2.*u1.ln()).sqrt() * (2.*PI*u2).cos()
+}
+
+// pcf bin centers in units of the mean radius
+fn pcf_bins() -> VectorFloat {
+    VectorFloat::from_iter((0..PCF_BINS).map(|m| (m as f64 + 0.5)*PCF_RANGE/PCF_BINS as f64))
+}
+
+fn get_beta(it: usize, it_max: usize, b_min: f64, b_max: f64, p: f64) -> f64 {
+    b_min + (it as f64/it_max as f64).powf(p) * (b_max - b_min)
+}
+
+// ##################################
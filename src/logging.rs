@@ -0,0 +1,20 @@
+use tracing_subscriber::{filter::EnvFilter, fmt, prelude::*};
+
+use crate::profile::{Profiler, TimingLayer};
+
+/// installs the global subscriber: log events to stderr filtered by RUST_LOG (default info),
+/// plus the timing layer when profiling. Function spans are at trace level, so they cost
+/// next to nothing unless profiling or RUST_LOG=trace
+pub fn init(profile: bool) -> Option<Profiler> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let fmt_layer = fmt::layer().with_writer(std::io::stderr).with_filter(filter);
+
+    let (profiler, timing): (Option<Profiler>, Option<TimingLayer>) = if profile {
+        let (p, l) = Profiler::new();
+        (Some(p), Some(l))
+    }
+    else { (None, None) };
+
+    tracing_subscriber::registry().with(fmt_layer).with(timing).init();
+    profiler
+}
@@ -0,0 +1,80 @@
+use ndarray::prelude::*;
+use rand::prelude::*;
+
+use crate::{Fuleren, Point6, gauss, rng};
+
+// time is in internal units sqrt(amu A^2/eV) = units::TIME_FS, so that F/m with F in eV/A gives A/time^2
+
+impl Fuleren {
+    /// integrates n_steps of velocity Verlet with time step dt; v (N x 3) is updated in place
+    pub fn velocity_verlet(&mut self, v: &mut Array2<f64>, dt: f64, n_steps: usize) {
+        let mut f = self.forces();
+
+        for _ in 0..n_steps {
+            self.verlet_step(v, &mut f, dt);
+        }
+    }
+
+    /// single velocity Verlet step; f holds the forces at the current positions and is updated
+    pub fn verlet_step(&mut self, v: &mut Array2<f64>, f: &mut Array2<f64>, dt: f64) {
+        // half kicks 0.5*dt/m_i per atom, broadcast over x, y, z
+        let kick = self.masses().mapv(|m| 0.5*dt/m).insert_axis(Axis(1));
+        *v += &(&kick*&*f);
+        for i in 0..self.size {
+            let xyz = [self.positions[i].x() + dt*v[[i, 0]],
+                       self.positions[i].y() + dt*v[[i, 1]],
+                       self.positions[i].z() + dt*v[[i, 2]]];
+            self.positions[i] = Point6::from_cartesian(&xyz);
+        }
+        *f = self.forces();
+        *v += &(&kick*&*f);
+    }
+
+    /// Maxwell-Boltzmann velocities at inverse temperature beta
+    pub fn random_velocities(&self, beta: f64) -> Array2<f64> {
+        let mut rng = rng();
+        let sigma = self.masses().mapv(|m| (1./(beta*m)).sqrt());
+        Array2::from_shape_fn((self.size, 3), |(i, _)| sigma[i]*gauss(&mut rng))
+    }
+
+    /// kinetic energy [eV] of the velocities v (N x 3)
+    pub fn kinetic_energy(&self, v: &Array2<f64>) -> f64 {
+        0.5*self.masses().iter().zip(v.rows()).map(|(m, vi)| m*vi.iter().map(|x| x.powi(2)).sum::<f64>()).sum::<f64>()
+    }
+
+    /// hybrid Monte Carlo move: short MD trajectory from random velocities,
+    /// accepted with the Metropolis rule on the total hamiltonian
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn hmc_step(&mut self, beta: f64, dt: f64, n_steps: usize) -> bool {
+        let mut rng = rng();
+        let distr = rand::distributions::Uniform::<f64>::new_inclusive(0., 1.);
+
+        let atoms_old_array = self.positions.clone();
+        let e_old = self.energy_calc();
+
+        let mut v = self.random_velocities(beta);
+        let k_old = self.kinetic_energy(&v);
+
+        self.velocity_verlet(&mut v, dt, n_steps);
+        if self._any_overlap().is_some() {
+            self.positions = atoms_old_array;
+            self.E = e_old;
+            return false
+        }
+        let e_new = self.energy_calc();
+        let k_new = self.kinetic_energy(&v);
+
+        let _exp = (-beta*(e_new + k_new - e_old - k_old)).exp();
+        let p_acc = if _exp < 1. { _exp} else { 1.};
+
+        let u = rng.sample(distr);
+        if u <= p_acc {
+            true
+        }
+        else {
+            self.positions = atoms_old_array;
+            self.E = e_old;
+            false
+        }
+    }
+}
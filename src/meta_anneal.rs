@@ -0,0 +1,97 @@
+use std::io;
+
+use rand::Rng;
+use tracing::info;
+
+use crate::{Fuleren, rng};
+use crate::anneal::{Schedule, ANNEAL_COLUMNS};
+use crate::observables::ObservableWriter;
+use crate::potential::Potential;
+
+/// meta-annealing: searches the schedule hyperparameters (beta_min, beta_max, p and the split of
+/// the iteration budget between equilibration and cooling) by successive halving over short pilot runs
+pub struct ScheduleSearch {
+    pub n: usize,                  // number of atoms
+    pub r_start: f64,              // radius of the random starting sphere
+    pub candidates: usize,         // schedules sampled in the first round
+    pub budget: usize,             // iterations per pilot run in the first round
+    pub eta: usize,                // 1/eta of the candidates survive a round, the budget grows eta times
+    pub repeats: usize,            // pilot runs per candidate, scored by the mean final energy
+    pub beta_min: (f64, f64),      // sampling ranges, beta's log-uniform
+    pub beta_max: (f64, f64),
+    pub p: (f64, f64),
+    pub equilibration: (f64, f64), // fraction of the budget spent on equilibration
+    pub potential: Potential,      // of the pilot runs
+}
+
+impl Default for ScheduleSearch {
+    fn default() -> Self {
+        ScheduleSearch { n: 60,
+                         r_start: 2.5,
+                         candidates: 27,
+                         budget: 1000,
+                         eta: 3,
+                         repeats: 2,
+                         beta_min: (0.1, 10.),
+                         beta_max: (10., 1000.),
+                         p: (0.5, 4.),
+                         equilibration: (0., 0.5),
+                         potential: Potential::default() }
+    }
+}
+
+impl ScheduleSearch {
+    /// returns the best schedule (scaled to the budget of the last round) and its mean final energy
+    pub fn run(&self) -> (Schedule, f64) {
+        let mut pool: Vec<(f64, f64, f64, f64)> = (0..self.candidates).map(|_| self.sample()).collect();
+        let mut budget = self.budget;
+
+        loop {
+            let mut scored: Vec<(Schedule, f64)> = pool.iter()
+                                                       .map(|&c| self.schedule(c, budget))
+                                                       .map(|s| { let e = self.score(&s); (s, e) })
+                                                       .collect();
+            scored.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            info!(budget, candidates = pool.len(), best_E = scored[0].1, best = ?scored[0].0, "round finished");
+
+            let keep = pool.len()/self.eta;
+            if keep == 0 {
+                return scored.swap_remove(0);
+            }
+            pool = scored.iter()
+                         .take(keep)
+                         .map(|(s, _)| (s.beta_min, s.beta_max, s.p, s.equilibration as f64/budget as f64))
+                         .collect();
+            budget *= self.eta;
+        }
+    }
+
+    // random candidate (beta_min, beta_max, p, equilibration fraction)
+    fn sample(&self) -> (f64, f64, f64, f64) {
+        let mut rng = rng();
+        let mut log_uniform = |(a, b): (f64, f64)| rng.gen_range(a.ln()..=b.ln()).exp();
+        let beta_min = log_uniform(self.beta_min);
+        let beta_max = log_uniform(self.beta_max);
+        (beta_min, beta_max, rng.gen_range(self.p.0..=self.p.1), rng.gen_range(self.equilibration.0..=self.equilibration.1))
+    }
+
+    fn schedule(&self, (beta_min, beta_max, p, equil): (f64, f64, f64, f64), budget: usize) -> Schedule {
+        let equilibration = (equil*budget as f64) as usize;
+        Schedule { beta_min, beta_max, p, equilibration, it_max: budget - equilibration, ..Default::default() }
+    }
+
+    // mean final energy of the pilot runs
+    fn score(&self, schedule: &Schedule) -> f64 {
+        let mut e = 0.;
+        for _ in 0..self.repeats {
+            let mut f = Fuleren::new(self.n);
+            f.potential = self.potential.clone();
+            f.randomize_on_sphere(self.r_start);
+            f.energy_calc();
+            let mut log = ObservableWriter::new(io::sink(), &ANNEAL_COLUMNS).expect("Error during saving");
+            f.anneal(schedule, &mut log, usize::MAX).expect("Error during saving");
+            e += f.energy_calc();
+        }
+        e/self.repeats as f64
+    }
+}
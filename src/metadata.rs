@@ -0,0 +1,76 @@
+//! provenance of saved structures: energy, potential, code version, seed and iteration, written
+//! as key=value pairs into the header of XYZ files (the "#" line of plain files, the comment line
+//! of extended XYZ), so a checkpoint says how it was made
+use crate::extxyz::key_values;
+use crate::potential::Potential;
+use crate::Fuleren;
+
+/// version of the code writing the files
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub energy: Option<f64>, // [eV]
+    pub potential: Option<String>,
+    pub version: Option<String>,
+    pub seed: Option<u64>,
+    pub iteration: Option<usize>,
+}
+
+impl Metadata {
+    /// energy (as stored, not recomputed) and potential of f with the current version; seed and
+    /// iteration unknown
+    pub fn of(f: &Fuleren) -> Metadata {
+        Metadata { energy: Some(f.E), potential: Some(f.potential.name()), version: Some(VERSION.to_string()),
+                   seed: None, iteration: None }
+    }
+
+    /// "key=value" pairs of the known fields, energy excluded (extended XYZ has its own)
+    pub fn to_info(&self) -> String {
+        let mut pairs = Vec::new();
+        if let Some(p) = &self.potential { pairs.push(format!("potential={}", p)) }
+        if let Some(v) = &self.version { pairs.push(format!("version={}", v)) }
+        if let Some(s) = self.seed { pairs.push(format!("seed={}", s)) }
+        if let Some(it) = self.iteration { pairs.push(format!("it={}", it)) }
+        pairs.join(" ")
+    }
+
+    /// fields read from a header line; unknown keys are ignored and missing ones stay None
+    pub fn from_info(line: &str) -> Metadata {
+        let mut meta = Metadata::default();
+        for (key, value) in key_values(line) {
+            match key.as_str() {
+                "energy" => meta.energy = value.parse().ok(),
+                "potential" => meta.potential = Some(value),
+                "version" => meta.version = Some(value),
+                "seed" => meta.seed = value.parse().ok(),
+                "it" => meta.iteration = value.parse().ok(),
+                _ => {}
+            }
+        }
+        meta
+    }
+
+    /// metadata of the first frame of an XYZ or extended XYZ file; files without a header give
+    /// the default
+    pub fn from_file(path: &str) -> Result<Metadata, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+        Ok(match lines.next() {
+            Some(first) if first.trim().parse::<usize>().is_ok() => lines.next().map(Metadata::from_info).unwrap_or_default(),
+            Some(first) => first.trim_start().strip_prefix('#').map(Metadata::from_info).unwrap_or_default(),
+            None => Metadata::default(),
+        })
+    }
+
+    /// warnings for a structure written by another version or with another potential than the
+    /// one it is used with; its energies and geometry may not carry over
+    pub fn check(&self, path: &str, potential: &Potential) {
+        if let Some(v) = self.version.as_deref().filter(|&v| v != VERSION) {
+            tracing::warn!(path, written = v, running = VERSION, "structure written by another version");
+        }
+        if let Some(p) = self.potential.as_deref().filter(|&p| p != potential.name()) {
+            tracing::warn!(path, written = p, using = %potential.name(), "structure written with another potential");
+        }
+    }
+}
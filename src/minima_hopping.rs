@@ -0,0 +1,94 @@
+use crate::Fuleren;
+
+/// minima hopping (Goedecker 2004): MD escapes from the current minimum, local relaxation,
+/// and feedback on the MD temperature from the history of visited minima
+pub struct MinimaHopping {
+    pub kt: f64,          // MD kinetic temperature [eV]
+    pub e_diff: f64,      // acceptance threshold for the new minimum [eV]
+    pub beta_same: f64,   // kt factor when the escape fell back into the same minimum
+    pub beta_old: f64,    // kt factor when the new minimum was visited before
+    pub beta_new: f64,    // kt factor when the new minimum is new
+    pub alpha_acc: f64,   // e_diff factor on acceptance
+    pub alpha_rej: f64,   // e_diff factor on rejection
+    pub md_minima: usize, // potential energy minima crossed before the MD is stopped
+    pub dt: f64,
+    pub e_tol: f64,       // two minima closer in energy than this are the same
+    pub f_tol: f64,       // relaxation force tolerance
+    pub history: Vec<f64>,
+}
+
+impl Default for MinimaHopping {
+    fn default() -> Self {
+        MinimaHopping { kt: 0.1,
+                        e_diff: 0.5,
+                        beta_same: 1.1,
+                        beta_old: 1.05,
+                        beta_new: 1./1.05,
+                        alpha_acc: 1./1.02,
+                        alpha_rej: 1.02,
+                        md_minima: 3,
+                        dt: 0.1,
+                        e_tol: 1e-3,
+                        f_tol: 1e-2,
+                        history: Vec::new() }
+    }
+}
+
+impl MinimaHopping {
+    /// runs n_hops hops starting from f; returns the lowest minimum found
+    pub fn run(&mut self, f: &mut Fuleren, n_hops: usize) -> Fuleren {
+        f.relax(self.f_tol, 10_000);
+        self.history.push(f.E);
+        let mut best = f.clone();
+
+        for _ in 0..n_hops {
+            let trial = self.escape(f);
+
+            if self._seen(trial.E) { self.kt *= self.beta_old; }
+            else {
+                self.kt *= self.beta_new;
+                self.history.push(trial.E);
+            }
+
+            if trial.E - f.E < self.e_diff {
+                *f = trial;
+                self.e_diff *= self.alpha_acc;
+                if f.E < best.E { best = f.clone(); }
+            }
+            else {
+                self.e_diff *= self.alpha_rej;
+            }
+        }
+        best
+    }
+
+    // MD + relaxation until a minimum different from the current one is found
+    fn escape(&mut self, f: &Fuleren) -> Fuleren {
+        loop {
+            let mut trial = f.clone();
+            let mut v = trial.random_velocities(1./self.kt);
+            let mut forces = trial.forces();
+
+            // potential energy minima are kinetic energy maxima, which are cheap to track
+            let mut minima = 0;
+            let mut k_prev = trial.kinetic_energy(&v);
+            let mut rising = false;
+            for _ in 0..10_000 {
+                trial.verlet_step(&mut v, &mut forces, self.dt);
+                let k = trial.kinetic_energy(&v);
+                if rising && k < k_prev { minima += 1; }
+                rising = k > k_prev;
+                k_prev = k;
+                if minima >= self.md_minima { break }
+            }
+
+            trial.relax(self.f_tol, 10_000);
+            if (trial.E - f.E).abs() > self.e_tol { return trial }
+            self.kt *= self.beta_same;
+        }
+    }
+
+    fn _seen(&self, e: f64) -> bool {
+        self.history.iter().any(|h| (h - e).abs() < self.e_tol)
+    }
+}
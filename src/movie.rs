@@ -0,0 +1,51 @@
+//! "watch the cage form": the anneal recorded as a multi-frame extended XYZ trajectory (every
+//! frame with per-atom coordination and the temperature in the comment line) together with an
+//! OVITO script that renders it into an animation with the atoms colored by coordination
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::utilities::{get_file_buffer, write_atomic};
+use crate::Fuleren;
+
+/// appends frames to a trajectory file
+pub struct FrameWriter {
+    out: BufWriter<File>,
+    pub frames: usize,
+}
+
+impl FrameWriter {
+    pub fn create(path: &str) -> io::Result<FrameWriter> {
+        Ok(FrameWriter { out: get_file_buffer(path)?, frames: 0 })
+    }
+
+    /// frame of f with `info` ("key=value ...") in the comment line; expects f.E to be up to date
+    pub fn write(&mut self, f: &Fuleren, info: &str) -> io::Result<()> {
+        f.write_extxyz(&mut self.out, info)?;
+        self.frames += 1;
+        // frames are seldom, a crash should not lose them
+        self.out.flush()
+    }
+}
+
+/// writes an OVITO (3.x) Python script next to the trajectory that renders it into `movie`
+/// (.gif, .mp4, ... as supported by OVITO); run it with `ovitos <script>` or a python with the
+/// ovito module, from the directory of the script
+pub fn save_ovito_script(path: &str, trajectory: &str, movie: &str) -> io::Result<()> {
+    write_atomic(path, |f| {
+        write!(f, r#"# renders {trajectory} into {movie}, atoms colored by coordination on a rainbow scale from 1 to 4 bonds
+from ovito.io import import_file
+from ovito.modifiers import ColorCodingModifier, CreateBondsModifier
+from ovito.vis import Viewport
+
+pipeline = import_file("{trajectory}")
+pipeline.modifiers.append(CreateBondsModifier(cutoff=1.7))
+pipeline.modifiers.append(ColorCodingModifier(property="coordination", start_value=1, end_value=4,
+                                              gradient=ColorCodingModifier.Rainbow()))
+pipeline.add_to_scene()
+
+viewport = Viewport(type=Viewport.Type.Perspective, camera_dir=(-1, -1, -1))
+viewport.zoom_all()
+viewport.render_anim(filename="{movie}", size=(640, 480), fps=10)
+"#)
+    })
+}
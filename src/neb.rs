@@ -0,0 +1,119 @@
+use ndarray::prelude::*;
+
+use crate::{Fuleren, M_C};
+
+/// climbing image nudged elastic band between two relaxed structures with the same atom ordering
+pub struct Neb {
+    pub images: Vec<Fuleren>, // including both end points
+    pub k: f64,               // spring constant [eV/A^2]
+    pub climbing: bool,
+}
+
+impl Neb {
+    /// path linearly interpolated in cartesian coordinates with n_images inner images
+    pub fn new(a: &Fuleren, b: &Fuleren, n_images: usize) -> Neb {
+        assert_eq!(a.size, b.size, "NEB end points must have the same number of atoms");
+
+        let xyz_a = a.xyz_array();
+        let xyz_b = b.xyz_array();
+        let images = (0..n_images + 2).map(|m| {
+            let t = m as f64/(n_images + 1) as f64;
+            let mut image = a.clone();
+            image.set_xyz_array(&((1. - t)*&xyz_a + t*&xyz_b));
+            image.energy_calc();
+            image
+        }).collect();
+
+        Neb { images, k: 5., climbing: true }
+    }
+
+    /// relaxes the band with quick-min until the largest NEB force is below f_tol [eV/A];
+    /// returns number of steps done
+    pub fn run(&mut self, f_tol: f64, max_steps: usize) -> usize {
+        let dt = 0.1;
+        let max_shift = 0.05;
+        let n = self.images.len();
+        let mut v: Vec<Array2<f64>> = self.images.iter().map(|im| Array2::zeros((im.size, 3))).collect();
+
+        let mut step = 0;
+        while step < max_steps {
+            let forces = self.neb_forces();
+            let f_max = forces.iter().flat_map(|f| f.iter()).fold(0., |m: f64, x| m.max(x.abs()));
+            if f_max < f_tol { break }
+
+            for m in 1..n-1 {
+                let f = &forces[m - 1];
+                // quick-min: keep only the velocity component along the force
+                let f_norm = f.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+                let vf = (&v[m]*f).sum();
+                v[m] = if vf > 0. { vf/f_norm.powi(2)*f } else { Array2::zeros(f.raw_dim()) };
+                v[m] = &v[m] + &(dt/M_C*f);
+
+                let mut shift = dt*&v[m];
+                let len = shift.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+                if len > max_shift { shift *= max_shift/len; }
+                let xyz = self.images[m].xyz_array() + shift;
+                self.images[m].set_xyz_array(&xyz);
+            }
+            step += 1;
+        }
+        self.energies();
+        step
+    }
+
+    // NEB forces on the inner images: perpendicular true force + parallel spring force,
+    // the highest image climbs instead
+    fn neb_forces(&mut self) -> Vec<Array2<f64>> {
+        let n = self.images.len();
+        let e: Vec<f64> = self.energies();
+        let xyz: Vec<Array2<f64>> = self.images.iter().map(|im| im.xyz_array()).collect();
+        let i_max = (1..n-1).max_by(|&a, &b| e[a].partial_cmp(&e[b]).unwrap()).unwrap_or(1);
+
+        (1..n-1).map(|m| {
+            let tau = tangent(&xyz, &e, m);
+            let f = self.images[m].forces();
+            let f_par = (&f*&tau).sum();
+
+            if self.climbing && m == i_max {
+                &f - &(2.*f_par*&tau)
+            }
+            else {
+                let d_next = dist(&xyz[m + 1], &xyz[m]);
+                let d_prev = dist(&xyz[m], &xyz[m - 1]);
+                &f - &(f_par*&tau) + self.k*(d_next - d_prev)*&tau
+            }
+        }).collect()
+    }
+
+    /// energies along the path
+    pub fn energies(&mut self) -> Vec<f64> {
+        self.images.iter_mut().map(|im| im.energy_calc()).collect()
+    }
+
+    /// forward barrier: highest image energy minus the energy of the first end point
+    pub fn barrier(&mut self) -> f64 {
+        let e = self.energies();
+        e.iter().cloned().fold(f64::MIN, f64::max) - e[0]
+    }
+}
+
+// improved tangent (Henkelman, Jonsson 2000), normalized
+fn tangent(xyz: &[Array2<f64>], e: &[f64], m: usize) -> Array2<f64> {
+    let t_next = &xyz[m + 1] - &xyz[m];
+    let t_prev = &xyz[m] - &xyz[m - 1];
+
+    let tau = if e[m + 1] > e[m] && e[m] > e[m - 1] { t_next }
+    else if e[m + 1] < e[m] && e[m] < e[m - 1] { t_prev }
+    else {
+        let de_max = f64::max((e[m + 1] - e[m]).abs(), (e[m - 1] - e[m]).abs());
+        let de_min = f64::min((e[m + 1] - e[m]).abs(), (e[m - 1] - e[m]).abs());
+        if e[m + 1] > e[m - 1] { de_max*&t_next + de_min*&t_prev }
+        else { de_min*&t_next + de_max*&t_prev }
+    };
+    let norm = tau.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+    tau/norm
+}
+
+fn dist(a: &Array2<f64>, b: &Array2<f64>) -> f64 {
+    (a - b).iter().map(|x| x.powi(2)).sum::<f64>().sqrt()
+}
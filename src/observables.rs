@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use crate::utilities::get_file_buffer;
+
+/// streams rows "it value..." to disk while the run goes; flushed every flush_rows rows or
+/// flush_interval, whichever comes first, so a crash loses at most the last few rows
+pub struct ObservableWriter<W: Write> {
+    out: W,
+    n_columns: usize,
+    pub flush_rows: usize,
+    pub flush_interval: Duration,
+    rows: usize,
+    last_flush: Instant,
+}
+
+impl ObservableWriter<BufWriter<File>> {
+    /// creates the file and writes the header "# it <columns>"
+    pub fn create(path: &str, columns: &[&str]) -> io::Result<Self> {
+        ObservableWriter::new(get_file_buffer(path)?, columns)
+    }
+}
+
+impl<W: Write> ObservableWriter<W> {
+    pub fn new(mut out: W, columns: &[&str]) -> io::Result<Self> {
+        write!(out, "# {:<8}", "it")?;
+        for c in columns {
+            write!(out, " {:<12}", c)?;
+        }
+        writeln!(out)?;
+        Ok(ObservableWriter { out, n_columns: columns.len(), flush_rows: 100, flush_interval: Duration::from_secs(10),
+                              rows: 0, last_flush: Instant::now() })
+    }
+
+    pub fn row(&mut self, it: usize, values: &[f64]) -> io::Result<()> {
+        debug_assert_eq!(values.len(), self.n_columns, "wrong number of observables");
+        write!(self.out, "  {:<8}", it)?;
+        for v in values {
+            write!(self.out, " {:<12.5}", v)?;
+        }
+        writeln!(self.out)?;
+
+        self.rows += 1;
+        if self.rows >= self.flush_rows || self.last_flush.elapsed() >= self.flush_interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.rows = 0;
+        self.last_flush = Instant::now();
+        self.out.flush()
+    }
+}
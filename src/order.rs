@@ -0,0 +1,79 @@
+use std::f64::consts::PI;
+
+use crate::harmonics::{angles, spherical_harmonic, wigner_3j};
+use crate::{Fuleren, R1};
+
+/// Steinhardt bond-orientational order of degree l: q_lm averaged over the bonds (pairs inside R1)
+/// of every atom and of the whole cluster, Q_l = sqrt(4pi/(2l+1) sum_m |q_lm|^2) and the normalized
+/// third-order invariant W_l. A fullerene cage, with every atom in the same local environment, has
+/// sharp per-atom values; an amorphous blob spreads them out
+#[derive(Debug, Clone)]
+pub struct BondOrder {
+    pub q: f64,
+    pub w: f64,
+    pub q_atom: Vec<f64>, // 0 for atoms without bonds
+    pub w_atom: Vec<f64>,
+}
+
+impl Fuleren {
+    pub fn bond_order(&self, l: usize) -> BondOrder {
+        let bonds = self.bond_graph(R1);
+        let mut q_global = vec![(0., 0.); 2*l + 1];
+        let mut n_global = 0;
+        let (mut q_atom, mut w_atom) = (vec![0.; self.size], vec![0.; self.size]);
+
+        for i in 0..self.size {
+            if bonds[i].is_empty() { continue }
+            let mut q_lm = vec![(0., 0.); 2*l + 1];
+            for &j in &bonds[i] {
+                let (theta, phi) = angles(&self._vec_ij::<f64>(i, j));
+                for (k, m) in (-(l as i64)..=l as i64).enumerate() {
+                    let y = spherical_harmonic(l, m, theta, phi);
+                    q_lm[k].0 += y.0;
+                    q_lm[k].1 += y.1;
+                }
+            }
+            for k in 0..2*l + 1 {
+                q_global[k].0 += q_lm[k].0;
+                q_global[k].1 += q_lm[k].1;
+            }
+            n_global += bonds[i].len();
+
+            let n = bonds[i].len() as f64;
+            let q_lm: Vec<(f64, f64)> = q_lm.iter().map(|(re, im)| (re/n, im/n)).collect();
+            q_atom[i] = _q(l, &q_lm);
+            w_atom[i] = _w(l, &q_lm);
+        }
+
+        let n = n_global.max(1) as f64;
+        let q_global: Vec<(f64, f64)> = q_global.iter().map(|(re, im)| (re/n, im/n)).collect();
+        BondOrder { q: _q(l, &q_global), w: _w(l, &q_global), q_atom, w_atom }
+    }
+}
+
+// Q_l of q_lm, m = -l..=l
+fn _q(l: usize, q_lm: &[(f64, f64)]) -> f64 {
+    let sum = q_lm.iter().map(|(re, im)| re*re + im*im).sum::<f64>();
+    (4.*PI/(2*l + 1) as f64*sum).sqrt()
+}
+
+// W_l = sum over m1 + m2 + m3 = 0 of the 3j symbol times q_lm1 q_lm2 q_lm3, over (sum_m |q_lm|^2)^(3/2);
+// the sum is real, only its real part is accumulated
+fn _w(l: usize, q_lm: &[(f64, f64)]) -> f64 {
+    let norm = q_lm.iter().map(|(re, im)| re*re + im*im).sum::<f64>().powf(1.5);
+    if norm == 0. {
+        return 0.
+    }
+    let l = l as i64;
+    let mut w = 0.;
+    for m1 in -l..=l {
+        for m2 in -l..=l {
+            let m3 = -m1 - m2;
+            if m3.abs() > l { continue }
+            let (a, b, c) = (q_lm[(m1 + l) as usize], q_lm[(m2 + l) as usize], q_lm[(m3 + l) as usize]);
+            let ab = (a.0*b.0 - a.1*b.1, a.0*b.1 + a.1*b.0);
+            w += wigner_3j(l, l, l, m1, m2, m3)*(ab.0*c.0 - ab.1*c.1);
+        }
+    }
+    w/norm
+}
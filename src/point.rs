@@ -0,0 +1,113 @@
+//! atom position held in cartesian and spherical coordinates at once: the moves work in r, phi
+//! and theta, the energy kernel in x, y and z. The fields are private, so the two can only change
+//! together, through the constructors and scale
+use std::f64::consts::PI;
+
+use crate::check_angles;
+
+#[derive( Debug, Clone)]
+pub(crate) struct Point6 {
+    x: f64,
+    y: f64,
+    z: f64,
+    r: f64,
+    phi: f64,
+    theta: f64
+}
+
+impl Point6 {
+    pub fn new() -> Point6 {
+        Point6 {x: 0., y: 0., z: 0., r: 0., phi: 0., theta: 0.}
+    }
+
+    pub fn from_cartesian(data: &[f64; 3]) -> Point6 {
+        let xt:f64 = data[0];
+        let yt = data[1];
+        let zt = data[2];
+        let rt = (xt.powi(2) + yt.powi(2) + zt.powi(2)).sqrt();
+        Point6 { x: xt, 
+                 y: yt, 
+                 z: zt, 
+                 r: rt, 
+                 phi: yt.atan2(xt).rem_euclid(2.*PI), // atan(y/x) loses the quadrant
+                 theta: (zt/rt).acos() }
+    }
+
+    /// any angles, normalized to phi [0, 2*PI), theta [0, PI] without moving the point
+    pub fn from_spherical(data: &[f64; 3]) -> Point6 {
+        let r = data[0];
+        let (phi, theta) = check_angles(data[1], data[2]);
+        debug_assert!((0. ..=2.*PI).contains(&phi) && (0. ..=PI).contains(&theta),
+                      "angles out of range: phi = {}, theta = {}", phi, theta);
+
+        Point6 { x: r*theta.sin()*phi.cos(), 
+                 y: r*theta.sin()*phi.sin(), 
+                 z: r*theta.cos(), 
+                 r, 
+                 phi, 
+                 theta }
+    }
+
+    #[inline]
+    pub fn x(&self) -> f64 { self.x }
+    #[inline]
+    pub fn y(&self) -> f64 { self.y }
+    #[inline]
+    pub fn z(&self) -> f64 { self.z }
+    #[inline]
+    pub fn r(&self) -> f64 { self.r }
+    #[inline]
+    pub fn phi(&self) -> f64 { self.phi }
+    #[inline]
+    pub fn theta(&self) -> f64 { self.theta }
+
+    pub fn xyz(&self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    // methods
+
+    /// scales the distance from the origin by s, keeping the angles
+    pub fn scale(&mut self, s: f64) {
+        self.x *= s;
+        self.y *= s;
+        self.z *= s;
+        self.r *= s;
+    }
+}
+
+impl std::fmt::Display for Point6 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<10.5}\t{:<10.5}\t{:<10.5}\t{:<10.5}\t{:<10.5}\t{:<10.5}",
+                 self.x, self.y, self.z, self.r, self.phi, self.theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn cartesian_round_trip(x in -10f64..10., y in -10f64..10., z in -10f64..10.) {
+            prop_assume!(x*x + y*y + z*z > 1e-6);
+            let p = Point6::from_cartesian(&[x, y, z]);
+            let q = Point6::from_spherical(&[p.r(), p.phi(), p.theta()]);
+            for (a, b) in p.xyz().iter().zip(q.xyz()) {
+                prop_assert!((a - b).abs() < 1e-9, "{} -> {}", p, q);
+            }
+        }
+
+        #[test]
+        fn spherical_round_trip(r in 0.1f64..10., phi in -20f64..20., theta in -20f64..20.) {
+            let p = Point6::from_spherical(&[r, phi, theta]);
+            let q = Point6::from_cartesian(&p.xyz());
+            prop_assert!((p.r() - q.r()).abs() < 1e-9);
+            for (a, b) in p.xyz().iter().zip(q.xyz()) {
+                prop_assert!((a - b).abs() < 1e-9, "{} -> {}", p, q);
+            }
+            prop_assert!((0. ..=2.*PI).contains(&p.phi()) && (0. ..=PI).contains(&p.theta()));
+        }
+    }
+}
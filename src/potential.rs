@@ -0,0 +1,532 @@
+use num_traits::Float;
+
+use crate::tables::Tables;
+use crate::{_mod_arr, cst, Fuleren, R0, R1, R2, DE, S, LAMBDA, DEL, A0, C0, D0};
+
+/// terms added to the Brenner potential; the default is plain Brenner. Set from the config
+/// file (see from_config), so the same config reproduces the run
+#[derive(Debug, Clone, Default)]
+pub struct Potential {
+    pub brenner: Brenner,
+    pub lj: Option<LennardJones>,
+    pub torsion: Option<Torsion>,
+    pub coulomb: Option<Coulomb>,
+    pub wall: Option<Wall>,
+    pub tables: Option<Tables>, // tabulated V_R, V_A and g instead of the analytic forms
+    pub shell: Option<ShellRestraint>, // set by the anneal (see shell_release), not by the config
+}
+
+// plain Brenner with the given constants
+impl From<Brenner> for Potential {
+    fn from(brenner: Brenner) -> Self {
+        Potential { brenner, ..Default::default() }
+    }
+}
+
+/// constants of the Brenner potential, parameter set I of Brenner (1990) by default: pair terms
+/// V_R = De/(S-1) exp(-sqrt(2S) lambda (r-R0)), V_A = De S/(S-1) exp(-sqrt(2/S) lambda (r-R0)),
+/// cutoff switching from R1 to R2, bond order exponent del and angular function g(a0, c0, d0)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Brenner {
+    pub r0: f64,
+    pub r1: f64,
+    pub r2: f64,
+    pub de: f64,
+    pub s: f64,
+    pub lambda: f64,
+    pub delta: f64,
+    pub a0: f64,
+    pub c0: f64,
+    pub d0: f64,
+}
+
+impl Default for Brenner {
+    fn default() -> Self {
+        Brenner { r0: R0, r1: R1, r2: R2, de: DE, s: S, lambda: LAMBDA, delta: DEL, a0: A0, c0: C0, d0: D0 }
+    }
+}
+
+impl Brenner {
+    pub fn v_r<T: Float>(&self, r: T) -> T {
+        cst::<T>(self.de/(self.s - 1.)) * (-cst::<T>((2.*self.s).sqrt()*self.lambda)*(r - cst(self.r0))).exp()
+    }
+
+    pub fn v_a<T: Float>(&self, r: T) -> T {
+        cst::<T>(self.de*self.s/(self.s - 1.)) * (-cst::<T>((2./self.s).sqrt()*self.lambda)*(r - cst(self.r0))).exp()
+    }
+
+    /// smooth cutoff, 1 up to R1 and 0 from R2
+    pub fn cutoff<T: Float>(&self, r: T) -> T {
+        let (r1, r2) = (cst::<T>(self.r1), cst::<T>(self.r2));
+        if r <= r1 { T::one() }
+        else if r <= r2 { cst::<T>(0.5)*(T::one() + ((r - r1)/(r2 - r1)*cst(std::f64::consts::PI)).cos()) }
+        else { T::zero() }
+    }
+
+    /// config lines in the from_config format
+    pub fn to_config(&self) -> String {
+        format!("R0 {}\nR1 {}\nR2 {}\nDe {}\nS {}\nlambda {}\ndel {}\na0 {}\nc0 {}\nd0 {}\n",
+                self.r0, self.r1, self.r2, self.de, self.s, self.lambda, self.delta, self.a0, self.c0, self.d0)
+    }
+
+    /// checks that the constants give a well defined potential
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0. < self.r1 && self.r1 < self.r2) {
+            return Err(format!("need 0 < R1 < R2, got R1 = {} and R2 = {}", self.r1, self.r2));
+        }
+        if self.s <= 1. {
+            return Err(format!("need S > 1, got {}", self.s));
+        }
+        for (name, value) in [("R0", self.r0), ("De", self.de), ("lambda", self.lambda), ("del", self.delta), ("d0", self.d0)] {
+            if value <= 0. {
+                return Err(format!("need {} > 0, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// dispersion between atoms beyond the Brenner cutoff, the LJ part of AIREBO:
+/// 4 eps ((sigma/r)^12 - (sigma/r)^6), shifted to zero at r_cut. The repulsive wall would count
+/// the covalent region twice, so below the minimum r_m = 2^(1/6) sigma the well bottom is
+/// continued and switched off smoothly (cosine) towards R2
+#[derive(Debug, Clone)]
+pub struct LennardJones {
+    pub epsilon: f64, // [eV]
+    pub sigma: f64,   // [A]
+    pub r_cut: f64,   // [A]
+}
+
+impl Default for LennardJones {
+    fn default() -> Self {
+        // carbon parameters of AIREBO
+        LennardJones { epsilon: 0.00284, sigma: 3.4, r_cut: 10.2 }
+    }
+}
+
+impl LennardJones {
+    /// pair energy at distance r, for the Brenner cutoff r2
+    pub fn pair<T: Float>(&self, r: T, r2: f64) -> T {
+        let (r2, r_cut) = (cst::<T>(r2), cst::<T>(self.r_cut));
+        let r_m = cst::<T>(2f64.powf(1./6.)*self.sigma);
+        if r <= r2 || r >= r_cut {
+            return T::zero()
+        }
+        if r < r_m {
+            let switch = cst::<T>(0.5)*(T::one() - ((r - r2)/(r_m - r2)*cst(std::f64::consts::PI)).cos());
+            return switch*self._lj(r_m)
+        }
+        self._lj(r)
+    }
+
+    // shifted LJ
+    fn _lj<T: Float>(&self, r: T) -> T {
+        let lj = |r: T| {
+            let s6 = (cst::<T>(self.sigma)/r).powi(6);
+            cst::<T>(4.*self.epsilon)*(s6*s6 - s6)
+        };
+        lj(r) - lj(cst(self.r_cut))
+    }
+}
+
+/// 4-body term on the dihedrals k-i-j-l of bonded chains, the torsion part of AIREBO:
+/// f_c(r_ki) f_c(r_ij) f_c(r_jl) eps (256/405 cos^10(w/2) - 1/10), w the dihedral angle
+/// (0 for cis). Favours the staggered arrangements of curved networks over eclipsed ones
+#[derive(Debug, Clone)]
+pub struct Torsion {
+    pub epsilon: f64, // [eV]
+}
+
+impl Default for Torsion {
+    fn default() -> Self {
+        // C-C-C-C value of AIREBO
+        Torsion { epsilon: 0.3079 }
+    }
+}
+
+impl Torsion {
+    /// energy of a dihedral with angle w, given its cos
+    pub fn dihedral<T: Float>(&self, cos_w: T) -> T {
+        // cos^2(w/2) = (1 + cos w)/2
+        let c2 = cst::<T>(0.5)*(T::one() + cos_w);
+        cst::<T>(self.epsilon)*(cst::<T>(256./405.)*c2.powi(5) - cst(0.1))
+    }
+}
+
+/// electrostatics of partial charges for charged cages (C60+, C60-) and endohedral ions: the
+/// damped shifted Coulomb pair of Wolf et al. (1999), k q_i q_j (erfc(alpha r)/r - erfc(alpha r_cut)/r_cut)
+/// up to r_cut, between all pairs including the bonded ones
+#[derive(Debug, Clone)]
+pub struct Coulomb {
+    pub charge: f64,                  // total [e], spread equally over the atoms when charges is empty
+    pub charges: Vec<f64>,            // per atom [e]
+    pub charges_path: Option<String>, // file the charges were read from
+    pub alpha: f64,                   // damping [1/A]
+    pub r_cut: f64,                   // [A]
+}
+
+impl Default for Coulomb {
+    fn default() -> Self {
+        Coulomb { charge: 0., charges: Vec::new(), charges_path: None, alpha: 0.2, r_cut: 10. }
+    }
+}
+
+// e^2/(4 pi eps0) [eV A]
+const COULOMB_K: f64 = 14.3996454;
+
+impl Coulomb {
+    /// charge of atom i of n [e]
+    pub fn q(&self, i: usize, n: usize) -> f64 {
+        if self.charges.is_empty() { self.charge/n as f64 } else { self.charges[i] }
+    }
+
+    /// pair energy of charges with product qq at distance r
+    pub fn pair<T: Float>(&self, qq: T, r: T) -> T {
+        let r_cut = cst::<T>(self.r_cut);
+        if r >= r_cut {
+            return T::zero()
+        }
+        let alpha = cst::<T>(self.alpha);
+        cst::<T>(COULOMB_K)*qq*(_erfc(alpha*r)/r - _erfc(alpha*r_cut)/r_cut)
+    }
+}
+
+// complementary error function, Abramowitz and Stegun 7.1.26 (error below 1.5e-7), x >= 0
+fn _erfc<T: Float>(x: T) -> T {
+    let t = T::one()/(T::one() + cst::<T>(0.3275911)*x);
+    let poly = [0.254829592, -0.284496736, 1.421413741, -1.453152027, 1.061405429]
+        .iter().rev().fold(T::zero(), |acc, &a| (acc + cst(a))*t);
+    poly*(-x*x).exp()
+}
+
+/// radial harmonic restraint 1/2 k (r - radius)^2 of every atom towards a sphere about the origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShellRestraint {
+    pub radius: f64, // [A]
+    pub k: f64,      // [eV/A^2]
+}
+
+impl ShellRestraint {
+    /// term of V_i of an atom at distance r from the origin, twice its energy as E = 1/2 sum V_i
+    pub fn v_i<T: Float>(&self, r: T) -> T {
+        cst::<T>(self.k)*(r - cst(self.radius)).powi(2)
+    }
+}
+
+/// confining sphere about the origin, harmonic beyond its radius, that keeps evaporated atoms
+/// from leaving during the hot part of an anneal. With a scale the radius follows scale*mean_r,
+/// moved by the anneal before every sweep (see Fuleren::place_wall); until then there is no wall
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wall {
+    pub k: f64,             // [eV/A^2]
+    pub radius: f64,        // [A]
+    pub scale: Option<f64>, // radius relative to mean_r
+}
+
+impl Default for Wall {
+    fn default() -> Self {
+        Wall { k: 1., radius: f64::INFINITY, scale: None }
+    }
+}
+
+impl Wall {
+    /// term of V_i of an atom at distance r from the origin, twice its energy 1/2 k (r - radius)^2
+    /// beyond the wall
+    pub fn v_i<T: Float>(&self, r: T) -> T {
+        let out = r - cst(self.radius);
+        if out > T::zero() { cst::<T>(self.k)*out*out } else { T::zero() }
+    }
+}
+
+/// config keys read by Potential::from_config (and skipped by Schedule::from_config)
+pub const POTENTIAL_KEYS: [&str; 25] = ["R0", "R1", "R2", "De", "S", "lambda", "del", "a0", "c0", "d0",
+                                         "lj", "lj_epsilon", "lj_sigma", "lj_cutoff", "torsion", "torsion_epsilon",
+                                         "coulomb", "charge", "charges", "coulomb_alpha", "coulomb_cutoff",
+                                         "wall_k", "wall_radius", "wall_scale", "tables"];
+
+impl Potential {
+    /// reads the potential keys of a "key value" config: the Brenner constants R0, R1, R2, De, S,
+    /// lambda, del, a0, c0 and d0 override the defaults (checked by Brenner::validate); lj (on, off) switches the dispersion
+    /// term on, lj_epsilon [eV], lj_sigma [A] and lj_cutoff [A] change its parameters;
+    /// torsion (on, off) and torsion_epsilon [eV] likewise for the torsion term;
+    /// coulomb (on, off) switches the electrostatics on, charge [e] is the total charge spread over
+    /// the atoms, charges <file> gives one charge per atom instead (a number per line, relative to
+    /// the config file), coulomb_alpha [1/A] and coulomb_cutoff [A] set the damping;
+    /// wall_k [eV/A^2] with wall_radius [A] or wall_scale (radius over mean_r) confine the atoms to
+    /// a sphere (any of them enables it, one of the last two is needed);
+    /// tables <file> reads tabulated pieces (see tables.rs), relative to the config file
+    pub fn from_config(path: &str) -> Result<Potential, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Potential::from_config_str(&content, path)
+    }
+
+    /// from_config on the content of a config; path labels the errors and anchors relative paths
+    pub fn from_config_str(content: &str, path: &str) -> Result<Potential, String> {
+        let mut potential = Potential::default();
+        let mut lj = LennardJones::default();
+        let mut lj_on = false;
+        let mut torsion = Torsion::default();
+        let mut torsion_on = false;
+        let mut coulomb = Coulomb::default();
+        let mut coulomb_on = false;
+        let mut wall: Option<Wall> = None;
+
+        for (n, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            let Some((key, value)) = line.split_once(char::is_whitespace) else { continue };
+            let value = value.trim();
+            let err = || format!("{}:{}: cannot parse \"{}\"", path, n + 1, line);
+            let float = || value.parse::<f64>().map_err(|_| err());
+            let switch = || match value {
+                "on" => Ok(true),
+                "off" => Ok(false),
+                _ => Err(err()),
+            };
+
+            let brenner = &mut potential.brenner;
+            match key {
+                "R0" => brenner.r0 = float()?,
+                "R1" => brenner.r1 = float()?,
+                "R2" => brenner.r2 = float()?,
+                "De" => brenner.de = float()?,
+                "S" => brenner.s = float()?,
+                "lambda" => brenner.lambda = float()?,
+                "del" => brenner.delta = float()?,
+                "a0" => brenner.a0 = float()?,
+                "c0" => brenner.c0 = float()?,
+                "d0" => brenner.d0 = float()?,
+                "lj" => lj_on = switch()?,
+                "lj_epsilon" => lj.epsilon = float()?,
+                "lj_sigma" => lj.sigma = float()?,
+                "lj_cutoff" => lj.r_cut = float()?,
+                "torsion" => torsion_on = switch()?,
+                "torsion_epsilon" => torsion.epsilon = float()?,
+                "coulomb" => coulomb_on = switch()?,
+                "charge" => coulomb.charge = float()?,
+                "charges" => {
+                    let charges_path = std::path::Path::new(path).with_file_name(value);
+                    coulomb.charges = read_charges(&charges_path.to_string_lossy())?;
+                    coulomb.charges_path = Some(charges_path.to_string_lossy().into_owned());
+                }
+                "coulomb_alpha" => coulomb.alpha = float()?,
+                "coulomb_cutoff" => coulomb.r_cut = float()?,
+                "wall_k" => wall.get_or_insert_with(Wall::default).k = float()?,
+                "wall_radius" => wall.get_or_insert_with(Wall::default).radius = float()?,
+                "wall_scale" => wall.get_or_insert_with(Wall::default).scale = Some(float()?),
+                "tables" => {
+                    let tables_path = std::path::Path::new(path).with_file_name(value);
+                    potential.tables = Some(Tables::from_file(&tables_path.to_string_lossy())?);
+                }
+                _ => {}
+            }
+        }
+        potential.brenner.validate().map_err(|e| format!("{}: {}", path, e))?;
+        if lj_on {
+            if lj.r_cut <= 2f64.powf(1./6.)*lj.sigma || 2f64.powf(1./6.)*lj.sigma <= potential.brenner.r2 {
+                return Err(format!("{}: LJ needs R2 < 2^(1/6) lj_sigma < lj_cutoff", path));
+            }
+            potential.lj = Some(lj);
+        }
+        if torsion_on {
+            potential.torsion = Some(torsion);
+        }
+        if coulomb_on {
+            if coulomb.alpha < 0. || coulomb.r_cut <= 0. {
+                return Err(format!("{}: need coulomb_alpha >= 0 and coulomb_cutoff > 0", path));
+            }
+            potential.coulomb = Some(coulomb);
+        }
+        if let Some(wall) = wall {
+            if wall.k <= 0. || (wall.radius.is_infinite() && wall.scale.is_none()) {
+                return Err(format!("{}: the wall needs wall_k > 0 and wall_radius or wall_scale", path));
+            }
+            potential.wall = Some(wall);
+        }
+        Ok(potential)
+    }
+
+    /// config lines in the from_config format
+    pub fn to_config(&self) -> String {
+        let mut config = self.brenner.to_config();
+        config += &match &self.lj {
+            Some(lj) => format!("lj on\nlj_epsilon {}\nlj_sigma {}\nlj_cutoff {}\n", lj.epsilon, lj.sigma, lj.r_cut),
+            None => "lj off\n".to_string(),
+        };
+        config += &match &self.torsion {
+            Some(t) => format!("torsion on\ntorsion_epsilon {}\n", t.epsilon),
+            None => "torsion off\n".to_string(),
+        };
+        config += &match &self.coulomb {
+            Some(c) => {
+                let charges = match &c.charges_path {
+                    Some(path) => format!("charges {}\n", path),
+                    None => format!("charge {}\n", c.charge),
+                };
+                format!("coulomb on\n{}coulomb_alpha {}\ncoulomb_cutoff {}\n", charges, c.alpha, c.r_cut)
+            }
+            None => "coulomb off\n".to_string(),
+        };
+        if let Some(wall) = &self.wall {
+            config += &match wall.scale {
+                Some(scale) => format!("wall_k {}\nwall_scale {}\n", wall.k, scale),
+                None => format!("wall_k {}\nwall_radius {}\n", wall.k, wall.radius),
+            };
+        }
+        if let Some(tables) = &self.tables {
+            config += &format!("tables {}\n", tables.path);
+        }
+        config
+    }
+
+    /// short name of the terms, "brenner" with "+lj", "+torsion", "+coulomb" and "+tables" for the extensions;
+    /// the constants are not part of it
+    pub fn name(&self) -> String {
+        let mut name = "brenner".to_string();
+        if self.lj.is_some() { name += "+lj" }
+        if self.torsion.is_some() { name += "+torsion" }
+        if self.coulomb.is_some() { name += "+coulomb" }
+        if self.tables.is_some() { name += "+tables" }
+        name
+    }
+
+    /// distance up to which moving an atom changes the energy of others; the torsion term
+    /// reaches as far as the bond order, 2*R2
+    pub fn range(&self) -> f64 {
+        let r2 = self.brenner.r2;
+        let range = self.lj.as_ref().map_or(2.*r2, |lj| lj.r_cut.max(2.*r2));
+        self.coulomb.as_ref().map_or(range, |c| c.r_cut.max(range))
+    }
+
+    /// checks that per-atom settings (the charges) fit a structure of n atoms
+    pub fn check_atoms(&self, n: usize) -> Result<(), String> {
+        match &self.coulomb {
+            Some(c) if !c.charges.is_empty() && c.charges.len() != n =>
+                Err(format!("{} charges for a structure of {} atoms", c.charges.len(), n)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Fuleren {
+    /// attaches a potential, an error if its per-atom settings do not fit (see Potential::check_atoms)
+    pub fn set_potential(&mut self, potential: Potential) -> Result<(), String> {
+        potential.check_atoms(self.size)?;
+        self.potential = potential;
+        Ok(())
+    }
+
+    // V_R, tabulated or analytic
+    pub(crate) fn _repulsive<T: Float>(&self, r: T) -> T {
+        match self.potential.tables.as_ref().and_then(|t| t.v_r.as_ref()) {
+            Some(table) => cst(table.eval(r.to_f64().unwrap())),
+            None => self.potential.brenner.v_r(r),
+        }
+    }
+
+    // V_A, tabulated or analytic
+    pub(crate) fn _attractive<T: Float>(&self, r: T) -> T {
+        match self.potential.tables.as_ref().and_then(|t| t.v_a.as_ref()) {
+            Some(table) => cst(table.eval(r.to_f64().unwrap())),
+            None => self.potential.brenner.v_a(r),
+        }
+    }
+
+    /// energy of the dispersion term alone (zero without it)
+    pub fn dispersion_energy(&self) -> f64 {
+        let Some(lj) = &self.potential.lj else { return 0. };
+        (0..self.size).flat_map(|i| (i + 1..self.size).map(move |j| (i, j)))
+                      .map(|(i, j)| lj.pair(self._r_ij(i, j), self.potential.brenner.r2))
+                      .sum()
+    }
+
+    /// energy of the electrostatic term alone (zero without it)
+    pub fn coulomb_energy(&self) -> f64 {
+        let Some(coulomb) = &self.potential.coulomb else { return 0. };
+        let q = |i: usize| coulomb.q(i, self.size);
+        (0..self.size).flat_map(|i| (i + 1..self.size).map(move |j| (i, j)))
+                      .map(|(i, j)| coulomb.pair(q(i)*q(j), self._r_ij(i, j)))
+                      .sum()
+    }
+
+    /// energy of the confining wall alone (zero without it)
+    pub fn wall_energy(&self) -> f64 {
+        let Some(wall) = &self.potential.wall else { return 0. };
+        0.5*self.positions.iter().map(|p| wall.v_i(p.r())).sum::<f64>()
+    }
+
+    /// energy of the radial shell restraint alone (zero without it)
+    pub fn shell_energy(&self) -> f64 {
+        let Some(shell) = &self.potential.shell else { return 0. };
+        0.5*self.positions.iter().map(|p| shell.v_i(p.r())).sum::<f64>()
+    }
+
+    /// moves a wall following mean_r to scale*mean_r, E updated by the change of the wall energy
+    pub fn place_wall(&mut self) {
+        let Some(wall) = self.potential.wall else { return };
+        let Some(scale) = wall.scale else { return };
+        let old = self.wall_energy();
+        self.potential.wall = Some(Wall { radius: scale*self.mean_r(), ..wall });
+        self.E += self.wall_energy() - old;
+    }
+
+    /// energy of the torsion term alone (zero without it)
+    pub fn torsion_energy(&self) -> f64 {
+        let Some(torsion) = &self.potential.torsion else { return 0. };
+        let (mut bonded_i, mut bonded_j) = (Vec::new(), Vec::new());
+        0.5*(0..self.size).map(|i| self._torsion_on::<f64>(i, torsion, &mut bonded_i, &mut bonded_j)).sum::<f64>()
+    }
+
+    // torsion energy of the dihedrals k-i-j-l around the bonds i-j of atom i; every bond is
+    // seen from both ends, which the 1/2 of E = 1/2 sum V_i compensates. The bonded neighbours
+    // of i and j are collected into the given buffers
+    pub(crate) fn _torsion_on<T: Float>(&self, i: usize, torsion: &Torsion, neighbours_i: &mut Bonded<T>,
+                                        neighbours_j: &mut Bonded<T>) -> T {
+        let brenner = &self.potential.brenner;
+        let bonded = |a: usize, out: &mut Bonded<T>| {
+            out.clear();
+            out.extend((0..self.size).filter(|&b| b != a)
+                                     .map(|b| (b, self._vec_ij::<T>(a, b)))
+                                     .filter_map(|(b, v)| {
+                                         let r = _mod_arr(&v);
+                                         // bond weight, the cutoff function
+                                         (r <= cst(brenner.r2)).then(|| (b, v, brenner.cutoff(r)))
+                                     }));
+        };
+
+        let mut v = T::zero();
+        bonded(i, neighbours_i);
+        for &(j, v_ij, w_ij) in neighbours_i.iter() {
+            bonded(j, neighbours_j);
+            let v_ji = v_ij.map(|x| -x);
+            for &(k, v_ik, w_ik) in neighbours_i.iter().filter(|(k, _, _)| *k != j) {
+                let n1 = _cross(&v_ik, &v_ij);
+                for &(_, v_jl, w_jl) in neighbours_j.iter().filter(|(l, _, _)| *l != i && *l != k) {
+                    let n2 = _cross(&v_ji, &v_jl);
+                    let norm = _mod_arr(&n1)*_mod_arr(&n2);
+                    // collinear bonds, the dihedral is undefined and the weight of such chains small
+                    if norm < cst(1e-12) { continue }
+                    let cos_w = (n1[0]*n2[0] + n1[1]*n2[1] + n1[2]*n2[2])/norm;
+                    v = v + w_ik*w_ij*w_jl*torsion.dihedral(cos_w);
+                }
+            }
+        }
+        v
+    }
+}
+
+// one charge [e] per non-empty line
+fn read_charges(path: &str) -> Result<Vec<f64>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    content.lines()
+           .enumerate()
+           .filter(|(_, line)| !line.trim().is_empty())
+           .map(|(n, line)| line.trim().parse::<f64>().map_err(|_| format!("{}:{}: cannot parse charge \"{}\"", path, n + 1, line)))
+           .collect()
+}
+
+/// bonded neighbours of an atom: index, bond vector and bond weight
+pub(crate) type Bonded<T> = Vec<(usize, [T; 3], T)>;
+
+fn _cross<T: Float>(a: &[T; 3], b: &[T; 3]) -> [T; 3] {
+    [a[1]*b[2] - a[2]*b[1], a[2]*b[0] - a[0]*b[2], a[0]*b[1] - a[1]*b[0]]
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::span;
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+// (calls, total time) per span name
+type Stats = Arc<Mutex<HashMap<&'static str, (u64, Duration)>>>;
+
+/// collects the time spent inside every instrumented function
+pub struct Profiler {
+    stats: Stats,
+    start: Instant,
+}
+
+impl Profiler {
+    /// profiler and the layer feeding it; the layer must see all spans, so it has no level filter
+    pub fn new() -> (Profiler, TimingLayer) {
+        let stats = Stats::default();
+        (Profiler { stats: stats.clone(), start: Instant::now() }, TimingLayer { stats })
+    }
+
+    /// prints calls, total and mean time per function; times of nested spans are inclusive
+    /// (energy_calc contains _vi, which contains _ksi_ij)
+    pub fn report(&self) {
+        let total = self.start.elapsed();
+        let stats = self.stats.lock().unwrap();
+        let mut rows: Vec<_> = stats.iter().collect();
+        rows.sort_by_key(|&(_, &(calls, _))| std::cmp::Reverse(calls));
+
+        println!("{:<24} {:>12} {:>12} {:>12} {:>8}", "function", "calls", "total [s]", "mean [us]", "%");
+        for (name, (calls, time)) in rows {
+            println!("{:<24} {:>12} {:>12.3} {:>12.3} {:>8.1}",
+                     name, calls, time.as_secs_f64(), time.as_secs_f64()*1e6/(*calls as f64),
+                     100.*time.as_secs_f64()/total.as_secs_f64());
+        }
+        println!("wall time: {:.3} s", total.as_secs_f64());
+    }
+}
+
+pub struct TimingLayer {
+    stats: Stats,
+}
+
+// moment the span was entered, kept in the span extensions
+struct Entered(Instant);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for TimingLayer {
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().replace(Entered(Instant::now()));
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(Entered(start)) = span.extensions_mut().remove::<Entered>() {
+                let mut stats = self.stats.lock().unwrap();
+                let entry = stats.entry(span.name()).or_insert((0, Duration::ZERO));
+                entry.0 += 1;
+                entry.1 += start.elapsed();
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::io;
+
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::anneal::{Schedule, ANNEAL_COLUMNS};
+use crate::metadata::Metadata;
+use crate::observables::ObservableWriter;
+use crate::{seed_rng, Fuleren};
+
+/// Python class wrapping Fuleren; positions and forces are (N, 3) NumPy arrays in A and eV/A
+#[pyclass(name = "Fullerene")]
+pub struct PyFuleren {
+    inner: Fuleren,
+}
+
+#[pymethods]
+impl PyFuleren {
+    /// n atoms at random on a sphere of radius r_start
+    #[new]
+    #[pyo3(signature = (n, r_start = 2.5))]
+    fn new(n: usize, r_start: f64) -> Self {
+        let mut inner = Fuleren::new(n);
+        inner.randomize_on_sphere(r_start);
+        inner.energy_calc();
+        PyFuleren { inner }
+    }
+
+    /// first structure of an "x y z" file
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<Self> {
+        let mut inner = Fuleren::frames_from_file(path).map_err(PyIOError::new_err)?
+                                                       .into_iter()
+                                                       .next()
+                                                       .ok_or_else(|| PyValueError::new_err("no structure found"))?;
+        inner.energy_calc();
+        Ok(PyFuleren { inner })
+    }
+
+    #[getter]
+    fn size(&self) -> usize {
+        self.inner.size
+    }
+
+    fn energy(&mut self) -> f64 {
+        self.inner.energy_calc()
+    }
+
+    /// energy split into repulsive, attractive and angular parts
+    fn energy_breakdown(&mut self) -> HashMap<&'static str, f64> {
+        let eb = self.inner.energy_calc_detailed();
+        HashMap::from([("total", eb.total), ("repulsive", eb.repulsive), ("attractive", eb.attractive),
+                       ("angular", eb.angular), ("cutoff", eb.cutoff), ("dispersion", eb.dispersion),
+                       ("torsion", eb.torsion), ("coulomb", eb.coulomb)])
+    }
+
+    fn positions<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        self.inner.xyz_array().into_pyarray(py)
+    }
+
+    fn set_positions(&mut self, xyz: PyReadonlyArray2<f64>) -> PyResult<()> {
+        let xyz = xyz.as_array();
+        if xyz.shape() != [self.inner.size, 3] {
+            return Err(PyValueError::new_err(format!("expected shape ({}, 3), got {:?}", self.inner.size, xyz.shape())));
+        }
+        self.inner.set_xyz_array(&xyz.to_owned());
+        self.inner.energy_calc();
+        Ok(())
+    }
+
+    fn forces<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f64>> {
+        self.inner.forces().into_pyarray(py)
+    }
+
+    /// power law annealing, see Schedule; log (optional path) gets a row of it, beta, T, E, r_mean
+    /// every log_step iterations. Returns the number of cooling iterations
+    #[pyo3(signature = (beta_min = 1., beta_max = 100., p = 2., it_max = 100_000, equilibration = 0,
+                        log = None, log_step = 100))]
+    #[allow(clippy::too_many_arguments)]
+    fn anneal(&mut self, py: Python<'_>, beta_min: f64, beta_max: f64, p: f64, it_max: usize, equilibration: usize,
+              log: Option<&str>, log_step: usize) -> PyResult<usize> {
+        let schedule = Schedule { beta_min, beta_max, p, it_max, equilibration, ..Default::default() };
+        let f = &mut self.inner;
+
+        // the GIL is released, so other Python threads can run their own structures meanwhile
+        let it = py.detach(|| match log {
+            Some(path) => f.anneal(&schedule, &mut ObservableWriter::create(path, &ANNEAL_COLUMNS)?, log_step),
+            None => f.anneal(&schedule, &mut ObservableWriter::new(io::sink(), &ANNEAL_COLUMNS)?, log_step),
+        }).map_err(|e| PyIOError::new_err(e.to_string()))?;
+        f.energy_calc();
+        Ok(it)
+    }
+
+    /// FIRE relaxation, returns the number of steps
+    #[pyo3(signature = (f_tol = 1e-3, max_steps = 10_000))]
+    fn relax(&mut self, py: Python<'_>, f_tol: f64, max_steps: usize) -> usize {
+        let f = &mut self.inner;
+        let steps = py.detach(|| f.relax(f_tol, max_steps));
+        f.energy_calc();
+        steps
+    }
+
+    /// saves the positions as "x y z" lines after a metadata header
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.inner.save_pos_xyz(path, &Metadata::of(&self.inner)).map_err(|e| PyIOError::new_err(e.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Fullerene(size={}, E={:.5})", self.inner.size, self.inner.E)
+    }
+}
+
+/// seeds the random generator of the calling thread
+#[pyfunction]
+fn seed(s: u64) {
+    seed_rng(s);
+}
+
+#[pymodule]
+fn fuleren(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFuleren>()?;
+    m.add_function(wrap_pyfunction!(seed, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::io::Write;
+
+use tracing::info;
+
+use crate::anneal::Schedule;
+use crate::runner::{Job, ParallelRunner};
+use crate::utilities::write_atomic;
+use crate::Fuleren;
+
+/// quench rate study: the same starting structure annealed with every combination of it_max and
+/// p on top of a base schedule, all seeds run in parallel; mean and best final E/N and defect
+/// counts per cooling rate written to quench.dat
+pub struct QuenchStudy {
+    pub start: Fuleren,
+    pub schedule: Schedule,
+    pub it_max: Vec<usize>,
+    pub p: Vec<f64>,
+    pub seeds: Vec<u64>,
+}
+
+/// final energies and defect counts of the runs of one cooling rate
+#[derive(Debug, Clone)]
+pub struct QuenchRate {
+    pub it_max: usize,
+    pub p: f64,
+    pub e_per_atom: Vec<f64>,
+    pub defects: Vec<usize>,
+}
+
+impl QuenchRate {
+    /// mean cooling rate of the schedule [K/iteration]
+    pub fn rate(&self, schedule: &Schedule) -> f64 {
+        let schedule = Schedule { it_max: self.it_max, p: self.p, ..schedule.clone() };
+        (schedule.temperature(0).0 - schedule.temperature(self.it_max).0)/self.it_max as f64
+    }
+
+    pub fn mean_e_per_atom(&self) -> f64 {
+        self.e_per_atom.iter().sum::<f64>()/self.e_per_atom.len() as f64
+    }
+
+    pub fn min_e_per_atom(&self) -> f64 {
+        self.e_per_atom.iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn mean_defects(&self) -> f64 {
+        self.defects.iter().sum::<usize>() as f64/self.defects.len() as f64
+    }
+}
+
+impl QuenchStudy {
+    /// queues the anneals of every cooling rate on the runner, runs them (the runner's summary.dat
+    /// lists the single jobs) and writes quench.dat to its out_dir, fastest quench first
+    pub fn run(&self, runner: &mut ParallelRunner) -> Result<Vec<QuenchRate>, String> {
+        if self.it_max.is_empty() || self.p.is_empty() {
+            return Err("a quench study needs at least one it_max and one p".to_string());
+        }
+        if self.seeds.is_empty() {
+            return Err("a quench study needs at least one run per cooling rate".to_string());
+        }
+        if self.schedule.table.is_some() {
+            return Err("a quench study varies the power law schedule, not a tabulated one".to_string());
+        }
+
+        let mut it_max = self.it_max.clone();
+        it_max.sort_unstable();
+        let mut rates = Vec::new();
+        let offset = runner.jobs.len();
+        for &it_max in &it_max {
+            for &p in &self.p {
+                let schedule = Schedule { it_max, p, ..self.schedule.clone() };
+                for &seed in &self.seeds {
+                    runner.jobs.push(Job { n: self.start.size, seed, schedule: schedule.clone(),
+                                           potential: self.start.potential.clone(), start: Some(self.start.clone()) });
+                }
+                rates.push(QuenchRate { it_max, p, e_per_atom: Vec::new(), defects: Vec::new() });
+            }
+        }
+        info!(rates = rates.len(), jobs = runner.jobs.len() - offset, "quench study started");
+
+        for r in runner.run().map_err(|e| e.to_string())? {
+            if r.id < offset { continue }
+            let rate = &mut rates[(r.id - offset)/self.seeds.len()];
+            rate.e_per_atom.push(r.e/r.n as f64);
+            rate.defects.push(r.defects);
+        }
+
+        self.save(&rates, &format!("{}/quench.dat", runner.out_dir)).map_err(|e| e.to_string())?;
+        Ok(rates)
+    }
+
+    fn save(&self, rates: &[QuenchRate], path: &str) -> std::io::Result<()> {
+        write_atomic(path, |f| {
+            writeln!(f, "# {:<10} {:<6} {:<12} {:<12} {:<12} {:<10} {:<6}",
+                     "it_max", "p", "dT/it[K]", "E/N_mean", "E/N_min", "defects", "runs")?;
+            // rates without a single finished run are left out
+            for q in rates.iter().filter(|q| !q.e_per_atom.is_empty()) {
+                writeln!(f, "  {:<10} {:<6} {:<12.5e} {:<12.5} {:<12.5} {:<10.2} {:<6}", q.it_max, q.p,
+                         q.rate(&self.schedule), q.mean_e_per_atom(), q.min_e_per_atom(), q.mean_defects(),
+                         q.e_per_atom.len())?;
+            }
+            Ok(())
+        })
+    }
+}
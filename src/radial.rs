@@ -0,0 +1,81 @@
+//! radial density profile: histogram of the atom distances from the center of mass accumulated
+//! over sampled structures, giving the shell thickness and the atoms that left the shell,
+//! complementary to the PCF
+use std::f64::consts::PI;
+use std::io::Write;
+
+use crate::utilities::write_atomic;
+use crate::{Fuleren, VectorFloat};
+
+/// atoms further than this from the mean radius of their structure [A] have left the shell
+pub const SHELL_TOLERANCE: f64 = 1.0;
+
+/// counts of atom distances in bins of width r_max/bins, over `samples` structures
+#[derive(Debug, Clone)]
+pub struct RadialProfile {
+    pub r_max: f64,
+    pub counts: VectorFloat,
+    pub samples: usize,
+    pub atoms: usize,    // all atoms counted, including those beyond r_max
+    pub detached: usize, // atoms off the shell by more than SHELL_TOLERANCE
+    sum_dev2: f64,       // squared deviations from the mean radius of their structure
+}
+
+impl RadialProfile {
+    pub fn new(r_max: f64, bins: usize) -> RadialProfile {
+        RadialProfile { r_max, counts: VectorFloat::zeros(bins), samples: 0, atoms: 0, detached: 0, sum_dev2: 0. }
+    }
+
+    /// adds the atom distances of f from its center of mass
+    pub fn add(&mut self, f: &Fuleren) {
+        let c = f.center_of_mass();
+        let r: Vec<f64> = f.iter_atoms()
+                           .map(|p| ((p[0] - c[0]).powi(2) + (p[1] - c[1]).powi(2) + (p[2] - c[2]).powi(2)).sqrt())
+                           .collect();
+        let mean = r.iter().sum::<f64>()/r.len() as f64;
+        let dr = self.r_max/self.counts.len() as f64;
+        for &ri in &r {
+            if let Some(count) = self.counts.get_mut((ri/dr) as usize) {
+                *count += 1.;
+            }
+            self.sum_dev2 += (ri - mean).powi(2);
+            if (ri - mean).abs() > SHELL_TOLERANCE {
+                self.detached += 1;
+            }
+        }
+        self.atoms += r.len();
+        self.samples += 1;
+    }
+
+    /// bin centers [A]
+    pub fn r(&self) -> VectorFloat {
+        let dr = self.r_max/self.counts.len() as f64;
+        VectorFloat::from_iter((0..self.counts.len()).map(|m| (m as f64 + 0.5)*dr))
+    }
+
+    /// number density rho(r) [1/A^3] averaged over the samples
+    pub fn density(&self) -> VectorFloat {
+        let dr = self.r_max/self.counts.len() as f64;
+        let shells = self.r().mapv(|r| 4.*PI*r*r*dr);
+        &self.counts/&shells/self.samples.max(1) as f64
+    }
+
+    /// rms deviation of the atom distances from the mean radius of their structure [A]
+    pub fn thickness(&self) -> f64 {
+        (self.sum_dev2/self.atoms.max(1) as f64).sqrt()
+    }
+
+    /// "r rho(r) P(r)" columns, P the fraction of the atoms per bin, after a header with the
+    /// shell thickness and the detached atoms
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        write_atomic(path, |f| {
+            writeln!(f, "# samples={} thickness={:.5} detached={}/{} (off the shell by more than {} A)",
+                     self.samples, self.thickness(), self.detached, self.atoms, SHELL_TOLERANCE)?;
+            writeln!(f, "# {:<10} {:<14} {:<12}", "r[A]", "rho[1/A^3]", "P(r)")?;
+            for ((r, rho), count) in self.r().iter().zip(self.density().iter()).zip(self.counts.iter()) {
+                writeln!(f, "  {:<10.4} {:<14.6e} {:<12.6}", r, rho, count/self.atoms.max(1) as f64)?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,24 @@
+use crate::Fuleren;
+
+/// known fullerene isomers, relaxed with the Brenner potential of this crate and embedded in the
+/// binary as "x y z" lines: (name, point group, positions). Centered, principal axes along x, y, z
+pub const REFERENCES: [(&str, &str, &str); 5] = [
+    ("C20", "Ih", include_str!("../data/reference/C20-Ih.xyz")),
+    ("C24", "D6d", include_str!("../data/reference/C24-D6d.xyz")),
+    ("C36", "D6h", include_str!("../data/reference/C36-D6h.xyz")),
+    ("C60", "Ih", include_str!("../data/reference/C60-Ih.xyz")),
+    ("C70", "D5h", include_str!("../data/reference/C70-D5h.xyz")),
+];
+
+impl Fuleren {
+    /// reference structure by name, "C60" or with the point group "C60-Ih" (case insensitive);
+    /// None for an unknown name
+    pub fn reference(name: &str) -> Option<Fuleren> {
+        let (_, _, xyz) = REFERENCES.iter().find(|(n, group, _)| {
+            name.eq_ignore_ascii_case(n) || name.eq_ignore_ascii_case(&format!("{}-{}", n, group))
+        })?;
+        let mut f = Fuleren::frames_from_str(xyz, name).ok()?.pop()?;
+        f.energy_calc();
+        Some(f)
+    }
+}
@@ -0,0 +1,74 @@
+use ndarray::prelude::*;
+
+use crate::{Fuleren, Point6, M_C};
+
+impl Fuleren {
+    /// local minimization with FIRE; stops when the largest force is below f_tol [eV/A]
+    /// or after max_steps. Returns number of steps done, E is updated
+    pub fn relax(&mut self, f_tol: f64, max_steps: usize) -> usize {
+        // standard FIRE parameters, time in md units
+        let dt_max = 0.5;
+        let n_min = 5;
+        let f_inc = 1.1;
+        let f_dec = 0.5;
+        let alpha_start = 0.1;
+        let f_alpha = 0.99;
+        let max_shift = 0.1; // [A] per step, keeps the first steps from blowing up
+
+        let mut dt = 0.1;
+        let mut alpha = alpha_start;
+        let mut n_pos = 0;
+        let mut v = Array2::<f64>::zeros((self.size, 3));
+        let mut f = self.forces();
+
+        let mut step = 0;
+        while step < max_steps {
+            if max_force(&f) < f_tol { break }
+
+            let power = (&f*&v).sum();
+            if power > 0. {
+                let v_norm = v.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+                let f_norm = f.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+                v = (1. - alpha)*&v + alpha*v_norm/f_norm*&f;
+
+                if n_pos > n_min {
+                    dt = f64::min(dt*f_inc, dt_max);
+                    alpha *= f_alpha;
+                }
+                n_pos += 1;
+            }
+            else {
+                v.fill(0.);
+                dt *= f_dec;
+                alpha = alpha_start;
+                n_pos = 0;
+            }
+
+            // semi-implicit Euler step, limited displacement
+            v += &(dt/M_C*&f);
+            for i in 0..self.size {
+                let mut shift = [dt*v[[i, 0]], dt*v[[i, 1]], dt*v[[i, 2]]];
+                let len = (shift[0].powi(2) + shift[1].powi(2) + shift[2].powi(2)).sqrt();
+                if len > max_shift {
+                    shift.iter_mut().for_each(|s| *s *= max_shift/len);
+                }
+                let xyz = [self.positions[i].x() + shift[0],
+                           self.positions[i].y() + shift[1],
+                           self.positions[i].z() + shift[2]];
+                self.positions[i] = Point6::from_cartesian(&xyz);
+            }
+            f = self.forces();
+            step += 1;
+        }
+
+        self.energy_calc();
+        step
+    }
+}
+
+/// largest force on any atom
+pub fn max_force(f: &Array2<f64>) -> f64 {
+    f.outer_iter()
+     .map(|fi| (fi[0].powi(2) + fi[1].powi(2) + fi[2].powi(2)).sqrt())
+     .fold(0., f64::max)
+}
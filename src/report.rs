@@ -0,0 +1,120 @@
+//! comparison of finished runs: final energies of every run directory side by side, the best
+//! structure of each, and the E(it) curves of all anneal logs overlaid in one gnuplot figure
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::potential::Potential;
+use crate::utilities::write_atomic;
+use crate::Fuleren;
+
+/// final structures of one run directory with their energies
+struct RunSummary {
+    name: String,
+    energies: Vec<(String, usize, f64)>, // (file, N, E)
+    logs: Vec<(String, usize)>,          // (file, column of E)
+}
+
+impl RunSummary {
+    fn read(dir: &Path, potential: &Potential) -> Result<RunSummary, String> {
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("cannot read {}: {}", dir.display(), e))?;
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+        paths.sort();
+
+        let mut energies = Vec::new();
+        let mut logs = Vec::new();
+        for path in paths {
+            let file = path.to_string_lossy().into_owned();
+            let stem = path.file_stem().map_or(String::new(), |s| s.to_string_lossy().into_owned());
+            match path.extension().and_then(|e| e.to_str()) {
+                // final structures; recorded trajectories and random starting points are no results
+                Some("xyz" | "extxyz") if stem != "trajectory" && !stem.starts_with("random") => {
+                    let Some(mut f) = Fuleren::frames_from_file(&file)?.pop() else { continue };
+                    f.set_potential(potential.clone())?;
+                    f.energy_calc();
+                    energies.push((file, f.size, f.E));
+                }
+                Some("log") => {
+                    if let Some(column) = energy_column(&file) {
+                        logs.push((file, column));
+                    }
+                }
+                _ => {}
+            }
+        }
+        let name = dir.file_name().map_or(dir.to_string_lossy().into_owned(), |n| n.to_string_lossy().into_owned());
+        Ok(RunSummary { name, energies, logs })
+    }
+}
+
+// gnuplot column (1-based) of E in an observable log "# it beta T E ..."
+fn energy_column(path: &str) -> Option<usize> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let header = content.lines().next()?.strip_prefix('#')?;
+    header.split_whitespace().position(|c| c == "E").map(|k| k + 1)
+}
+
+/// compares the run directories: writes report.csv (run, N, structures, best and mean E/N and the
+/// best structure of every N in every run), report.gp, a gnuplot script drawing the E(it) curves
+/// of all logs (report_energy.png) and the best E/N per run (report_en.png), and prints the table
+pub fn report(run_dirs: &[PathBuf], out_dir: &str, potential: &Potential) -> Result<(), String> {
+    let runs = run_dirs.iter().map(|d| RunSummary::read(d, potential)).collect::<Result<Vec<_>, _>>()?;
+
+    // (run, N, structures, best E/N, mean E/N, best file)
+    let mut rows = Vec::new();
+    for run in runs.iter() {
+        let mut sizes: Vec<usize> = run.energies.iter().map(|e| e.1).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        for n in sizes {
+            let of_n: Vec<&(String, usize, f64)> = run.energies.iter().filter(|e| e.1 == n).collect();
+            let best = of_n.iter().min_by(|a, b| a.2.total_cmp(&b.2)).unwrap();
+            let mean = of_n.iter().map(|e| e.2).sum::<f64>()/of_n.len() as f64;
+            rows.push((run.name.clone(), n, of_n.len(), best.2/n as f64, mean/n as f64, best.0.clone()));
+        }
+    }
+
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("cannot create {}: {}", out_dir, e))?;
+    let err = |e: std::io::Error| e.to_string();
+    write_atomic(&format!("{}/report.csv", out_dir), |f| {
+        writeln!(f, "run,N,structures,best_E/N,mean_E/N,best_structure")?;
+        for (run, n, count, best, mean, file) in rows.iter() {
+            writeln!(f, "{},{},{},{:.6},{:.6},{}", run, n, count, best, mean, file)?;
+        }
+        Ok(())
+    }).map_err(err)?;
+
+    write_atomic(&format!("{}/report.gp", out_dir), |f| {
+        writeln!(f, "# gnuplot report.gp, run in this directory")?;
+        writeln!(f, "set terminal pngcairo size 1000,700 noenhanced\nset datafile separator whitespace")?;
+        writeln!(f, "set output 'report_energy.png'\nset xlabel 'iteration'\nset ylabel 'E [eV]'\nset key outside")?;
+        let curves: Vec<String> = runs.iter()
+                                      .flat_map(|run| run.logs.iter().map(move |(file, column)| {
+                                          let label = Path::new(file).file_stem().map_or(String::new(), |s| s.to_string_lossy().into_owned());
+                                          format!("'{}' using 1:{} with lines title '{}/{}'", absolute(file), column, run.name, label)
+                                      }))
+                                      .collect();
+        if !curves.is_empty() {
+            writeln!(f, "plot {}", curves.join(", \\\n     "))?;
+        }
+        writeln!(f, "\nset output 'report_en.png'\nset datafile separator ','\nset xlabel 'N'\nset ylabel 'best E/N [eV]'")?;
+        let per_run: Vec<String> = runs.iter()
+                                       .map(|run| format!("'report.csv' using (strcol(1) eq '{}' ? $2 : 1/0):4 with linespoints title '{}'",
+                                                          run.name, run.name))
+                                       .collect();
+        if !per_run.is_empty() {
+            writeln!(f, "plot {}", per_run.join(", \\\n     "))?;
+        }
+        Ok(())
+    }).map_err(err)?;
+
+    println!("{:<32} {:<6} {:<6} {:<12} {:<12} best structure", "run", "N", "runs", "best E/N", "mean E/N");
+    for (run, n, count, best, mean, file) in rows.iter() {
+        println!("{:<32} {:<6} {:<6} {:<12.5} {:<12.5} {}", run, n, count, best, mean, file);
+    }
+    Ok(())
+}
+
+// logs are referenced from the report directory, relative paths would break
+fn absolute(path: &str) -> String {
+    Path::new(path).canonicalize().map_or(path.to_string(), |p| p.to_string_lossy().into_owned())
+}
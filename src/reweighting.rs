@@ -0,0 +1,143 @@
+//! energy histograms of the temperature windows of an anneal and Ferrenberg-Swendsen multiple
+//! histogram reweighting, which joins all windows into continuous <E>(T) and C_v(T) curves.
+//! The samples are the rows of an anneal log (beta and E columns), so the log should be written
+//! with a small log_step; every window is treated as sampled at its mean beta, which holds for
+//! windows short against the cooling
+use std::io::{self, Write};
+
+use crate::units::Beta;
+use crate::utilities::write_atomic;
+
+/// energy samples of one temperature window
+#[derive(Debug, Clone)]
+pub struct Window {
+    pub beta: f64,
+    pub energies: Vec<f64>,
+}
+
+/// splits the rows of an observable log with beta and E columns into n_windows windows of
+/// consecutive rows
+pub fn windows_from_log(path: &str, n_windows: usize) -> Result<Vec<Window>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let header: Vec<&str> = content.lines()
+                                   .next()
+                                   .and_then(|l| l.strip_prefix('#'))
+                                   .ok_or(format!("{}: no header line", path))?
+                                   .split_whitespace()
+                                   .collect();
+    let column = |name: &str| header.iter().position(|c| *c == name).ok_or(format!("{}: no {} column", path, name));
+    let (c_beta, c_e) = (column("beta")?, column("E")?);
+
+    let mut rows = Vec::new();
+    for (n, line) in content.lines().enumerate().skip(1) {
+        if line.trim().is_empty() { continue }
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        let value = |c: usize| cols.get(c).and_then(|x| x.parse::<f64>().ok())
+                                   .ok_or(format!("{}:{}: cannot parse \"{}\"", path, n + 1, line));
+        rows.push((value(c_beta)?, value(c_e)?));
+    }
+    if rows.len() < n_windows.max(1) {
+        return Err(format!("{}: {} rows are too few for {} windows", path, rows.len(), n_windows));
+    }
+
+    let size = rows.len().div_ceil(n_windows.max(1));
+    Ok(rows.chunks(size)
+           .map(|chunk| Window { beta: chunk.iter().map(|r| r.0).sum::<f64>()/chunk.len() as f64,
+                                 energies: chunk.iter().map(|r| r.1).collect() })
+           .collect())
+}
+
+/// histograms of the windows on `bins` common bins: rows "E count_1 count_2 ..."
+pub fn save_histograms(windows: &[Window], bins: usize, path: &str) -> io::Result<()> {
+    let all = windows.iter().flat_map(|w| w.energies.iter().copied());
+    let lo = all.clone().fold(f64::INFINITY, f64::min);
+    let hi = all.fold(f64::NEG_INFINITY, f64::max);
+    let width = ((hi - lo)/bins as f64).max(f64::MIN_POSITIVE);
+
+    let mut counts = vec![vec![0; windows.len()]; bins];
+    for (k, w) in windows.iter().enumerate() {
+        for e in w.energies.iter() {
+            counts[(((e - lo)/width) as usize).min(bins - 1)][k] += 1;
+        }
+    }
+
+    write_atomic(path, |f| {
+        write!(f, "# {:<14}", "E")?;
+        for w in windows {
+            write!(f, " T={:<10.1}", Beta(w.beta).temperature().0)?;
+        }
+        writeln!(f)?;
+        for (b, row) in counts.iter().enumerate() {
+            write!(f, "  {:<14.6}", lo + (b as f64 + 0.5)*width)?;
+            for c in row {
+                write!(f, " {:<12}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    })
+}
+
+/// Ferrenberg-Swendsen reweighting of all samples: dimensionless free energies f_k of the windows
+/// iterated until they change less than tol, then <E> [eV] and C_v [k_B] = beta^2 (<E^2> - <E>^2)
+/// at every requested beta. Returns (beta, <E>, C_v)
+pub fn multihistogram(windows: &[Window], betas: &[f64], tol: f64, max_iter: usize) -> Vec<(f64, f64, f64)> {
+    let samples: Vec<f64> = windows.iter().flat_map(|w| w.energies.iter().copied()).collect();
+    let ln_n: Vec<f64> = windows.iter().map(|w| (w.energies.len() as f64).ln()).collect();
+
+    // ln sum_j N_j exp(f_j - beta_j E) of every sample, the denominator of all weights
+    let ln_denominators = |f: &[f64]| -> Vec<f64> {
+        samples.iter()
+               .map(|&e| log_sum_exp(windows.iter().enumerate().map(|(j, w)| ln_n[j] + f[j] - w.beta*e)))
+               .collect()
+    };
+
+    let mut f = vec![0.; windows.len()];
+    for it in 0..max_iter {
+        let ln_d = ln_denominators(&f);
+        let mut f_new: Vec<f64> = windows.iter()
+                                         .map(|w| -log_sum_exp(samples.iter().zip(ln_d.iter()).map(|(&e, d)| -w.beta*e - d)))
+                                         .collect();
+        // fixed by f_0 = 0, only differences matter
+        let shift = f_new[0];
+        f_new.iter_mut().for_each(|x| *x -= shift);
+        let change = f_new.iter().zip(f.iter()).map(|(a, b)| (a - b).abs()).fold(0., f64::max);
+        f = f_new;
+        if change < tol {
+            tracing::debug!(it, "multiple histogram iteration converged");
+            break
+        }
+    }
+
+    let ln_d = ln_denominators(&f);
+    betas.iter().map(|&beta| {
+        let ln_w: Vec<f64> = samples.iter().zip(ln_d.iter()).map(|(&e, d)| -beta*e - d).collect();
+        let ln_z = log_sum_exp(ln_w.iter().copied());
+        let (mut e1, mut e2) = (0., 0.);
+        for (e, lw) in samples.iter().zip(ln_w.iter()) {
+            let w = (lw - ln_z).exp();
+            e1 += w*e;
+            e2 += w*e*e;
+        }
+        (beta, e1, beta*beta*(e2 - e1*e1))
+    }).collect()
+}
+
+/// rows "T beta <E> C_v" of multihistogram
+pub fn save_thermodynamics(curve: &[(f64, f64, f64)], path: &str) -> io::Result<()> {
+    write_atomic(path, |f| {
+        writeln!(f, "# {:<12} {:<12} {:<14} {:<12}", "T[K]", "beta[1/eV]", "<E>[eV]", "C_v[k_B]")?;
+        for &(beta, e, cv) in curve {
+            writeln!(f, "  {:<12.3} {:<12.6} {:<14.6} {:<12.6}", Beta(beta).temperature().0, beta, e, cv)?;
+        }
+        Ok(())
+    })
+}
+
+fn log_sum_exp<I: Iterator<Item = f64> + Clone>(xs: I) -> f64 {
+    let max = xs.clone().fold(f64::NEG_INFINITY, f64::max);
+    if !max.is_finite() {
+        return max
+    }
+    max + xs.map(|x| (x - max).exp()).sum::<f64>().ln()
+}
@@ -0,0 +1,85 @@
+//! strain energy per ring: the energy of every atom (V_i/2) shared equally between the rings
+//! through it, so the ring energies add up to the energy of the atoms in rings. Pentagons are
+//! strained against the hexagons of the same structure; their total excess energy is the cost of
+//! the curvature, and cages where the pentagons cannot spread out anneal poorly
+use std::io::Write;
+
+use crate::utilities::write_atomic;
+use crate::Fuleren;
+
+/// largest ring looked for
+pub const MAX_RING: usize = 8;
+
+/// a ring with its share of the energy [eV]
+#[derive(Debug, Clone)]
+pub struct Ring {
+    pub atoms: Vec<usize>,
+    pub energy: f64,
+}
+
+impl Ring {
+    pub fn size(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn energy_per_atom(&self) -> f64 {
+        self.energy/self.atoms.len() as f64
+    }
+}
+
+/// mean energies per ring atom [eV] of the pentagons and hexagons (NaN without any) and the
+/// pentagon strain, sum over pentagons of E - 5 e_hexagon [eV] (0 without pentagons, NaN without
+/// hexagons)
+#[derive(Debug, Clone, Copy)]
+pub struct RingStrain {
+    pub pentagons: usize,
+    pub hexagons: usize,
+    pub e_pentagon: f64,
+    pub e_hexagon: f64,
+    pub pentagon_strain: f64,
+}
+
+impl Fuleren {
+    /// rings up to MAX_RING atoms with their energies; atoms in no ring are left out
+    pub fn ring_energies(&self) -> Vec<Ring> {
+        let rings = self.rings(MAX_RING);
+        let mut shares = vec![0usize; self.size];
+        for &i in rings.iter().flatten() {
+            shares[i] += 1;
+        }
+        let e_atom: Vec<f64> = (0..self.size).map(|i| 0.5*self._vi(i)).collect();
+
+        rings.into_iter()
+             .map(|atoms| {
+                 let energy = atoms.iter().map(|&i| e_atom[i]/shares[i] as f64).sum();
+                 Ring { atoms, energy }
+             })
+             .collect()
+    }
+}
+
+pub fn ring_strain(rings: &[Ring]) -> RingStrain {
+    let mean = |size: usize| {
+        let (sum, count) = rings.iter().filter(|r| r.size() == size)
+                                .fold((0., 0), |(sum, count), r| (sum + r.energy_per_atom(), count + 1));
+        (sum/count as f64, count)
+    };
+    let (e_pentagon, pentagons) = mean(5);
+    let (e_hexagon, hexagons) = mean(6);
+    RingStrain { pentagons, hexagons, e_pentagon, e_hexagon,
+                 pentagon_strain: if pentagons > 0 { pentagons as f64*5.*(e_pentagon - e_hexagon) } else { 0. } }
+}
+
+/// per ring table: size, energy, energy per atom, strain against the mean hexagon and the atoms
+pub fn save_rings(rings: &[Ring], path: &str) -> std::io::Result<()> {
+    let e_hexagon = ring_strain(rings).e_hexagon;
+    write_atomic(path, |f| {
+        writeln!(f, "# {:<6} {:<6} {:<12} {:<12} {:<12} atoms", "ring", "size", "E[eV]", "E/atom", "strain[eV]")?;
+        for (n, r) in rings.iter().enumerate() {
+            let atoms = r.atoms.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
+            writeln!(f, "  {:<6} {:<6} {:<12.5} {:<12.5} {:<12.5} {}", n, r.size(), r.energy, r.energy_per_atom(),
+                     r.energy - r.size() as f64*e_hexagon, atoms)?;
+        }
+        Ok(())
+    })
+}
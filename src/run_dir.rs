@@ -0,0 +1,90 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utilities::write_atomic;
+
+/// output directory of a single run: base/<name> or base/<prefix>_<UTC timestamp>, so runs never
+/// overwrite each other; holds the resolved config and, once finished, a manifest of all files
+pub struct RunDirectory {
+    pub path: PathBuf,
+}
+
+impl RunDirectory {
+    /// creates base/<prefix>_<timestamp>, with a _<k> suffix if that already exists
+    pub fn create(base: &Path, prefix: &str) -> io::Result<RunDirectory> {
+        std::fs::create_dir_all(base)?;
+        let stamp = format!("{}_{}", prefix, timestamp());
+
+        for k in 0.. {
+            let name = if k == 0 { stamp.clone() } else { format!("{}_{}", stamp, k) };
+            match std::fs::create_dir(base.join(&name)) {
+                Ok(()) => return Ok(RunDirectory { path: base.join(name) }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+
+    /// base/<name>, reused if it exists; for runs split over several processes
+    pub fn named(base: &Path, name: &str) -> io::Result<RunDirectory> {
+        std::fs::create_dir_all(base.join(name))?;
+        Ok(RunDirectory { path: base.join(name) })
+    }
+
+    /// path of a file inside the directory
+    pub fn file(&self, name: &str) -> String {
+        self.path.join(name).to_string_lossy().into_owned()
+    }
+
+    /// the directory itself, as the out_dir string the drivers take
+    pub fn dir(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    /// saves the resolved config as config.txt
+    pub fn save_config(&self, config: &str) -> io::Result<()> {
+        write_atomic(&self.file("config.txt"), |f| f.write_all(config.as_bytes()))
+    }
+
+    /// writes manifest.txt: every file produced in the directory with its size in bytes
+    pub fn finish(&self) -> io::Result<()> {
+        let mut files: Vec<(String, u64)> = Vec::new();
+        for entry in std::fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type()?.is_file() && name != "manifest.txt" {
+                files.push((name, entry.metadata()?.len()));
+            }
+        }
+        files.sort();
+
+        write_atomic(&self.file("manifest.txt"), |f| {
+            writeln!(f, "# {} files, written {}", files.len(), timestamp())?;
+            for (name, size) in files {
+                writeln!(f, "{:<40} {:>12}", name, size)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+// current UTC time as YYYYMMDD-HHMMSS
+fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs/86400, secs%86400);
+
+    // civil date from days since 1970-01-01 (H. Hinnant's algorithm)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era*146097;
+    let yoe = (doe - doe/1460 + doe/36524 - doe/146096)/365;
+    let doy = doe - (365*yoe + yoe/4 - yoe/100);
+    let mp = (5*doy + 2)/153;
+    let day = doy - (153*mp + 2)/5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era*400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, rem/3600, rem%3600/60, rem%60)
+}
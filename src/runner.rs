@@ -0,0 +1,464 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{error, info};
+
+use crate::anneal::{anneal_columns, Cadence, Schedule};
+use crate::archive::Archive;
+use crate::cv::CollectiveVariable;
+use crate::isomer::Isomer;
+use crate::metadata::Metadata;
+use crate::potential::Potential;
+use crate::observables::ObservableWriter;
+use crate::utilities::write_atomic;
+use crate::vibrations::rank_by_free_energy;
+use crate::{Fuleren, seed_rng};
+
+/// single independent annealing job
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub n: usize,
+    pub seed: u64,
+    pub schedule: Schedule,
+    pub potential: Potential,
+    pub start: Option<Fuleren>, // starting structure, random on a sphere of r_start if None
+}
+
+#[derive(Debug, Clone)]
+pub struct JobResult {
+    pub id: usize,
+    pub n: usize,
+    pub seed: u64,
+    pub schedule: Schedule,
+    pub e: f64,
+    pub r_mean: f64,
+    pub defects: usize, // atoms without exactly 3 bonds
+    pub isomer: Option<Isomer>, // None unless the final structure is a closed cage
+    pub seconds: f64,
+}
+
+/// runs independent jobs on a fixed number of worker threads; every worker holds only
+/// the structure it is annealing, final structures and logs go straight to out_dir
+pub struct ParallelRunner {
+    pub jobs: Vec<Job>,
+    pub threads: usize,
+    pub out_dir: String,
+    pub cadence: Cadence, // of the anneal logs
+    pub r_start: f64, // radius of the random starting sphere
+    pub align: bool,  // recenter and align principal axes before saving final structures
+    pub extxyz: bool, // final structures as extended XYZ instead of plain "x y z"
+    pub potential: Potential, // of the jobs added by add_grid
+    pub observables: Vec<Box<dyn CollectiveVariable>>, // logged after the ANNEAL_COLUMNS
+    pub archive: Option<Archive>, // offered every final structure
+    #[cfg(feature = "rusqlite")]
+    pub store: Option<crate::database::ResultStore>, // every finished job inserted
+}
+
+impl ParallelRunner {
+    pub fn new(out_dir: &str) -> ParallelRunner {
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        ParallelRunner { jobs: Vec::new(), threads, out_dir: out_dir.to_string(), cadence: Cadence::every(100), r_start: 2.5,
+                         align: true, extxyz: false, potential: Potential::default(), observables: Vec::new(),
+                         archive: None,
+                         #[cfg(feature = "rusqlite")]
+                         store: None }
+    }
+
+    /// adds a job for every (N, seed) pair with the same schedule
+    pub fn add_grid<I: IntoIterator<Item = usize>>(&mut self, sizes: I, seeds: &[u64], schedule: &Schedule) {
+        for n in sizes {
+            for &seed in seeds {
+                self.jobs.push(Job { n, seed, schedule: schedule.clone(), potential: self.potential.clone(), start: None });
+            }
+        }
+    }
+
+    /// runs all jobs, writes out_dir/summary.dat and returns the results ordered by job id;
+    /// failed jobs are logged and left out
+    pub fn run(&self) -> io::Result<Vec<JobResult>> {
+        let results = self._run_jobs(|_| true)?;
+        self.save_summary(&results, "summary.dat", None)?;
+        Ok(results)
+    }
+
+    /// multi-process mode: this process runs the jobs with id % size == rank and writes
+    /// summary_rank<rank>.dat, tagged with the run (see run_tag); rank 0 then waits (up to timeout)
+    /// for the partial summaries of all ranks with the same tag and gathers them into summary.dat,
+    /// so partials left by an earlier run under the same name are never merged. Needs out_dir on a
+    /// filesystem shared by all ranks
+    pub fn run_distributed(&self, group: &ProcessGroup, timeout: Duration) -> io::Result<Vec<JobResult>> {
+        let tag = self.run_tag(group);
+        let partial = format!("summary_rank{}.dat", group.rank);
+        match std::fs::remove_file(format!("{}/{}", self.out_dir, partial)) {
+            Ok(()) => info!(rank = group.rank, "partial summary of an earlier run removed"),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let results = self._run_jobs(|id| id % group.size == group.rank)?;
+
+        // written atomically, so an existing partial summary is always complete
+        self.save_summary(&results, &partial, Some(&tag))?;
+
+        if group.rank == 0 {
+            self.gather(group.size, &tag, timeout)?;
+        }
+        Ok(results)
+    }
+
+    // identifies a run across its ranks: the job of the launcher (LAB7_RUN_ID, if set, or the
+    // Slurm job and step, the PMIx namespace, the Open MPI job id) and a hash of the jobs, which
+    // every rank builds alike
+    fn run_tag(&self, group: &ProcessGroup) -> String {
+        let mut hasher = DefaultHasher::new();
+        group.size.hash(&mut hasher);
+        for job in &self.jobs {
+            (job.n, job.seed, job.schedule.to_config(), job.potential.to_config()).hash(&mut hasher);
+        }
+        let launcher = ["LAB7_RUN_ID", "SLURM_JOB_ID", "PMIX_NAMESPACE", "OMPI_MCA_ess_base_jobid"].iter()
+            .find_map(|v| std::env::var(v).ok())
+            .map_or("-".to_string(), |id| match std::env::var("SLURM_STEP_ID") {
+                Ok(step) if std::env::var("SLURM_JOB_ID").as_ref() == Ok(&id) => format!("{}.{}", id, step),
+                _ => id,
+            });
+        format!("{}:{:016x}", launcher, hasher.finish())
+    }
+
+    // merges the partial summaries of all ranks tagged with the run into summary.dat, ordered by
+    // job id
+    fn gather(&self, size: usize, tag: &str, timeout: Duration) -> io::Result<()> {
+        let start = Instant::now();
+        let paths: Vec<String> = (0..size).map(|r| format!("{}/summary_rank{}.dat", self.out_dir, r)).collect();
+        let run_line = format!("# run {}", tag);
+        let mut contents: Vec<Option<String>> = vec![None; size];
+        loop {
+            for (content, p) in contents.iter_mut().zip(&paths).filter(|(c, _)| c.is_none()) {
+                match std::fs::read_to_string(p) {
+                    Ok(c) if c.lines().next() == Some(run_line.as_str()) => *content = Some(c),
+                    Ok(_) => {} // of an earlier run, the rank has not replaced it yet
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            if contents.iter().all(|c| c.is_some()) {
+                break
+            }
+            if start.elapsed() > timeout {
+                let missing: Vec<usize> = (0..size).filter(|&r| contents[r].is_none()).collect();
+                return Err(io::Error::new(io::ErrorKind::TimedOut,
+                                          format!("timeout while waiting for the partial summaries of ranks {:?} in {}",
+                                                  missing, self.out_dir)));
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+
+        let mut header = String::new();
+        let mut rows: Vec<(usize, String)> = Vec::new();
+        for content in contents.iter().flatten() {
+            for line in content.lines().skip(1) {
+                if line.starts_with('#') { header = line.to_string(); continue }
+                if let Some(id) = line.split_whitespace().next().and_then(|x| x.parse().ok()) {
+                    rows.push((id, line.to_string()));
+                }
+            }
+        }
+        rows.sort_by_key(|r| r.0);
+
+        write_atomic(&format!("{}/summary.dat", self.out_dir), |f| {
+            writeln!(f, "{}", header)?;
+            for (_, line) in rows {
+                writeln!(f, "{}", line)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn _run_jobs<P: Fn(usize) -> bool>(&self, take: P) -> io::Result<Vec<JobResult>> {
+        std::fs::create_dir_all(&self.out_dir)?;
+
+        let queue = Mutex::new(self.jobs.iter().cloned().enumerate()
+                                          .filter(|(id, _)| take(*id))
+                                          .collect::<VecDeque<_>>());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::scope(|s| {
+            for _ in 0..self.threads.max(1) {
+                let tx = tx.clone();
+                let queue = &queue;
+                s.spawn(move || {
+                    loop {
+                        let next = queue.lock().unwrap().pop_front();
+                        let Some((id, job)) = next else { break };
+                        match self.run_job(id, job) {
+                            Ok(result) => tx.send(result).expect("result channel closed"),
+                            Err(e) => error!(id, "job failed: {}", e),
+                        }
+                    }
+                });
+            }
+        });
+        drop(tx);
+
+        let mut results: Vec<JobResult> = rx.into_iter().collect();
+        results.sort_by_key(|r| r.id);
+        Ok(results)
+    }
+
+    fn run_job(&self, id: usize, job: Job) -> io::Result<JobResult> {
+        let start = Instant::now();
+        seed_rng(job.seed);
+        info!(id, n = job.n, seed = job.seed, "job started");
+
+        let columns = anneal_columns(&self.observables);
+        let mut log = ObservableWriter::create(&format!("{}/job_{}_N{}_seed{}.log", self.out_dir, id, job.n, job.seed),
+                                               &columns.iter().map(|c| c.as_str()).collect::<Vec<_>>())?;
+        let mut f = match job.start {
+            Some(f) => f,
+            None => {
+                let mut f = Fuleren::new(job.n);
+                f.randomize_on_sphere(self.r_start);
+                f
+            }
+        };
+        f.set_potential(job.potential).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        f.energy_calc();
+        let it = f.anneal_observed(&job.schedule, &mut log, &self.cadence, &self.observables, None, None)?;
+        f.energy_calc();
+        if self.align {
+            f.align_principal_axes();
+        }
+        let name = format!("{}/job_{}_N{}_seed{}", self.out_dir, id, job.n, job.seed);
+        let meta = Metadata { seed: Some(job.seed), iteration: Some(it), ..Metadata::of(&f) };
+        if self.extxyz { f.save_extxyz(&format!("{}.extxyz", name), &meta)?; }
+        else { f.save_pos_xyz(&format!("{}.xyz", name), &meta)?; }
+        if let Some(archive) = &self.archive {
+            match archive.add(&f, &name) {
+                Ok(Some(rank)) => info!(id, n = job.n, rank, "structure archived"),
+                Ok(None) => {}
+                Err(e) => error!(id, "structure not archived: {}", e),
+            }
+        }
+        #[cfg(feature = "rusqlite")]
+        if let Some(store) = &self.store {
+            let parameters = job.schedule.to_config() + &f.potential.to_config();
+            if let Err(e) = store.insert(&f, Some(job.seed), &parameters, &name) {
+                error!(id, "result not stored: {}", e);
+            }
+        }
+
+        info!(id, it, T_final = %job.schedule.temperature(it), E = f.E, seconds = start.elapsed().as_secs_f64(), "job finished");
+        let defects = f.coordination().iter().filter(|&&c| c != 3).count();
+        Ok(JobResult { id, n: job.n, seed: job.seed, schedule: job.schedule, e: f.E, r_mean: f.mean_r(), defects,
+                       isomer: f.isomer(), seconds: start.elapsed().as_secs_f64() })
+    }
+
+    // with a tag, a partial summary starting with the "# run <tag>" line
+    fn save_summary(&self, results: &[JobResult], name: &str, tag: Option<&str>) -> io::Result<()> {
+        write_atomic(&format!("{}/{}", self.out_dir, name), |f| {
+            if let Some(tag) = tag {
+                writeln!(f, "# run {}", tag)?;
+            }
+            writeln!(f, "# {:<6} {:<6} {:<10} {:<8} {:<8} {:<6} {:<10} {:<12} {:<10} {:<10} {:<8} {:<10} isomer",
+                     "job", "N", "seed", "b_min", "b_max", "p", "it_max", "E", "E/N", "r_mean", "defects", "time[s]")?;
+            for r in results {
+                let isomer = r.isomer.as_ref().map_or("-".to_string(), |i| i.to_string());
+                writeln!(f, "  {:<6} {:<6} {:<10} {:<8} {:<8} {:<6} {:<10} {:<12.5} {:<10.5} {:<10.5} {:<8} {:<10.2} {}",
+                         r.id, r.n, r.seed, r.schedule.beta_min, r.schedule.beta_max, r.schedule.p, r.schedule.it_max,
+                         r.e, r.e/r.n as f64, r.r_mean, r.defects, r.seconds, isomer)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// final E/N of the runs of one N over the seeds
+#[derive(Debug, Clone)]
+pub struct EnsemblePoint {
+    pub n: usize,
+    pub mean: f64,
+    pub se: f64, // standard error of the mean, 0 for a single run
+    pub runs: usize,
+}
+
+/// (N, E/N) of every job of a summary.dat written by ParallelRunner
+pub fn read_summary(path: &str) -> Result<Vec<(usize, f64)>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    content.lines()
+           .enumerate()
+           .filter(|(_, line)| !line.starts_with('#') && !line.trim().is_empty())
+           .map(|(k, line)| {
+               let cols: Vec<&str> = line.split_whitespace().collect();
+               let n = cols.get(1).and_then(|c| c.parse().ok());
+               let e = cols.get(8).and_then(|c| c.parse().ok());
+               n.zip(e).ok_or(format!("{}:{}: cannot parse \"{}\"", path, k + 1, line))
+           })
+           .collect()
+}
+
+/// a job of a summary.dat as the isomer summary sees it
+#[derive(Debug, Clone)]
+pub struct IsomerRun {
+    pub n: usize,
+    pub isomer: String,    // "-" for structures that are not closed cages
+    pub e: f64,
+    pub structure: String, // stem of the final structure, job_<id>_N<n>_seed<seed> next to the summary
+}
+
+/// harmonic free energy of a closed cage isomer and its rank by it among the isomers of its N
+#[derive(Debug, Clone)]
+pub struct IsomerRank {
+    pub n: usize,
+    pub isomer: String,
+    pub f: f64,      // F(T) [eV]
+    pub rank: usize, // 0 for the lowest F
+}
+
+/// every job of a summary.dat
+pub fn read_isomers(path: &str) -> Result<Vec<IsomerRun>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    content.lines()
+           .enumerate()
+           .filter(|(_, line)| !line.starts_with('#') && !line.trim().is_empty())
+           .map(|(k, line)| {
+               let cols: Vec<&str> = line.split_whitespace().collect();
+               let parse = |c: usize| cols.get(c).and_then(|c| c.parse::<f64>().ok());
+               match (cols.first(), parse(1), cols.get(2), parse(7), cols.get(12)) {
+                   (Some(id), Some(n), Some(seed), Some(e), Some(isomer)) => {
+                       Ok(IsomerRun { n: n as usize, isomer: isomer.to_string(), e,
+                                      structure: format!("job_{}_N{}_seed{}", id, n, seed) })
+                   }
+                   _ => Err(format!("{}:{}: cannot parse \"{}\"", path, k + 1, line)),
+               }
+           })
+           .collect()
+}
+
+/// ranks the closed cage isomers of every N by harmonic free energy at temperature t [K] (see
+/// vibrations::rank_by_free_energy), each from its lowest-energy run in out_dir relaxed with the
+/// potential; a hessian per isomer, so only on request
+pub fn rank_isomers(runs: &[IsomerRun], out_dir: &str, potential: &Potential, t: f64) -> Result<Vec<IsomerRank>, String> {
+    let mut sizes: Vec<usize> = runs.iter().map(|r| r.n).collect();
+    sizes.sort_unstable();
+    sizes.dedup();
+
+    let mut ranks = Vec::new();
+    for n in sizes {
+        let mut best: Vec<&IsomerRun> = Vec::new();
+        for run in runs.iter().filter(|r| r.n == n && r.isomer != "-") {
+            match best.iter_mut().find(|b| b.isomer == run.isomer) {
+                Some(b) if run.e < b.e => *b = run,
+                Some(_) => {}
+                None => best.push(run),
+            }
+        }
+        let mut structures = best.iter().map(|run| {
+            let stem = format!("{}/{}", out_dir, run.structure);
+            let path = if std::path::Path::new(&format!("{}.extxyz", stem)).exists() { format!("{}.extxyz", stem) }
+                       else { format!("{}.xyz", stem) };
+            let mut f = Fuleren::frames_from_file(&path)?.pop().ok_or(format!("{}: no structure found", path))?;
+            f.set_potential(potential.clone())?;
+            f.relax(1e-3, 10_000);
+            Ok(f)
+        }).collect::<Result<Vec<_>, String>>()?;
+        for (rank, (k, f)) in rank_by_free_energy(&mut structures, t).into_iter().enumerate() {
+            info!(n, isomer = %best[k].isomer, F = f, rank, "isomer ranked");
+            ranks.push(IsomerRank { n, isomer: best[k].isomer.clone(), f, rank });
+        }
+    }
+    Ok(ranks)
+}
+
+/// how often every isomer was reached per N, as out_dir/isomers.dat ("N isomer runs fraction"),
+/// most frequent first; with a ranking (from rank_isomers at temperature t [K]) the free energy and
+/// rank of every closed cage in two more columns
+pub fn save_isomers(runs: &[IsomerRun], ranking: Option<(&[IsomerRank], f64)>, out_dir: &str) -> io::Result<()> {
+    let mut counts: Vec<(usize, &str, usize)> = Vec::new();
+    for run in runs {
+        match counts.iter_mut().find(|(m, i, _)| *m == run.n && *i == run.isomer) {
+            Some(c) => c.2 += 1,
+            None => counts.push((run.n, &run.isomer, 1)),
+        }
+    }
+    counts.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)));
+    write_atomic(&format!("{}/isomers.dat", out_dir), |f| {
+        match ranking {
+            Some((_, t)) => {
+                writeln!(f, "# F: harmonic free energy at T = {} K of the lowest-energy run, relaxed; rank_F by F within N", t)?;
+                writeln!(f, "# {:<6} {:<40} {:<6} {:<8} {:<14} rank_F", "N", "isomer", "runs", "fraction", "F[eV]")?;
+            }
+            None => writeln!(f, "# {:<6} {:<40} {:<6} {:<8}", "N", "isomer", "runs", "fraction")?,
+        }
+        for &(n, isomer, count) in &counts {
+            let total = runs.iter().filter(|r| r.n == n).count();
+            write!(f, "  {:<6} {:<40} {:<6} {:<8.4}", n, isomer, count, count as f64/total as f64)?;
+            if let Some((ranks, _)) = ranking {
+                match ranks.iter().find(|r| r.n == n && r.isomer == isomer) {
+                    Some(r) => write!(f, " {:<14.6} {}", r.f, r.rank)?,
+                    None => write!(f, " {:<14} -", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    })
+}
+
+/// mean and standard error of E/N for every N of the (N, E/N) pairs, ordered by N
+pub fn ensemble(runs: &[(usize, f64)]) -> Vec<EnsemblePoint> {
+    let mut sizes: Vec<usize> = runs.iter().map(|&(n, _)| n).collect();
+    sizes.sort_unstable();
+    sizes.dedup();
+    sizes.into_iter().map(|n| {
+        let e: Vec<f64> = runs.iter().filter(|&&(m, _)| m == n).map(|&(_, e)| e).collect();
+        let k = e.len() as f64;
+        let mean = e.iter().sum::<f64>()/k;
+        let var = if e.len() > 1 { e.iter().map(|x| (x - mean).powi(2)).sum::<f64>()/(k - 1.) } else { 0. };
+        EnsemblePoint { n, mean, se: (var/k).sqrt(), runs: e.len() }
+    }).collect()
+}
+
+/// writes the E/N curve as out_dir/en_curve.dat ("N E/N_mean E/N_se runs") and en_curve.gp, a
+/// gnuplot script drawing it with error bars into en_curve.png
+pub fn save_ensemble(points: &[EnsemblePoint], out_dir: &str) -> io::Result<()> {
+    write_atomic(&format!("{}/en_curve.dat", out_dir), |f| {
+        writeln!(f, "# {:<6} {:<12} {:<12} {:<6}", "N", "E/N_mean", "E/N_se", "runs")?;
+        for p in points {
+            writeln!(f, "  {:<6} {:<12.6} {:<12.6} {:<6}", p.n, p.mean, p.se, p.runs)?;
+        }
+        Ok(())
+    })?;
+    write_atomic(&format!("{}/en_curve.gp", out_dir), |f| {
+        writeln!(f, "# gnuplot en_curve.gp, run in this directory")?;
+        writeln!(f, "set terminal pngcairo size 1000,700 noenhanced\nset output 'en_curve.png'")?;
+        writeln!(f, "set xlabel 'N'\nset ylabel 'E/N [eV]'\nset xtics 2\nset key off")?;
+        writeln!(f, "plot 'en_curve.dat' using 1:2:3 with yerrorbars pt 7, '' using 1:2 with lines")
+    })
+}
+
+/// position of this process in a multi-process run
+#[derive(Debug, Clone)]
+pub struct ProcessGroup {
+    pub rank: usize,
+    pub size: usize,
+}
+
+impl ProcessGroup {
+    /// rank and size as set by the launcher (mpirun from Open MPI or MPICH, or srun);
+    /// a single process run if none is found
+    pub fn from_env() -> ProcessGroup {
+        let vars = [("OMPI_COMM_WORLD_RANK", "OMPI_COMM_WORLD_SIZE"),
+                    ("PMI_RANK", "PMI_SIZE"),
+                    ("SLURM_PROCID", "SLURM_NTASKS")];
+        for (rank, size) in vars {
+            let rank = std::env::var(rank).ok().and_then(|v| v.parse().ok());
+            let size = std::env::var(size).ok().and_then(|v| v.parse().ok());
+            if let (Some(rank), Some(size)) = (rank, size) {
+                return ProcessGroup { rank, size }
+            }
+        }
+        ProcessGroup { rank: 0, size: 1 }
+    }
+}
@@ -0,0 +1,110 @@
+//! Schlegel diagram of a closed cage: the bond graph (pairs inside R1) drawn in the plane without
+//! crossings by the barycentric (Tutte) embedding, one face stretched around all others and every
+//! other atom at the mean position of its neighbours. Pentagons are shaded, so the ring structure
+//! can be checked at a glance
+use std::f64::consts::PI;
+use std::io::Write;
+
+use crate::utilities::write_atomic;
+use crate::{Fuleren, R1};
+
+// Gauss-Seidel sweeps of the barycentric embedding, stopped earlier once no atom moves by more
+// than TOLERANCE (in units of the outer radius)
+const SWEEPS: usize = 100_000;
+const TOLERANCE: f64 = 1e-9;
+// drawing size [px]
+const SIZE: f64 = 600.;
+
+// bonded neighbours of every atom, or atoms of every face in cyclic order
+type AtomLists = Vec<Vec<usize>>;
+
+impl Fuleren {
+    /// plane positions of the atoms, the outer face (a hexagon, or a pentagon for C20) on the unit
+    /// circle; an error unless every atom has 3 bonds and the faces close the cage (n/2 + 2 rings
+    /// of 5 or 6 atoms)
+    pub fn schlegel(&self) -> Result<Vec<[f64; 2]>, String> {
+        let (bonds, faces) = self.cage_faces()?;
+        Ok(tutte(&bonds, &faces[outer_face(&faces)]))
+    }
+
+    /// the Schlegel diagram as SVG: bonds, pentagons shaded, atom and pentagon indices as tooltips
+    pub fn save_schlegel_svg(&self, path: &str) -> Result<(), String> {
+        let (bonds, faces) = self.cage_faces()?;
+        let outer = outer_face(&faces);
+        let x = tutte(&bonds, &faces[outer]);
+        let px = |p: [f64; 2]| (SIZE/2.*(1. + 0.95*p[0]), SIZE/2.*(1. - 0.95*p[1]));
+
+        write_atomic(path, |f| {
+            writeln!(f, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#, SIZE)?;
+            writeln!(f, r#"  <rect width="100%" height="100%" fill="white"/>"#)?;
+            // the outer face is the rest of the plane, left blank
+            for (k, face) in faces.iter().enumerate().filter(|&(k, face)| face.len() == 5 && k != outer) {
+                let points = face.iter().map(|&i| { let (a, b) = px(x[i]); format!("{:.2},{:.2}", a, b) })
+                                 .collect::<Vec<_>>().join(" ");
+                writeln!(f, r##"  <polygon points="{}" fill="#f4b183"><title>pentagon {}</title></polygon>"##, points, k)?;
+            }
+            for i in 0..self.size {
+                for &j in bonds[i].iter().filter(|&&j| j > i) {
+                    let ((a, b), (c, d)) = (px(x[i]), px(x[j]));
+                    writeln!(f, r#"  <line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="black" stroke-width="1.5"/>"#,
+                             a, b, c, d)?;
+                }
+            }
+            for (i, &p) in x.iter().enumerate() {
+                let (a, b) = px(p);
+                writeln!(f, r#"  <circle cx="{:.2}" cy="{:.2}" r="3" fill="black"><title>atom {}</title></circle>"#, a, b, i)?;
+            }
+            writeln!(f, "</svg>")
+        }).map_err(|e| format!("cannot write {}: {}", path, e))
+    }
+
+    // bond graph and faces (atoms in cyclic order) of a closed cage
+    fn cage_faces(&self) -> Result<(AtomLists, AtomLists), String> {
+        let bonds = self.bond_graph(R1);
+        if let Some(i) = (0..self.size).find(|&i| bonds[i].len() != 3) {
+            return Err(format!("not a closed cage: atom {} has {} bonds", i, bonds[i].len()));
+        }
+        let faces: AtomLists = self.rings(6).iter().filter_map(|ring| cycle(ring, &bonds)).collect();
+        if faces.len() != self.size/2 + 2 || faces.iter().any(|f| f.len() < 5) {
+            return Err(format!("not a closed cage: {} faces of 5 or 6 atoms, a cage of {} atoms has {}",
+                               faces.iter().filter(|f| f.len() >= 5).count(), self.size, self.size/2 + 2));
+        }
+        Ok((bonds, faces))
+    }
+}
+
+// the first hexagon, a pentagon only for C20
+fn outer_face(faces: &[Vec<usize>]) -> usize {
+    faces.iter().position(|f| f.len() == 6).unwrap_or(0)
+}
+
+// barycentric embedding with the outer face on the unit circle
+fn tutte(bonds: &[Vec<usize>], outer: &[usize]) -> Vec<[f64; 2]> {
+    let mut x = vec![[0.; 2]; bonds.len()];
+    let mut fixed = vec![false; bonds.len()];
+    for (k, &i) in outer.iter().enumerate() {
+        let angle = 2.*PI*k as f64/outer.len() as f64;
+        x[i] = [angle.cos(), angle.sin()];
+        fixed[i] = true;
+    }
+    for _ in 0..SWEEPS {
+        let mut change: f64 = 0.;
+        for i in (0..bonds.len()).filter(|&i| !fixed[i]) {
+            let mean = [0, 1].map(|c| bonds[i].iter().map(|&j| x[j][c]).sum::<f64>()/bonds[i].len() as f64);
+            change = change.max((mean[0] - x[i][0]).abs().max((mean[1] - x[i][1]).abs()));
+            x[i] = mean;
+        }
+        if change < TOLERANCE { break }
+    }
+    x
+}
+
+// atoms of a ring in the order along its bonds, None if they do not form a cycle
+fn cycle(ring: &[usize], bonds: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let mut order = vec![ring[0]];
+    while order.len() < ring.len() {
+        let last = *order.last()?;
+        order.push(bonds[last].iter().copied().find(|j| ring.contains(j) && !order.contains(j))?);
+    }
+    bonds[*order.last()?].contains(&order[0]).then_some(order)
+}
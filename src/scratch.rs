@@ -0,0 +1,45 @@
+//! per-thread scratch buffers of the energy kernel: the neighbour list, the shells and the bonded
+//! neighbours of the torsion term are refilled in place, so after the first calls on a thread an
+//! evaluation of V_i allocates nothing
+use std::cell::RefCell;
+
+use num_traits::Float;
+
+use crate::potential::Bonded;
+use crate::Shell;
+
+pub(crate) struct Scratch<T> {
+    pub near: Vec<usize>,   // atoms within 2*R2 of the center
+    pub shell_i: Shell<T>,  // shell of the center
+    pub shell_j: Shell<T>,  // shell of the bond partner
+    pub bonded_i: Bonded<T>,
+    pub bonded_j: Bonded<T>,
+}
+
+impl<T> Scratch<T> {
+    fn new() -> Scratch<T> {
+        Scratch { near: Vec::new(), shell_i: Shell::new(), shell_j: Shell::new(),
+                  bonded_i: Vec::new(), bonded_j: Vec::new() }
+    }
+}
+
+/// float types of the energy kernel, each with the scratch buffers of the running thread
+pub(crate) trait KernelFloat: Float + 'static {
+    /// runs f on the thread's buffers; f must not evaluate the kernel itself (the buffers are
+    /// borrowed for the duration)
+    fn with_scratch<R>(f: impl FnOnce(&mut Scratch<Self>) -> R) -> R;
+}
+
+macro_rules! kernel_float {
+    ($t:ty) => {
+        impl KernelFloat for $t {
+            fn with_scratch<R>(f: impl FnOnce(&mut Scratch<$t>) -> R) -> R {
+                thread_local!(static SCRATCH: RefCell<Scratch<$t>> = RefCell::new(Scratch::new()));
+                SCRATCH.with(|s| f(&mut s.borrow_mut()))
+            }
+        }
+    };
+}
+
+kernel_float!(f32);
+kernel_float!(f64);
@@ -0,0 +1,314 @@
+//! atom selections by a small expression language, evaluated on the current structure:
+//!   all | index 10:20,25 | element Si | within 4.5 of 0:5 | <property> <op> <number>
+//! combined with and, or, not and parentheses. Index lists are inclusive ranges (10:20 or 10-20)
+//! of increasing indices and single atoms; the properties are x, y, z, r, theta, phi [A, rad], coordination or coord
+//! (bonds inside R1) and energy (0.5*V_i [eV]), the operators <, <=, >, >=, == and !=.
+//! Selections pick the atoms of the temperature groups and of the fixed constraint of the anneal,
+//! the per-atom output of analyze and the atoms of exported graphs.
+//! Example: "coord<3 or within 3 of 10:14"
+use std::fmt;
+
+use crate::{Element, Fuleren};
+
+/// a parsed selection expression, displayed as the text it was parsed from
+#[derive(Debug, Clone)]
+pub struct Selection {
+    text: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    All,
+    Index(Vec<(usize, usize)>),
+    Element(Element),
+    Within(f64, Vec<(usize, usize)>),
+    Compare(Property, Op, f64),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Property { X, Y, Z, R, Theta, Phi, Coordination, Energy }
+
+#[derive(Debug, Clone, Copy)]
+enum Op { Lt, Le, Gt, Ge, Eq, Ne }
+
+impl Selection {
+    pub fn parse(text: &str) -> Result<Selection, String> {
+        let tokens = tokenize(text);
+        let mut parser = Parser { tokens: &tokens, pos: 0, text };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(parser.err(&format!("unexpected \"{}\"", token)));
+        }
+        Ok(Selection { text: text.trim().to_string(), expr })
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Fuleren {
+    /// atoms in the selection, one flag per atom
+    pub fn select(&self, selection: &Selection) -> Vec<bool> {
+        self._select(&selection.expr)
+    }
+
+    /// copy with only the atoms flagged in keep, e.g. by select
+    pub fn subset(&self, keep: &[bool]) -> Fuleren {
+        let atoms: Vec<usize> = (0..self.size).filter(|&i| keep[i]).collect();
+        let mut f = self.clone();
+        f.positions = atoms.iter().map(|&i| self.positions[i].clone()).collect();
+        f.species = atoms.iter().map(|&i| self.species[i]).collect();
+        f.size = atoms.len();
+        if let Some(coulomb) = f.potential.coulomb.as_mut().filter(|c| !c.charges.is_empty()) {
+            coulomb.charges = atoms.iter().map(|&i| coulomb.charges[i]).collect();
+        }
+        f
+    }
+
+    fn _select(&self, expr: &Expr) -> Vec<bool> {
+        let in_ranges = |i: usize, ranges: &[(usize, usize)]| ranges.iter().any(|&(a, b)| a <= i && i <= b);
+        match expr {
+            Expr::All => vec![true; self.size],
+            Expr::Index(ranges) => (0..self.size).map(|i| in_ranges(i, ranges)).collect(),
+            Expr::Element(element) => self.species.iter().map(|e| e == element).collect(),
+            Expr::Within(r, ranges) => (0..self.size)
+                .map(|i| (0..self.size).any(|j| in_ranges(j, ranges) && (i == j || self._r_ij(i, j) <= *r)))
+                .collect(),
+            Expr::Compare(property, op, value) => {
+                let values: Vec<f64> = match property {
+                    Property::Coordination => self.coordination().into_iter().map(|c| c as f64).collect(),
+                    Property::Energy => (0..self.size).map(|i| 0.5*self._vi(i)).collect(),
+                    _ => self.positions.iter().map(|p| match property {
+                        Property::X => p.x(),
+                        Property::Y => p.y(),
+                        Property::Z => p.z(),
+                        Property::R => p.r(),
+                        Property::Theta => p.theta(),
+                        _ => p.phi(),
+                    }).collect(),
+                };
+                values.into_iter().map(|v| match op {
+                    Op::Lt => v < *value,
+                    Op::Le => v <= *value,
+                    Op::Gt => v > *value,
+                    Op::Ge => v >= *value,
+                    Op::Eq => v == *value,
+                    Op::Ne => v != *value,
+                }).collect()
+            }
+            Expr::Not(a) => self._select(a).into_iter().map(|s| !s).collect(),
+            Expr::And(a, b) => self._select(a).into_iter().zip(self._select(b)).map(|(x, y)| x && y).collect(),
+            Expr::Or(a, b) => self._select(a).into_iter().zip(self._select(b)).map(|(x, y)| x || y).collect(),
+        }
+    }
+}
+
+// words (names, numbers, index lists), runs of comparison characters and single parentheses
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let kind = |c: char| if "()".contains(c) { 0 } else if "<>=!".contains(c) { 1 } else { 2 };
+    let mut last = None;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            last = None;
+            continue
+        }
+        match tokens.last_mut() {
+            Some(token) if last == Some(kind(c)) && kind(c) != 0 => token.push(c),
+            _ => tokens.push(c.to_string()),
+        }
+        last = Some(kind(c));
+    }
+    tokens
+}
+
+// recursive descent: or of ands of (possibly negated) terms
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    text: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn err(&self, message: &str) -> String {
+        format!("cannot parse selection \"{}\": {}", self.text, message)
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        let token = self.tokens.get(self.pos).ok_or_else(|| self.err("unexpected end"))?;
+        self.pos += 1;
+        Ok(token.as_str())
+    }
+
+    fn peek_is(&self, word: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|t| t == word)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek_is("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        while self.peek_is("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        match self.next()? {
+            "not" => Ok(Expr::Not(Box::new(self.term()?))),
+            "(" => {
+                let expr = self.or()?;
+                match self.next()? {
+                    ")" => Ok(expr),
+                    t => Err(self.err(&format!("expected \")\", got \"{}\"", t))),
+                }
+            }
+            "all" => Ok(Expr::All),
+            "element" => {
+                let symbol = self.next()?;
+                Element::from_symbol(symbol).map(Expr::Element)
+                                            .ok_or_else(|| self.err(&format!("unknown element \"{}\"", symbol)))
+            }
+            "index" => {
+                let list = self.next()?;
+                Ok(Expr::Index(self.ranges(list)?))
+            }
+            "within" => {
+                let r = self.number()?;
+                if self.next()? != "of" {
+                    return Err(self.err("expected \"within <distance> of <atoms>\""));
+                }
+                let list = self.next()?;
+                Ok(Expr::Within(r, self.ranges(list)?))
+            }
+            name => {
+                let property = match name {
+                    "x" => Property::X,
+                    "y" => Property::Y,
+                    "z" => Property::Z,
+                    "r" => Property::R,
+                    "theta" => Property::Theta,
+                    "phi" => Property::Phi,
+                    "coordination" | "coord" => Property::Coordination,
+                    "energy" => Property::Energy,
+                    _ => return Err(self.err(&format!("unknown property \"{}\"", name))),
+                };
+                let op = match self.next()? {
+                    "<" => Op::Lt,
+                    "<=" => Op::Le,
+                    ">" => Op::Gt,
+                    ">=" => Op::Ge,
+                    "==" => Op::Eq,
+                    "!=" => Op::Ne,
+                    t => return Err(self.err(&format!("expected a comparison, got \"{}\"", t))),
+                };
+                Ok(Expr::Compare(property, op, self.number()?))
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        let token = self.next()?;
+        token.parse::<f64>().map_err(|_| self.err(&format!("expected a number, got \"{}\"", token)))
+    }
+
+    // "10:20,25" (or 10-20) as inclusive ranges; a reversed range such as 20:10 is an error
+    fn ranges(&self, list: &str) -> Result<Vec<(usize, usize)>, String> {
+        list.split(',').map(|part| {
+            let err = || self.err(&format!("cannot parse atom list \"{}\"", list));
+            let index = |s: &str| s.parse::<usize>().map_err(|_| err());
+            match part.split_once([':', '-']) {
+                Some((a, b)) => match (index(a)?, index(b)?) {
+                    (a, b) if a > b => Err(self.err(&format!("reversed range \"{}\"", part))),
+                    range => Ok(range),
+                },
+                None => index(part).map(|i| (i, i)),
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // indices of the atoms of C60 in the selection
+    fn selected(text: &str) -> Vec<usize> {
+        let f = Fuleren::reference("C60").unwrap();
+        let flags = f.select(&Selection::parse(text).unwrap());
+        (0..flags.len()).filter(|&i| flags[i]).collect()
+    }
+
+    #[test]
+    fn precedence() {
+        // and binds tighter than or, not tighter than and
+        assert_eq!(selected("index 0 or index 1 and index 2"), [0]);
+        assert_eq!(selected("(index 0 or index 1) and index 1"), [1]);
+        assert_eq!(selected("not index 0:57 or index 3"), [3, 58, 59]);
+        assert_eq!(selected("not index 0 and index 0:1"), [1]);
+        assert_eq!(selected("not (index 0:58 and not index 5)"), [5, 59]);
+        assert_eq!(selected("not not index 7"), [7]);
+    }
+
+    #[test]
+    fn ranges_and_properties() {
+        assert_eq!(selected("index 10:12,20-21,30"), [10, 11, 12, 20, 21, 30]);
+        assert_eq!(selected("index 58:100"), [58, 59]);
+        assert_eq!(selected("all").len(), 60);
+        assert_eq!(selected("element C").len(), 60);
+        assert!(selected("element Si").is_empty());
+        assert_eq!(selected("coord==3").len(), 60);
+        assert_eq!(selected("coord < 3 or r > 100"), Vec::<usize>::new());
+        let f = Fuleren::reference("C60").unwrap();
+        let above = (0..60).filter(|&i| f.positions[i].z() > 0.).collect::<Vec<_>>();
+        assert_eq!(selected("z>0"), above);
+        assert_eq!(selected("z>-1e9 and not z>0").len(), 60 - above.len());
+    }
+
+    #[test]
+    fn within() {
+        // an atom of C60 and its three bonded neighbours
+        let f = Fuleren::reference("C60").unwrap();
+        let mut expected = f.bond_graph(crate::R1)[0].clone();
+        expected.push(0);
+        expected.sort_unstable();
+        assert_eq!(selected("within 1.6 of 0"), expected);
+        assert_eq!(selected("within 0 of 4,9"), [4, 9]);
+        assert_eq!(selected("within 100 of 0").len(), 60);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let err = |text: &str| Selection::parse(text).err().unwrap();
+        assert_eq!(err("index 20:10"), "cannot parse selection \"index 20:10\": reversed range \"20:10\"");
+        assert_eq!(err("index 1,5-2"), "cannot parse selection \"index 1,5-2\": reversed range \"5-2\"");
+        assert_eq!(err("index"), "cannot parse selection \"index\": unexpected end");
+        assert_eq!(err("index 0 or"), "cannot parse selection \"index 0 or\": unexpected end");
+        assert_eq!(err("(index 0"), "cannot parse selection \"(index 0\": unexpected end");
+        assert_eq!(err("(index 0 all"), "cannot parse selection \"(index 0 all\": expected \")\", got \"all\"");
+        assert_eq!(err("index 0 index 1"), "cannot parse selection \"index 0 index 1\": unexpected \"index\"");
+        assert_eq!(err("index a:3"), "cannot parse selection \"index a:3\": cannot parse atom list \"a:3\"");
+        assert_eq!(err("mass > 1"), "cannot parse selection \"mass > 1\": unknown property \"mass\"");
+        assert_eq!(err("coord 3"), "cannot parse selection \"coord 3\": expected a comparison, got \"3\"");
+        assert_eq!(err("coord <> 3"), "cannot parse selection \"coord <> 3\": expected a comparison, got \"<>\"");
+        assert_eq!(err("x < y"), "cannot parse selection \"x < y\": expected a number, got \"y\"");
+        assert_eq!(err("within 2 index 0"), "cannot parse selection \"within 2 index 0\": expected \"within <distance> of <atoms>\"");
+        assert_eq!(err("element Xx"), "cannot parse selection \"element Xx\": unknown element \"Xx\"");
+    }
+}
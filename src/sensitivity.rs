@@ -0,0 +1,168 @@
+use std::io::Write;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use tracing::info;
+
+use crate::anneal::{Schedule, INTEGER_KEYS};
+use crate::potential::Potential;
+use crate::rng;
+use crate::runner::{Job, ParallelRunner};
+use crate::utilities::write_atomic;
+
+/// how the parameter ranges are sampled
+#[derive(Debug, Clone, Copy)]
+pub enum Sampling {
+    Grid(usize),           // k evenly spaced values per parameter, k^d points
+    LatinHypercube(usize), // k points, every parameter range split into k strata hit once each
+}
+
+/// sensitivity study: one or two config keys of the schedule or the potential scanned over ranges,
+/// short anneals of every point with all seeds run in parallel, mean and best final E/N and
+/// defect counts per point written to sensitivity.dat
+pub struct SensitivitySweep {
+    pub n: usize,
+    pub parameters: Vec<(String, f64, f64)>, // (config key, min, max)
+    pub sampling: Sampling,
+    pub seeds: Vec<u64>,
+    pub it_max: Option<usize>, // overrides the schedule, for short anneals
+    pub config: String,        // base config, the point values are appended to it
+    pub config_path: String,   // labels errors and anchors relative paths of the base config
+}
+
+/// final energies and defect counts of the runs of one point
+#[derive(Debug, Clone)]
+pub struct SensitivityPoint {
+    pub values: Vec<f64>,
+    pub e_per_atom: Vec<f64>,
+    pub defects: Vec<usize>,
+}
+
+impl SensitivityPoint {
+    pub fn mean_e_per_atom(&self) -> f64 {
+        self.e_per_atom.iter().sum::<f64>()/self.e_per_atom.len() as f64
+    }
+
+    pub fn min_e_per_atom(&self) -> f64 {
+        self.e_per_atom.iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn mean_defects(&self) -> f64 {
+        self.defects.iter().sum::<usize>() as f64/self.defects.len() as f64
+    }
+}
+
+/// parses a "key:min:max" range
+pub fn parse_parameter(s: &str) -> Result<(String, f64, f64), String> {
+    let err = || format!("cannot parse parameter range \"{}\", expected key:min:max", s);
+    match s.split(':').collect::<Vec<_>>()[..] {
+        [key, min, max] => {
+            let (min, max) = (min.parse::<f64>().map_err(|_| err())?, max.parse::<f64>().map_err(|_| err())?);
+            Ok((key.to_string(), min, max))
+        }
+        _ => Err(err()),
+    }
+}
+
+impl SensitivitySweep {
+    /// parameter values of all points
+    pub fn points(&self) -> Vec<Vec<f64>> {
+        let lerp = |(_, lo, hi): &(String, f64, f64), x: f64| lo + (hi - lo)*x;
+        match self.sampling {
+            Sampling::Grid(k) => {
+                let fraction = |i: usize| if k > 1 { i as f64/(k - 1) as f64 } else { 0.5 };
+                // point m has index (m / k^(d-1-j)) % k along parameter j, the last one varies fastest
+                let d = self.parameters.len();
+                (0..k.pow(d as u32)).map(|m| {
+                    self.parameters.iter()
+                                   .enumerate()
+                                   .map(|(j, par)| lerp(par, fraction(m/k.pow((d - 1 - j) as u32) % k)))
+                                   .collect()
+                }).collect()
+            }
+            Sampling::LatinHypercube(k) => {
+                let mut rng = rng();
+                let strata: Vec<Vec<f64>> = self.parameters.iter().map(|par| {
+                    let mut column: Vec<f64> = (0..k).map(|i| lerp(par, (i as f64 + rng.gen::<f64>())/k as f64)).collect();
+                    column.shuffle(&mut rng);
+                    column
+                }).collect();
+                (0..k).map(|i| strata.iter().map(|column| column[i]).collect()).collect()
+            }
+        }
+    }
+
+    // base config with the point values appended (later keys override earlier ones)
+    fn config_of(&self, values: &[f64]) -> String {
+        let mut config = self.config.clone();
+        config.push('\n');
+        for ((key, _, _), value) in self.parameters.iter().zip(values) {
+            if INTEGER_KEYS.contains(&key.as_str()) {
+                config += &format!("{} {}\n", key, value.round().max(0.) as usize);
+            }
+            else {
+                config += &format!("{} {}\n", key, value);
+            }
+        }
+        config
+    }
+
+    /// queues the anneals of every point on the runner, runs them (the runner's summary.dat lists
+    /// the single jobs) and writes sensitivity.dat to its out_dir
+    pub fn run(&self, runner: &mut ParallelRunner) -> Result<Vec<SensitivityPoint>, String> {
+        if self.parameters.is_empty() || self.parameters.len() > 2 {
+            return Err(format!("a sensitivity sweep scans one or two parameters, got {}", self.parameters.len()));
+        }
+        if self.seeds.is_empty() {
+            return Err("a sensitivity sweep needs at least one run per point".to_string());
+        }
+
+        let mut points = Vec::new();
+        let offset = runner.jobs.len();
+        for values in self.points() {
+            let config = self.config_of(&values);
+            let mut schedule = Schedule::from_config_str(&config, &self.config_path)?;
+            if let Some(it_max) = self.it_max {
+                schedule.it_max = it_max;
+            }
+            let potential = Potential::from_config_str(&config, &self.config_path)?;
+            for &seed in &self.seeds {
+                runner.jobs.push(Job { n: self.n, seed, schedule: schedule.clone(), potential: potential.clone(),
+                                        start: None });
+            }
+            points.push(SensitivityPoint { values, e_per_atom: Vec::new(), defects: Vec::new() });
+        }
+        info!(points = points.len(), jobs = runner.jobs.len() - offset, "sensitivity sweep started");
+
+        for r in runner.run().map_err(|e| e.to_string())? {
+            if r.id < offset { continue }
+            let point = &mut points[(r.id - offset)/self.seeds.len()];
+            point.e_per_atom.push(r.e/r.n as f64);
+            point.defects.push(r.defects);
+        }
+
+        self.save(&points, &format!("{}/sensitivity.dat", runner.out_dir)).map_err(|e| e.to_string())?;
+        Ok(points)
+    }
+
+    fn save(&self, points: &[SensitivityPoint], path: &str) -> std::io::Result<()> {
+        write_atomic(path, |f| {
+            write!(f, "# {:<6}", "point")?;
+            for (key, _, _) in self.parameters.iter() {
+                write!(f, " {:<12}", key)?;
+            }
+            writeln!(f, " {:<12} {:<12} {:<10} {:<6}", "E/N_mean", "E/N_min", "defects", "runs")?;
+
+            // points without a single finished run are left out
+            for (i, p) in points.iter().enumerate().filter(|(_, p)| !p.e_per_atom.is_empty()) {
+                write!(f, "  {:<6}", i)?;
+                for value in p.values.iter() {
+                    write!(f, " {:<12.6}", value)?;
+                }
+                writeln!(f, " {:<12.5} {:<12.5} {:<10.2} {:<6}", p.mean_e_per_atom(), p.min_e_per_atom(),
+                         p.mean_defects(), p.e_per_atom.len())?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,58 @@
+//! serde support (feature "serde"): a structure is stored with a stable schema, the cartesian
+//! positions, the elements (left out when all carbon), the step sizes, the hard core and the
+//! potential as its config lines; the spherical coordinates and the energy are recomputed on load,
+//! so they never disagree with the positions
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::potential::Potential;
+use crate::{Element, Fuleren, Point6, Point6Array, StepSizes};
+
+// a point is stored as [x, y, z]
+impl Serialize for Point6 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.xyz().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Point6 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <[f64; 3]>::deserialize(deserializer).map(|xyz| Point6::from_cartesian(&xyz))
+    }
+}
+
+// stored form of Fuleren; energy is informative only, it is recomputed on load
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    positions: Vec<Point6>,
+    #[serde(default)]
+    species: Vec<Element>, // empty for all carbon
+    energy: f64,
+    r_core: f64,
+    steps: StepSizes,
+    potential: String, // Potential::to_config
+}
+
+impl Serialize for Fuleren {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let species = if self.species.iter().all(|&e| e == Element::C) { Vec::new() } else { self.species.clone() };
+        Stored { positions: self.positions.to_vec(), species, energy: self.E, r_core: self.r_core, steps: self.steps.clone(),
+                 potential: self.potential.to_config() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fuleren {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = Stored::deserialize(deserializer)?;
+        let potential = Potential::from_config_str(&stored.potential, "potential").map_err(serde::de::Error::custom)?;
+        let size = stored.positions.len();
+        let species = match stored.species.len() {
+            0 => vec![Element::C; size],
+            n if n == size => stored.species,
+            n => return Err(serde::de::Error::custom(format!("{} species for {} positions", n, size))),
+        };
+        let mut f = Fuleren { size, species, positions: Point6Array::from_vec(stored.positions), E: 0.,
+                              r_core: stored.r_core, steps: stored.steps, potential };
+        f.energy_calc();
+        Ok(f)
+    }
+}
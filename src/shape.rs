@@ -0,0 +1,53 @@
+use ndarray::prelude::*;
+
+use crate::harmonics::{angles, spherical_harmonic};
+use crate::vibrations::jacobi_eigen;
+use crate::Fuleren;
+
+impl Fuleren {
+    /// shape fingerprint: least squares fit of the radial function r(theta, phi) of the atoms
+    /// (around the center of mass) with real spherical harmonics up to l_max, and the power
+    /// P_l = sum_m a_lm^2 of every l relative to P_0. A sphere has only l = 0; elongation shows up
+    /// in l = 2, the tetrahedral and icosahedral faceting in l = 3 and l = 6. l_max is lowered
+    /// until there are at least as many atoms as coefficients
+    pub fn shape_spectrum(&self, l_max: usize) -> Vec<f64> {
+        let l_max = (0..=l_max).rev().find(|l| (l + 1)*(l + 1) <= self.size).unwrap_or(0);
+        let n_coef = (l_max + 1)*(l_max + 1);
+        let c = self.center_of_mass();
+
+        let mut a = Array2::<f64>::zeros((self.size, n_coef));
+        let mut r = Array1::<f64>::zeros(self.size);
+        for (i, p) in self.positions.iter().enumerate() {
+            let d = [p.x() - c[0], p.y() - c[1], p.z() - c[2]];
+            r[i] = (d[0]*d[0] + d[1]*d[1] + d[2]*d[2]).sqrt();
+            let (theta, phi) = angles(&d);
+            for l in 0..=l_max {
+                for m in -(l as i64)..=l as i64 {
+                    a[[i, l*l + (m + l as i64) as usize]] = real_harmonic(l, m, theta, phi);
+                }
+            }
+        }
+
+        // normal equations through the eigenvectors of A^T A, nearly singular directions dropped
+        let (eigenvalues, v) = jacobi_eigen(a.t().dot(&a));
+        let rhs = v.t().dot(&a.t().dot(&r));
+        let cutoff = 1e-10*eigenvalues.iter().copied().fold(0., f64::max);
+        let scaled = Array1::from_iter(rhs.iter().zip(eigenvalues.iter()).map(|(b, &e)| if e > cutoff { b/e } else { 0. }));
+        let coefficients = v.dot(&scaled);
+
+        let power: Vec<f64> = (0..=l_max).map(|l| (l*l..(l + 1)*(l + 1)).map(|k| coefficients[k].powi(2)).sum())
+                                         .collect();
+        power.iter().map(|p| if power[0] > 0. { p/power[0] } else { 0. }).collect()
+    }
+}
+
+// real spherical harmonic: sqrt(2)(-1)^m Re Y_lm for m > 0, sqrt(2)(-1)^m Im Y_l|m| for m < 0
+fn real_harmonic(l: usize, m: i64, theta: f64, phi: f64) -> f64 {
+    let (re, im) = spherical_harmonic(l, m.abs(), theta, phi);
+    let sign = if m % 2 == 0 { 1. } else { -1. };
+    match m {
+        0 => re,
+        m if m > 0 => std::f64::consts::SQRT_2*sign*re,
+        _ => std::f64::consts::SQRT_2*sign*im,
+    }
+}
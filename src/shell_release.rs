@@ -0,0 +1,37 @@
+//! first stage of the anneal with the atoms held near a spherical shell: a radial harmonic
+//! restraint, part of the energy so the acceptance stays exact, whose force constant decays to
+//! zero over the first iterations; the cage forms on the shell before the atoms move freely
+use std::f64::consts::PI;
+
+use crate::potential::ShellRestraint;
+
+/// restraint of force constant k [eV/A^2] at the start, released over `it` iterations, about a
+/// shell of `radius` [A] (by default that of a fullerene of the structure's size)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShellRelease {
+    pub k: f64,
+    pub it: usize,
+    pub radius: Option<f64>,
+}
+
+impl Default for ShellRelease {
+    fn default() -> Self {
+        ShellRelease { k: 5., it: 10_000, radius: None }
+    }
+}
+
+impl ShellRelease {
+    /// restraint at iteration it of the cooling for n atoms, k (1 - it/release)^2; None once released
+    pub fn restraint(&self, it: usize, n: usize) -> Option<ShellRestraint> {
+        (it < self.it).then(|| ShellRestraint {
+            radius: self.radius.unwrap_or_else(|| fullerene_radius(n)),
+            k: self.k*(1. - it as f64/self.it as f64).powi(2),
+        })
+    }
+}
+
+/// radius of a fullerene of n atoms, every atom covering the area of a graphene atom
+pub fn fullerene_radius(n: usize) -> f64 {
+    let area = 3.*3f64.sqrt()/4.*1.42f64.powi(2);
+    (n as f64*area/(4.*PI)).sqrt()
+}
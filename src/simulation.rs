@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+
+use crate::anneal::{Cadence, Control, Schedule, Step};
+use crate::observables::ObservableWriter;
+use crate::potential::Potential;
+use crate::{seed_rng, Fuleren};
+
+/// an anneal assembled by Simulation::builder(): the starting structure with its potential and
+/// the schedule, ready to run
+pub struct Simulation {
+    structure: Fuleren,
+    schedule: Schedule,
+}
+
+/// builder of a Simulation; the defaults are 60 atoms at random on a sphere of radius 2.5 A, plain
+/// Brenner, the default Schedule and an unseeded generator
+pub struct SimulationBuilder {
+    n: usize,
+    r_start: f64,
+    cage: bool,
+    start: Option<Fuleren>,
+    potential: Potential,
+    schedule: Schedule,
+    seed: Option<u64>,
+}
+
+impl Simulation {
+    pub fn builder() -> SimulationBuilder {
+        SimulationBuilder { n: 60, r_start: 2.5, cage: false, start: None, potential: Potential::default(),
+                            schedule: Schedule::default(), seed: None }
+    }
+
+    /// anneals the structure, returns the number of cooling iterations done
+    pub fn run(&mut self) -> usize {
+        self.run_with(|_, _| Control::Continue)
+    }
+
+    /// run with a callback after every iteration (see Fuleren::anneal_with)
+    pub fn run_with<F>(&mut self, callback: F) -> usize
+    where F: FnMut(&mut Fuleren, &mut Step) -> Control {
+        let it = self.structure.anneal_with(&self.schedule, callback);
+        self.structure.energy_calc();
+        it
+    }
+
+    /// run with the ANNEAL_COLUMNS logged at the cadence
+    pub fn run_logged<W: Write>(&mut self, log: &mut ObservableWriter<W>, cadence: &Cadence) -> io::Result<usize> {
+        let it = self.structure.anneal_observed(&self.schedule, log, cadence, &[], None, None)?;
+        self.structure.energy_calc();
+        Ok(it)
+    }
+
+    pub fn structure(&self) -> &Fuleren {
+        &self.structure
+    }
+
+    pub fn into_structure(self) -> Fuleren {
+        self.structure
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn energy(&self) -> f64 {
+        self.structure.E
+    }
+}
+
+impl SimulationBuilder {
+    /// number of atoms of a random start
+    pub fn atoms(mut self, n: usize) -> Self {
+        self.n = n;
+        self
+    }
+
+    /// radius [A] of the sphere of a random start
+    pub fn r_start(mut self, r: f64) -> Self {
+        self.r_start = r;
+        self
+    }
+
+    /// start from a random fullerene cage (spiral_cage) instead of random points
+    pub fn cage(mut self, cage: bool) -> Self {
+        self.cage = cage;
+        self
+    }
+
+    /// start from this structure, atoms, r_start and cage are not used
+    pub fn start(mut self, f: Fuleren) -> Self {
+        self.start = Some(f);
+        self
+    }
+
+    /// a Potential or just the Brenner constants
+    pub fn potential(mut self, potential: impl Into<Potential>) -> Self {
+        self.potential = potential.into();
+        self
+    }
+
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// seed of the random generator of the building thread, set at build(); run the simulation on
+    /// the same thread for a reproducible run
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// the starting structure with the potential and its energy; an error for invalid Brenner
+    /// constants, no atoms or a cage size without a fullerene
+    pub fn build(self) -> Result<Simulation, String> {
+        self.potential.brenner.validate()?;
+        if let Some(seed) = self.seed {
+            seed_rng(seed);
+        }
+        let mut structure = match self.start {
+            Some(f) => f,
+            None if self.n == 0 => return Err("a simulation needs at least one atom".to_string()),
+            None if self.cage => Fuleren::spiral_cage(self.n)?,
+            None => {
+                let mut f = Fuleren::new(self.n);
+                f.randomize_on_sphere(self.r_start);
+                f
+            }
+        };
+        self.potential.check_atoms(structure.size)?;
+        structure.potential = self.potential;
+        structure.energy_calc();
+        Ok(Simulation { structure, schedule: self.schedule })
+    }
+}
@@ -0,0 +1,109 @@
+//! event triggered snapshots of the anneal: conditions "<observable> <op> <threshold>" on E [eV],
+//! T [K], r_mean [A] or a collective variable (see cv::from_name) checked after every sweep, the
+//! structure saved each time a condition becomes true (held at the first check, or false before),
+//! so the moments the cage closes or the energy reaches a target are captured at full resolution.
+//! Examples: "pentagons>=12", "E<-400", "rmsd:C60<0.1"
+use std::fmt;
+use std::io;
+
+use crate::cv::{self, CollectiveVariable};
+use crate::movie::FrameWriter;
+use crate::Fuleren;
+
+/// a threshold condition on an observable, displayed as the text it was parsed from
+pub struct Trigger {
+    text: String,
+    observable: Observable,
+    op: Op,
+    threshold: f64,
+}
+
+enum Observable {
+    Energy,
+    Temperature,
+    MeanRadius,
+    Cv(Box<dyn CollectiveVariable>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op { Lt, Le, Gt, Ge }
+
+impl Trigger {
+    /// "<observable><op><threshold>" with op one of <, <=, >, >=; spaces allowed
+    pub fn parse(text: &str) -> Result<Trigger, String> {
+        let err = |message: &str| format!("cannot parse trigger \"{}\": {}", text, message);
+        let at = text.find(['<', '>']).ok_or_else(|| err("expected <observable> <op> <threshold>"))?;
+        let (name, rest) = (text[..at].trim(), &text[at..]);
+        let (op, threshold) = match rest.strip_prefix("<=").or_else(|| rest.strip_prefix(">=")) {
+            Some(threshold) => (if rest.starts_with('<') { Op::Le } else { Op::Ge }, threshold),
+            None => (if rest.starts_with('<') { Op::Lt } else { Op::Gt }, &rest[1..]),
+        };
+        let threshold = threshold.trim().parse::<f64>()
+                                 .map_err(|_| err(&format!("expected a number, got \"{}\"", threshold.trim())))?;
+        let observable = match name {
+            "E" => Observable::Energy,
+            "T" => Observable::Temperature,
+            "r_mean" => Observable::MeanRadius,
+            _ => Observable::Cv(cv::from_name(name).map_err(|e| err(&e))?),
+        };
+        Ok(Trigger { text: text.trim().to_string(), observable, op, threshold })
+    }
+
+    // value of the observable for f at temperature t [K]
+    fn value(&self, f: &Fuleren, t: f64) -> f64 {
+        match &self.observable {
+            Observable::Energy => f.E,
+            Observable::Temperature => t,
+            Observable::MeanRadius => f.mean_r(),
+            Observable::Cv(cv) => cv.value(f),
+        }
+    }
+
+    fn holds(&self, value: f64) -> bool {
+        match self.op {
+            Op::Lt => value < self.threshold,
+            Op::Le => value <= self.threshold,
+            Op::Gt => value > self.threshold,
+            Op::Ge => value >= self.threshold,
+        }
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// the triggers of a run with the trajectory their snapshots are appended to
+pub struct Snapshots {
+    triggers: Vec<Trigger>,
+    held: Vec<bool>,
+    frames: FrameWriter,
+}
+
+impl Snapshots {
+    pub fn create(path: &str, triggers: Vec<Trigger>) -> io::Result<Snapshots> {
+        Ok(Snapshots { held: vec![false; triggers.len()], triggers, frames: FrameWriter::create(path)? })
+    }
+
+    /// snapshots taken so far
+    pub fn taken(&self) -> usize {
+        self.frames.frames
+    }
+
+    /// checks the triggers on f after iteration it at temperature t [K]; a frame with the iteration,
+    /// the trigger and the value of its observable in the comment line for every trigger that fires
+    pub fn check(&mut self, f: &Fuleren, it: usize, t: f64) -> io::Result<()> {
+        for (trigger, held) in self.triggers.iter().zip(self.held.iter_mut()) {
+            let value = trigger.value(f, t);
+            let holds = trigger.holds(value);
+            if holds && !*held {
+                tracing::info!(it, trigger = %trigger, value, "snapshot");
+                self.frames.write(f, &format!("it={} T={:.1} trigger=\"{}\" value={:.6}", it, t, trigger, value))?;
+            }
+            *held = holds;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+//! speculative Metropolis: the uniform moves of a block of `depth` consecutive atoms are proposed
+//! up front and their energy changes evaluated in parallel on the structure at the start of the
+//! block, then accepted one after the other. A prefetched energy change is only used if no move
+//! accepted earlier in the block came within the interaction range of the atom, otherwise it is
+//! recomputed. Proposals and acceptance draws come from the calling thread, so the outcome is that
+//! of accepting the same proposals one by one, whatever the number of threads
+use rand::Rng;
+
+use crate::{rng, Fuleren, Point6};
+
+impl Fuleren {
+    /// one sweep of uniform atom moves (atom i at beta[i], none for the fixed ones) in blocks of
+    /// `depth` atoms evaluated by `threads` workers; returns the number of accepted moves
+    pub fn speculative_sweep(&mut self, beta: &[f64], fixed: &[bool], depth: usize, threads: usize) -> usize {
+        let range = self.potential.range();
+        let depth = depth.max(1);
+        let threads = threads.max(1).min(depth);
+        // copies for the parallel evaluation, kept in step with self after every acceptance
+        let mut workers = if threads > 1 { vec![self.clone(); threads] } else { Vec::new() };
+        let (mut accepted, mut recomputed) = (0, 0);
+
+        let moving: Vec<usize> = (0..self.size).filter(|&i| !fixed[i]).collect();
+        for block in moving.chunks(depth) {
+            let proposals: Vec<(usize, Point6, f64)> = block.iter()
+                .map(|&i| (i, self._propose_shift(i), rng().gen()))
+                .collect();
+
+            let prefetched: Vec<Option<f64>> = if threads == 1 {
+                proposals.iter().map(|(i, new, _)| self._shift_dvi(*i, new)).collect()
+            }
+            else {
+                let chunk = proposals.len().div_ceil(threads);
+                std::thread::scope(|s| {
+                    let handles: Vec<_> = workers.iter_mut().zip(proposals.chunks(chunk)).map(|(w, block)| {
+                        s.spawn(move || block.iter().map(|(i, new, _)| w._shift_dvi(*i, new)).collect::<Vec<_>>())
+                    }).collect();
+                    handles.into_iter().flat_map(|h| h.join().expect("sweep worker panicked")).collect()
+                })
+            };
+
+            // (from, to) of the moves accepted in this block
+            let mut moved: Vec<(Point6, Point6)> = Vec::new();
+            for ((i, new, u), dv) in proposals.into_iter().zip(prefetched) {
+                let stale = moved.iter().any(|(from, to)| {
+                    [&self.positions[i], &new].iter().any(|p| within(p, from, range) || within(p, to, range))
+                });
+                let dv = if stale {
+                    recomputed += 1;
+                    self._shift_dvi(i, &new)
+                }
+                else {
+                    dv
+                };
+
+                if dv.is_some_and(|dv| u <= (-beta[i]*dv).exp()) {
+                    for w in workers.iter_mut() {
+                        w.positions[i] = new.clone();
+                    }
+                    moved.push((std::mem::replace(&mut self.positions[i], new.clone()), new));
+                    accepted += 1;
+                }
+            }
+        }
+        tracing::trace!(accepted, recomputed, "speculative sweep");
+        accepted
+    }
+}
+
+fn within(p: &Point6, q: &Point6, r: f64) -> bool {
+    (p.x() - q.x()).powi(2) + (p.y() - q.y()).powi(2) + (p.z() - q.z()).powi(2) <= r*r
+}
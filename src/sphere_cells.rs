@@ -0,0 +1,93 @@
+//! neighbour search for shell-like configurations: atoms are bucketed into angular cells on the
+//! sphere (bands of polar angle split into azimuthal sectors of about equal area, in the spirit
+//! of HEALPix) and a search only visits the cells within the angular reach of the cutoff. Two
+//! atoms closer than r_cut, both at least r_min from the origin, are separated by an angle of at
+//! most 2 asin(r_cut/(2 r_min)), so the cells are exact for any configuration and efficient when
+//! the atoms sit on a shell of radius well above r_cut/2 (randomize_on_sphere, the anneal)
+use std::f64::consts::PI;
+
+use crate::Fuleren;
+
+/// atoms bucketed by polar angle band and azimuthal sector
+pub struct SphereCells {
+    gamma: f64,                  // largest angle between two atoms within the cutoff
+    sectors: Vec<usize>,         // sectors of every band
+    cells: Vec<Vec<Vec<usize>>>, // atoms of every band and sector
+}
+
+/// atoms from which bond_graph searches the cells instead of all pairs
+pub const CELLS_MIN_SIZE: usize = 100;
+
+impl SphereCells {
+    /// cells for neighbour searches up to r_cut; positions are taken about the origin
+    pub fn build(f: &Fuleren, r_cut: f64) -> SphereCells {
+        let r_min = f.positions.iter().map(|p| p.r()).fold(f64::INFINITY, f64::min);
+        // a cutoff reaching across the shell leaves a single cell
+        let gamma = if r_min.is_finite() && r_cut < 2.*r_min { 2.*(r_cut/(2.*r_min)).asin() } else { PI };
+
+        // bands at least gamma wide, so neighbours are in the same or an adjacent band; sectors
+        // about as wide as the band at its widest
+        let n_bands = ((PI/gamma).floor() as usize).max(1);
+        let band = PI/n_bands as f64;
+        let sectors: Vec<usize> = (0..n_bands).map(|b| {
+            let (top, bottom) = (b as f64*band, (b + 1) as f64*band);
+            let widest = if top <= PI/2. && PI/2. <= bottom { 1. } else { top.sin().max(bottom.sin()) };
+            ((2.*PI*widest/band).floor() as usize).max(1)
+        }).collect();
+
+        let mut cells: Vec<Vec<Vec<usize>>> = sectors.iter().map(|&m| vec![Vec::new(); m]).collect();
+        for (i, p) in f.positions.iter().enumerate() {
+            let b = ((p.theta()/band) as usize).min(n_bands - 1);
+            let s = ((p.phi()/(2.*PI)*sectors[b] as f64) as usize).min(sectors[b] - 1);
+            cells[b][s].push(i);
+        }
+        SphereCells { gamma, sectors, cells }
+    }
+
+    /// atoms of the cells an atom at polar angle theta and azimuth phi can have neighbours in; a
+    /// superset of the neighbours, to be filtered by distance
+    pub fn candidates(&self, theta: f64, phi: f64) -> Vec<usize> {
+        let n_bands = self.sectors.len();
+        let band = PI/n_bands as f64;
+        let b = ((theta/band) as usize).min(n_bands - 1);
+        let mut found = Vec::new();
+
+        for bb in b.saturating_sub(1)..=(b + 1).min(n_bands - 1) {
+            let m = self.sectors[bb];
+            // azimuthal reach: sin(dphi/2) <= sin(gamma/2)/sqrt(sin(theta) sin(theta')), theta'
+            // the polar angle of the neighbour, as close to a pole as this band and gamma allow
+            let (lo, hi) = ((bb as f64*band).max(theta - self.gamma), ((bb + 1) as f64*band).min(theta + self.gamma));
+            let sin_min = lo.max(0.).sin().min(hi.min(PI).sin());
+            let x = (self.gamma/2.).sin()/(theta.sin()*sin_min).sqrt();
+            if x.is_nan() || x >= 1. || m == 1 {
+                self.cells[bb].iter().for_each(|c| found.extend_from_slice(c));
+                continue
+            }
+            let dphi = 2.*x.asin();
+            let width = 2.*PI/m as f64;
+            let first = ((phi - dphi)/width).floor() as i64;
+            let last = ((phi + dphi)/width).floor() as i64;
+            if (last - first + 1) as usize >= m {
+                self.cells[bb].iter().for_each(|c| found.extend_from_slice(c));
+                continue
+            }
+            for s in first..=last {
+                found.extend_from_slice(&self.cells[bb][s.rem_euclid(m as i64) as usize]);
+            }
+        }
+        found
+    }
+}
+
+impl Fuleren {
+    /// atoms j != i closer than r_cut to atom i, from the cells built for at least r_cut
+    pub fn neighbours_in_cells(&self, cells: &SphereCells, i: usize, r_cut: f64) -> Vec<usize> {
+        let p = &self.positions[i];
+        let mut neighbours: Vec<usize> = cells.candidates(p.theta(), p.phi())
+                                              .into_iter()
+                                              .filter(|&j| j != i && self._r_ij(i, j) <= r_cut)
+                                              .collect();
+        neighbours.sort_unstable();
+        neighbours
+    }
+}
@@ -0,0 +1,49 @@
+//! virial stress of the cage: W_ab = sum_i (r_i - c)_a F_i_b over the atoms (positions about the
+//! center of mass), zero for a relaxed structure. A stretched shell (W < 0) pulls inwards, a
+//! compressed one pushes out; per atom the radial virial shows where the strain sits
+use crate::Fuleren;
+
+/// eV/A^3 in GPa
+pub const EV_A3_GPA: f64 = 160.21766;
+
+/// virial stress tensor of a structure and the scalars derived from it
+#[derive(Debug, Clone)]
+pub struct Stress {
+    /// W_ab/V [eV/A^3], V the volume of the convex hull
+    pub tensor: [[f64; 3]; 3],
+    /// virial pressure tr(W)/(3V) [eV/A^3]
+    pub pressure: f64,
+    /// effective surface tension of the shell -tr(W)/(2A) [eV/A^2], A the hull area: minus the
+    /// energy change per unit area of a uniform dilation, positive for a shell under tension
+    pub tension: f64,
+    /// radial virial (r_i - c).F_i of every atom [eV]
+    pub atom_virial: Vec<f64>,
+}
+
+impl Fuleren {
+    /// virial stress from the forces (central differences) on the atoms
+    pub fn stress(&self) -> Stress {
+        let forces = self.forces();
+        let c = self.center_of_mass();
+        let hull = self.hull();
+
+        let mut w = [[0.; 3]; 3];
+        let mut atom_virial = vec![0.; self.size];
+        for (i, p) in self.positions.iter().enumerate() {
+            let d = [p.x() - c[0], p.y() - c[1], p.z() - c[2]];
+            for a in 0..3 {
+                for b in 0..3 {
+                    w[a][b] += d[a]*forces[[i, b]];
+                }
+                atom_virial[i] += d[a]*forces[[i, a]];
+            }
+        }
+        let trace = w[0][0] + w[1][1] + w[2][2];
+
+        // flat or smaller than a tetrahedron: no enclosed volume
+        let volume = if hull.volume > 0. { hull.volume } else { f64::NAN };
+        let area = if hull.area > 0. { hull.area } else { f64::NAN };
+        Stress { tensor: w.map(|row| row.map(|x| x/volume)), pressure: trace/(3.*volume),
+                 tension: -trace/(2.*area), atom_virial }
+    }
+}
@@ -0,0 +1,107 @@
+//! point symmetry of a structure: the rotations and reflections about the center of mass that map
+//! every atom onto an atom of the same mass. An operation is fixed by the images of two reference
+//! atoms, so only the pairs of atoms at the same distances from the center and from each other
+//! are tried
+use crate::Fuleren;
+
+/// orthogonal matrix of a point operation and the permutation of the atoms it induces, atom i
+/// goes to permutation[i]
+#[derive(Debug, Clone)]
+pub struct SymmetryOperation {
+    pub matrix: [[f64; 3]; 3],
+    pub permutation: Vec<usize>,
+}
+
+impl SymmetryOperation {
+    /// character on the vectors (x, y, z): the trace
+    pub fn trace(&self) -> f64 {
+        self.matrix[0][0] + self.matrix[1][1] + self.matrix[2][2]
+    }
+
+    /// character on the symmetric second rank tensors (xx, xy, ..., the polarizability),
+    /// (tr(R)^2 + tr(R^2))/2
+    pub fn tensor_character(&self) -> f64 {
+        let m = &self.matrix;
+        let trace_squared = (0..3).map(|a| (0..3).map(|b| m[a][b]*m[b][a]).sum::<f64>()).sum::<f64>();
+        0.5*(self.trace().powi(2) + trace_squared)
+    }
+
+    pub fn apply(&self, v: [f64; 3]) -> [f64; 3] {
+        self.matrix.map(|row| row[0]*v[0] + row[1]*v[1] + row[2]*v[2])
+    }
+}
+
+impl Fuleren {
+    /// all point operations mapping the structure onto itself, atoms matched within tol [A] and
+    /// only onto atoms of the same mass (masses per atom, isotopes lower the symmetry); the
+    /// identity alone for a linear or single atom structure
+    pub fn symmetry_operations(&self, masses: &[f64], tol: f64) -> Vec<SymmetryOperation> {
+        let m_total = masses.iter().sum::<f64>();
+        let c = [0, 1, 2].map(|k| self.positions.iter().zip(masses).map(|(p, m)| m*p.xyz()[k]).sum::<f64>()/m_total);
+        let x: Vec<[f64; 3]> = self.positions.iter().map(|p| sub(p.xyz(), c)).collect();
+        let identity = SymmetryOperation { matrix: [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+                                           permutation: (0..self.size).collect() };
+
+        // reference atoms: the furthest from the center, and the one spanning the largest
+        // parallelogram with it
+        let Some(a) = (0..self.size).max_by(|&i, &j| norm(x[i]).total_cmp(&norm(x[j]))) else { return vec![identity] };
+        let Some(b) = (0..self.size).max_by(|&i, &j| norm(cross(x[a], x[i])).total_cmp(&norm(cross(x[a], x[j]))))
+        else { return vec![identity] };
+        if norm(cross(x[a], x[b])) < tol*norm(x[a]) {
+            return vec![identity]
+        }
+        let frame = |a: [f64; 3], b: [f64; 3], improper: bool| -> [[f64; 3]; 3] {
+            let e1 = scale(a, 1./norm(a));
+            let e2 = sub(b, scale(e1, dot(b, e1)));
+            let e2 = scale(e2, 1./norm(e2));
+            let e3 = cross(e1, e2);
+            [e1, e2, if improper { scale(e3, -1.) } else { e3 }]
+        };
+        let reference = frame(x[a], x[b], false);
+
+        let mut operations = Vec::new();
+        let same = |i: usize, j: usize| (masses[i] - masses[j]).abs() < 1e-6;
+        for a2 in (0..self.size).filter(|&i| same(i, a) && (norm(x[i]) - norm(x[a])).abs() < tol) {
+            for b2 in (0..self.size).filter(|&j| j != a2 && same(j, b) && (norm(x[j]) - norm(x[b])).abs() < tol
+                                                 && (norm(sub(x[j], x[a2])) - norm(sub(x[b], x[a]))).abs() < tol) {
+                for improper in [false, true] {
+                    // R = F' F^T, the frames as rows
+                    let image = frame(x[a2], x[b2], improper);
+                    let matrix = [0, 1, 2].map(|r| [0, 1, 2].map(|s| (0..3).map(|k| image[k][r]*reference[k][s]).sum()));
+                    let mut operation = SymmetryOperation { matrix, permutation: Vec::with_capacity(self.size) };
+                    for i in 0..self.size {
+                        let y = operation.apply(x[i]);
+                        match (0..self.size).find(|&j| same(i, j) && norm(sub(x[j], y)) < tol) {
+                            Some(j) => operation.permutation.push(j),
+                            None => break,
+                        }
+                    }
+                    if operation.permutation.len() == self.size {
+                        operations.push(operation);
+                    }
+                }
+            }
+        }
+        operations
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f64; 3], s: f64) -> [f64; 3] {
+    a.map(|x| s*x)
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1]*b[2] - a[2]*b[1], a[2]*b[0] - a[0]*b[2], a[0]*b[1] - a[1]*b[0]]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
@@ -0,0 +1,138 @@
+//! tabulated replacements of the analytic pieces of the Brenner potential, read from a file so
+//! a parametrization can be changed without recompiling. Sections start with a header line
+//! `spline <name>` (knots "x y", natural cubic spline through them) or `poly <name>`
+//! (intervals "x_lo x_hi c0 c1 ... cn", y = sum c_k x^k, the layout of the gC splines of
+//! LAMMPS' CH.airebo); names are V_R and V_A (on r [A]) and g (on cos theta). A tabulated g
+//! replaces the analytic one including the cos > 0 penalty. '#' starts a comment
+use std::path::Path;
+
+/// y(x) given as a table
+#[derive(Debug, Clone)]
+pub enum Table {
+    Spline(CubicSpline),
+    Poly(Vec<(f64, f64, Vec<f64>)>),
+}
+
+impl Table {
+    /// value at x; beyond the table splines continue linearly, polynomials with their end interval
+    pub fn eval(&self, x: f64) -> f64 {
+        match self {
+            Table::Spline(s) => s.eval(x),
+            Table::Poly(intervals) => {
+                let k = intervals.partition_point(|(_, hi, _)| *hi < x).min(intervals.len() - 1);
+                intervals[k].2.iter().rev().fold(0., |y, c| y*x + c)
+            }
+        }
+    }
+}
+
+/// natural cubic spline
+#[derive(Debug, Clone)]
+pub struct CubicSpline {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    m: Vec<f64>, // second derivatives at the knots
+}
+
+impl CubicSpline {
+    /// needs at least two knots with increasing x
+    pub fn new(x: Vec<f64>, y: Vec<f64>) -> Result<CubicSpline, String> {
+        let n = x.len();
+        if n < 2 || y.len() != n {
+            return Err("a spline needs at least two knots".to_string());
+        }
+        if x.windows(2).any(|w| w[1] <= w[0]) {
+            return Err("spline knots must be increasing".to_string());
+        }
+
+        // tridiagonal system for the inner second derivatives, m_0 = m_{n-1} = 0 (Thomas algorithm)
+        let mut m = vec![0.; n];
+        let mut c = vec![0.; n];
+        let mut d = vec![0.; n];
+        for i in 1..n - 1 {
+            let (h0, h1) = (x[i] - x[i - 1], x[i + 1] - x[i]);
+            let rhs = 6.*((y[i + 1] - y[i])/h1 - (y[i] - y[i - 1])/h0);
+            let diag = 2.*(h0 + h1) - h0*c[i - 1];
+            c[i] = h1/diag;
+            d[i] = (rhs - h0*d[i - 1])/diag;
+        }
+        for i in (1..n - 1).rev() {
+            m[i] = d[i] - c[i]*m[i + 1];
+        }
+        Ok(CubicSpline { x, y, m })
+    }
+
+    pub fn eval(&self, x: f64) -> f64 {
+        let n = self.x.len();
+        let k = self.x.partition_point(|&xk| xk <= x).clamp(1, n - 1) - 1;
+        let h = self.x[k + 1] - self.x[k];
+        let slope = (self.y[k + 1] - self.y[k])/h;
+
+        if x < self.x[0] {
+            return self.y[0] + (x - self.x[0])*(slope - h*(2.*self.m[0] + self.m[1])/6.);
+        }
+        if x > self.x[n - 1] {
+            return self.y[n - 1] + (x - self.x[n - 1])*(slope + h*(self.m[k] + 2.*self.m[k + 1])/6.);
+        }
+        let (a, b) = ((self.x[k + 1] - x)/h, (x - self.x[k])/h);
+        a*self.y[k] + b*self.y[k + 1] + ((a.powi(3) - a)*self.m[k] + (b.powi(3) - b)*self.m[k + 1])*h*h/6.
+    }
+}
+
+/// the tables of a file; missing pieces keep their analytic form
+#[derive(Debug, Clone, Default)]
+pub struct Tables {
+    pub path: String,
+    pub v_r: Option<Table>,
+    pub v_a: Option<Table>,
+    pub g: Option<Table>,
+}
+
+impl Tables {
+    pub fn from_file(path: &str) -> Result<Tables, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let absolute = Path::new(path).canonicalize().map_or(path.to_string(), |p| p.to_string_lossy().into_owned());
+        let mut tables = Tables { path: absolute, ..Default::default() };
+
+        // (kind, name, line of the header, rows)
+        let mut sections: Vec<(String, String, usize, Vec<Vec<f64>>)> = Vec::new();
+        for (n, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() { continue }
+            let err = || format!("{}:{}: cannot parse \"{}\"", path, n + 1, line);
+
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [kind @ ("spline" | "poly"), name] => sections.push((kind.to_string(), name.to_string(), n, Vec::new())),
+                _ => {
+                    let row = line.split_whitespace().map(|x| x.parse::<f64>().map_err(|_| err())).collect::<Result<_, _>>()?;
+                    sections.last_mut().ok_or_else(|| format!("{}:{}: numbers before the first section", path, n + 1))?
+                            .3.push(row);
+                }
+            }
+        }
+
+        for (kind, name, n, rows) in sections {
+            let err = |what: String| format!("{}:{}: {} {}: {}", path, n + 1, kind, name, what);
+            let table = if kind == "spline" {
+                if rows.iter().any(|r| r.len() != 2) {
+                    return Err(err("expected \"x y\" rows".to_string()));
+                }
+                Table::Spline(CubicSpline::new(rows.iter().map(|r| r[0]).collect(), rows.iter().map(|r| r[1]).collect())
+                                         .map_err(err)?)
+            }
+            else {
+                if rows.is_empty() || rows.iter().any(|r| r.len() < 3) {
+                    return Err(err("expected \"x_lo x_hi c0 ...\" rows".to_string()));
+                }
+                Table::Poly(rows.into_iter().map(|r| (r[0], r[1], r[2..].to_vec())).collect())
+            };
+            match name.as_str() {
+                "V_R" => tables.v_r = Some(table),
+                "V_A" => tables.v_a = Some(table),
+                "g" => tables.g = Some(table),
+                _ => return Err(err("unknown table, expected V_R, V_A or g".to_string())),
+            }
+        }
+        Ok(tables)
+    }
+}
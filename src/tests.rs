@@ -0,0 +1,412 @@
+// tests of the structure and its moves that need the private parts of Fuleren
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::f64::consts::PI;
+
+use proptest::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::anneal::Schedule;
+use crate::bond_cache::BondCache;
+use crate::potential::{Coulomb, ShellRestraint, Wall};
+use crate::umbrella::{wham, UmbrellaSampling};
+use crate::{seed_rng, Fuleren, Point6, Potential};
+
+// n atoms on a sphere of radius r, drawn with seed
+fn random_cage(n: usize, r: f64, seed: u64) -> Fuleren {
+    seed_rng(seed);
+    let mut f = Fuleren::new(n);
+    f.randomize_on_sphere(r);
+    f.energy_calc();
+    f
+}
+
+fn same_positions(a: &Fuleren, b: &Fuleren) -> bool {
+    a.positions.iter().zip(b.positions.iter()).all(|(p, q)| {
+        [p.x(), p.y(), p.z(), p.r(), p.phi(), p.theta()].iter()
+            .zip([q.x(), q.y(), q.z(), q.r(), q.phi(), q.theta()])
+            .all(|(u, v)| u.to_bits() == v.to_bits())
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn angles_in_range_after_moves(n in 4usize..40, seed in any::<u64>(), beta in 0.1f64..100.) {
+        let mut f = random_cage(n, 3., seed);
+        let mut cache = BondCache::new(f.size);
+        for i in 0..n {
+            f.random_atom_shift(i, beta, &mut cache);
+            f.smart_atom_shift(i, beta, 1e-3);
+        }
+        for p in f.positions.iter() {
+            prop_assert!((0. ..=2.*PI).contains(&p.phi()) && (0. ..=PI).contains(&p.theta()), "{}", p);
+        }
+    }
+
+    #[test]
+    fn r_ij_symmetric(n in 2usize..40, seed in any::<u64>()) {
+        let f = random_cage(n, 3., seed);
+        for i in 0..n {
+            for j in 0..n {
+                prop_assert_eq!(f._r_ij(i, j).to_bits(), f._r_ij(j, i).to_bits());
+            }
+        }
+    }
+
+    // at a large beta nearly every move raising the energy is rejected, each of them has to leave
+    // the structure as it was, bit for bit
+    #[test]
+    fn rejected_moves_restore(n in 4usize..40, seed in any::<u64>()) {
+        let mut f = random_cage(n, 3., seed);
+        let mut cache = BondCache::new(f.size);
+        for i in 0..n {
+            let before = f.clone();
+            if !f.random_atom_shift(i, 1e6, &mut cache) {
+                prop_assert!(same_positions(&f, &before), "random move of {} not undone", i);
+            }
+            let before = f.clone();
+            if !f.smart_atom_shift(i, 1e6, 1e-3) {
+                prop_assert!(same_positions(&f, &before), "smart move of {} not undone", i);
+            }
+        }
+    }
+}
+
+// the f32 kernel against f64 on the reference cages
+#[cfg(feature = "f32")]
+#[test]
+fn f32_energy_error() {
+    for name in ["C20", "C24", "C36", "C60", "C70"] {
+        let f = Fuleren::reference(name).unwrap();
+        let e64 = 0.5 * (0..f.size).map(|i| f._vi_t::<f64>(i)).sum::<f64>();
+        let e32 = 0.5 * (0..f.size).map(|i| f._vi_t::<f32>(i) as f64).sum::<f64>();
+        let error = ((e32 - e64)/e64).abs();
+        println!("{}: E64 = {:.8}, E32 = {:.8}, relative error {:.2e}", name, e64, e32, error);
+        assert!(error < 1e-5, "{}: relative error {:.2e} of the f32 kernel", name, error);
+    }
+}
+
+#[test]
+fn rmsd_reference_size() {
+    let rmsd = crate::cv::from_name("rmsd:C60").unwrap();
+    assert_eq!(rmsd.check_size(60), Ok(()));
+    assert_eq!(rmsd.check_size(70), Err("rmsd:C60: the reference has 60 atoms, the run 70".to_string()));
+}
+
+#[test]
+fn charges_of_another_size() {
+    let mut potential = Potential { coulomb: Some(Coulomb { charges: vec![0.1; 59], ..Coulomb::default() }),
+                                    ..Potential::default() };
+    let mut f = Fuleren::reference("C60").unwrap();
+    assert_eq!(f.set_potential(potential.clone()), Err("59 charges for a structure of 60 atoms".to_string()));
+    assert!(f.potential.coulomb.is_none());
+
+    potential.coulomb.as_mut().unwrap().charges = (0..60).map(|i| i as f64).collect();
+    f.set_potential(potential).unwrap();
+    let half = f.subset(&(0..60).map(|i| i % 2 == 0).collect::<Vec<_>>());
+    assert_eq!(half.potential.coulomb.unwrap().charges, (0..30).map(|i| 2.*i as f64).collect::<Vec<_>>());
+}
+
+// the cartesian position of (r, phi, theta) taken literally, whatever the range of the angles
+fn cartesian(r: f64, phi: f64, theta: f64) -> [f64; 3] {
+    [r*theta.sin()*phi.cos(), r*theta.sin()*phi.sin(), r*theta.cos()]
+}
+
+#[test]
+fn check_angles_extremes() {
+    let angles = [0., PI, 2.*PI, 3.*PI, -3.*PI, 7.5*PI, -PI, -0.5*PI, -2.*PI, -1e-300, -1e-12, PI + 1e-12,
+                  1.5*PI, 1e6, -1e6];
+    for &phi in &angles {
+        for &theta in &angles {
+            let (phi_n, theta_n) = crate::check_angles(phi, theta);
+            assert!((0. ..2.*PI).contains(&phi_n), "phi {} -> {}", phi, phi_n);
+            assert!((0. ..=PI).contains(&theta_n), "theta {} -> {}", theta, theta_n);
+            for r in [0., 1., 3.5] {
+                let p = Point6::from_spherical(&[r, phi, theta]);
+                assert_eq!((p.phi(), p.theta()), (phi_n, theta_n));
+                for (a, b) in p.xyz().iter().zip(cartesian(r, phi, theta)) {
+                    assert!((a - b).abs() < 1e-9, "({}, {}, {}) moved to {}", r, phi, theta, p);
+                }
+                if r == 0. {
+                    assert_eq!(p.xyz().map(f64::abs), [0.; 3]);
+                }
+            }
+        }
+    }
+}
+
+// a malformed line is an error naming the file and the line
+#[test]
+fn malformed_xyz_lines() {
+    let path = std::env::temp_dir().join(format!("lab7_malformed_{}.xyz", std::process::id()));
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "0 0 1\n1 abc 0\n").unwrap();
+    let err = Fuleren::frames_from_file(path).err().unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(err, format!("{}:2: expected \"x y z\"", path));
+
+    let err = Fuleren::frames_from_str("0 0 1\n1 0 0\n\n0 1 0 2\n", "test.xyz").err().unwrap();
+    assert_eq!(err, "test.xyz:4: expected \"x y z\"");
+    let err = Fuleren::frames_from_str("0 0 1\n1 0\n", "test.xyz").err().unwrap();
+    assert_eq!(err, "test.xyz:2: expected \"x y z\"");
+}
+
+#[test]
+fn missing_files() {
+    let path = "/nonexistent/lab7/C60.xyz";
+    assert!(Fuleren::frames_from_file(path).err().unwrap().starts_with("cannot read /nonexistent/lab7/C60.xyz: "));
+    let path = "/nonexistent/lab7/schedule.cfg";
+    assert!(Schedule::from_config(path).err().unwrap().starts_with("cannot read /nonexistent/lab7/schedule.cfg: "));
+    assert!(Schedule::from_file(path).err().unwrap().starts_with("cannot read /nonexistent/lab7/schedule.cfg: "));
+    let path = "/nonexistent/lab7/potential.cfg";
+    assert!(Potential::from_config(path).err().unwrap().starts_with("cannot read /nonexistent/lab7/potential.cfg: "));
+}
+
+#[test]
+fn bad_config_keys() {
+    let err = Schedule::from_config_str("beta_min 1\nbeta_maks 10\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg:2: unknown key beta_maks");
+    let err = Schedule::from_config_str("# comment\nbeta_min one\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg:2: cannot parse \"beta_min one\"");
+    let err = Schedule::from_config_str("beta_min\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg:1: cannot parse \"beta_min\"");
+    let err = Schedule::from_config_str("window 0\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg:1: window must be at least 1");
+    let err = Schedule::from_config_str("watchdog_kicks 2\nwatchdog_window 0\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg:2: watchdog_window must be at least 1");
+    let err = Potential::from_config_str("R1 1.7\nlj yes\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg:2: cannot parse \"lj yes\"");
+}
+
+#[test]
+fn groups_and_moves() {
+    let err = Schedule::from_config_str("moves smart\ngroup inf index 0:9\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg: an infinite beta factor needs uniform, colored or speculative moves, \
+                     smart moves step along beta*F");
+    assert!(Schedule::from_config_str("moves smart\ndefect_beta inf\n", "test.cfg").is_err());
+    let err = Schedule::from_config_str("group 0.5 index 0:9\nmoves hmc\n", "test.cfg").err().unwrap();
+    assert_eq!(err, "test.cfg: defect_beta and temperature groups need atom moves, hmc moves all atoms at one beta");
+    assert!(Schedule::from_config_str("moves hmc\ndefect_beta 0.5\n", "test.cfg").is_err());
+
+    let schedule = Schedule::from_config_str("moves speculative\ngroup inf index 0:9\n", "test.cfg").unwrap();
+    assert_eq!(schedule.groups[0].beta_factor, f64::INFINITY);
+    assert!(Schedule::from_config_str("moves smart\ngroup 2 index 0:9\n", "test.cfg").is_ok());
+}
+
+#[test]
+fn unknown_references() {
+    assert!(Fuleren::reference("C61").is_none());
+    let err = crate::cv::from_name("rmsd:C61").err().unwrap();
+    assert!(err.starts_with("cannot read C61: "), "{}", err);
+    let err = crate::cv::from_name("radiuss").err().unwrap();
+    assert!(err.starts_with("unknown collective variable radiuss, "), "{}", err);
+}
+
+// two atoms: their bond order is 1, so the change of V_i of a single atom move is the exact
+// energy change apart from the one-body terms
+fn dimer() -> Fuleren {
+    let mut f = Fuleren::new(2);
+    f.positions[0] = Point6::from_cartesian(&[0.7, 0.1, 0.9]);
+    f.positions[1] = Point6::from_cartesian(&[-0.6, 0.2, 1.1]);
+    f.energy_calc();
+    f
+}
+
+// the energy change a single atom move is accepted on against energy_calc before and after
+fn check_move_energy(f: &mut Fuleren, seed: u64) {
+    seed_rng(seed);
+    f.steps.w_r = 0.05;
+    for k in 0..200 {
+        let i = k % f.size;
+        let new = f._propose_shift(i);
+        let Some(de) = f._shift_dvi(i, &new) else { continue };
+        let e_old = f.energy_calc();
+        f.positions[i] = new;
+        let e_new = f.energy_calc();
+        #[cfg_attr(not(feature = "f32"), allow(clippy::unnecessary_cast))] // Real is f32 with the f32 feature
+        let tol = 1e3*crate::Real::EPSILON as f64*(1. + e_old.abs());
+        assert!((de - (e_new - e_old)).abs() < tol, "move {}: dE {} instead of {}", k, de, e_new - e_old);
+    }
+}
+
+#[test]
+fn shell_restraint_move_energy() {
+    let mut f = dimer();
+    f.potential.shell = Some(ShellRestraint { radius: 1.2, k: 3. });
+    check_move_energy(&mut f, 11);
+}
+
+#[test]
+fn wall_move_energy() {
+    let mut f = dimer();
+    f.potential.wall = Some(Wall { k: 3., radius: 1.1, scale: None });
+    check_move_energy(&mut f, 13);
+}
+
+// WHAM on samples drawn exactly from the biased distributions exp(-beta (F(s) + w_i(s))) of a
+// double well F(s) = 0.2 (s^2 - 1)^2 gives back F up to a constant
+#[test]
+fn wham_recovers_double_well() {
+    let free = |s: f64| 0.2*(s*s - 1.).powi(2);
+    let beta = 10.;
+    let windows = UmbrellaSampling::evenly_spaced(-1.5, 1.5, 11, 5.);
+    let grid: Vec<f64> = (0..4000).map(|k| -2.5 + 5.*(k as f64 + 0.5)/4000.).collect();
+    let mut rng = StdRng::seed_from_u64(5);
+    let samples: Vec<Vec<f64>> = windows.iter().map(|w| {
+        // inverse of the cumulative distribution on the grid
+        let mut cdf: Vec<f64> = grid.iter().map(|&s| (-beta*(free(s) + w.bias(s))).exp()).collect();
+        for k in 1..cdf.len() {
+            cdf[k] += cdf[k - 1];
+        }
+        let total = cdf[cdf.len() - 1];
+        (0..20_000).map(|_| {
+            let u = rng.gen::<f64>()*total;
+            grid[cdf.partition_point(|&c| c < u)]
+        }).collect()
+    }).collect();
+
+    let profile = wham(&windows, &samples, beta, 60, 1e-7, 10_000);
+    assert!(profile.len() > 50, "{} bins", profile.len());
+    let inside: Vec<_> = profile.iter().filter(|(s, _, _)| s.abs() <= 1.5).collect();
+    let offset = inside.iter().map(|(s, f, _)| f - free(*s)).sum::<f64>()/inside.len() as f64;
+    for (s, f, _) in inside {
+        assert!((f - offset - free(*s)).abs() < 0.01, "F({}) = {} instead of {}", s, f - offset, free(*s));
+    }
+}
+
+// with S = 1 both Brenner terms are infinite and their difference NaN, so are the frequencies
+// and the free energy; such a structure is ranked last
+#[test]
+fn free_energy_ranking_with_nan() {
+    let mut broken = Fuleren::reference("C20").unwrap();
+    broken.potential.brenner.s = 1.;
+    let mut isomers = [broken, Fuleren::reference("C20").unwrap()];
+    let ranking = crate::vibrations::rank_by_free_energy(&mut isomers, 300.);
+    assert_eq!(ranking[0].0, 1);
+    assert!(ranking[0].1.is_finite());
+    assert_eq!(ranking[1].0, 0);
+    assert!(ranking[1].1.is_nan());
+}
+
+// AR(1) series x_t+1 = a x_t + noise: rho(t) = a^t, tau = (1 + a)/(2 (1 - a)), the variance of
+// the series 1/(1 - a^2) for unit noise
+#[test]
+fn autocorrelation_of_ar1() {
+    use crate::autocorrelation::{autocorrelation, diagnose, integrated_time};
+
+    let a: f64 = 0.9;
+    let mut rng = StdRng::seed_from_u64(17);
+    let mut x = 0.;
+    let series: Vec<f64> = (0..50_000).map(|_| {
+        x = a*x + crate::gauss(&mut rng);
+        x
+    }).collect();
+
+    let rho = autocorrelation(&series);
+    for (t, r) in rho.iter().enumerate().take(20) {
+        assert!((r - a.powi(t as i32)).abs() < 0.05, "rho({}) = {} instead of {}", t, r, a.powi(t as i32));
+    }
+    let tau = (1. + a)/(2.*(1. - a));
+    assert!((integrated_time(&series) - tau).abs() < 0.1*tau, "tau {} instead of {}", integrated_time(&series), tau);
+
+    let d = &diagnose(&[crate::reweighting::Window { beta: 1., energies: series.clone() }])[0];
+    let ess = series.len() as f64/(2.*tau);
+    assert!((d.ess - ess).abs() < 0.1*ess, "ESS {} instead of {}", d.ess, ess);
+    let error = (1./(1. - a*a)/ess).sqrt();
+    assert!((d.error - error).abs() < 0.1*error, "error {} instead of {}", d.error, error);
+
+    assert!(autocorrelation(&[1.; 10]).is_empty());
+    assert_eq!(integrated_time(&[f64::NAN; 10]), 0.5);
+}
+
+// the cell search finds exactly the pairs of the O(N^2) search, on shells of some thickness,
+// with atoms at the poles and for cutoffs up to beyond the diameter
+#[test]
+fn sphere_cells_match_brute_force() {
+    use crate::sphere_cells::SphereCells;
+
+    for (seed, &(n, r)) in [(20, 1.5), (150, 3.), (400, 5.), (1000, 9.)].iter().enumerate() {
+        let mut f = random_cage(n, r, seed as u64);
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        for p in f.positions.iter_mut() {
+            p.scale(1. + 0.2*(rng.gen::<f64>() - 0.5));
+        }
+        f.positions[0] = Point6::from_spherical(&[r, 0., 0.]);
+        f.positions[1] = Point6::from_spherical(&[r, 0., PI]);
+        for r_cut in [0.5, 1.7, 2.*r, 2.5*r] {
+            let cells = SphereCells::build(&f, r_cut);
+            for i in 0..n {
+                let brute: Vec<usize> = (0..n).filter(|&j| j != i && f._r_ij(i, j) <= r_cut).collect();
+                assert_eq!(f.neighbours_in_cells(&cells, i, r_cut), brute, "N = {}, r_cut = {}, atom {}", n, r_cut, i);
+            }
+        }
+    }
+}
+
+// uniform points on a sphere against the analytic pcf: 1 up to the diameter, 0 beyond; the
+// first bins hold few pairs weighted by 1/r and the last one below the diameter is partly
+// covered, so they are left out of the bin by bin comparison
+#[test]
+fn pcf_of_uniform_sphere() {
+    seed_rng(7);
+    let pcf = Fuleren::ideal_sphere(4000, 1.).pcf_about(1.);
+    let ideal = Fuleren::ideal_pcf();
+    let inside: Vec<usize> = (0..ideal.len()).filter(|&m| ideal[m] == 1.).collect();
+    for &m in &inside[4..inside.len() - 1] {
+        assert!((pcf[m] - 1.).abs() < 0.05, "bin {}: {} instead of 1", m, pcf[m]);
+    }
+    let mean = inside[4..].iter().map(|&m| pcf[m]).sum::<f64>()/(inside.len() - 4) as f64;
+    assert!((mean - 1.).abs() < 0.005, "mean {} instead of 1", mean);
+    for m in (0..ideal.len()).filter(|&m| ideal[m] == 0.) {
+        assert_eq!(pcf[m], 0., "bin {} beyond the diameter", m);
+    }
+}
+
+// counts the allocations of the current thread, the tests run in parallel
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// after the scratch buffers of the thread have grown, evaluating a move allocates nothing
+#[test]
+fn shift_dvi_does_not_allocate() {
+    seed_rng(3);
+    let mut f = Fuleren::reference("C60").unwrap();
+    let proposals: Vec<(usize, Point6)> = (0..200).map(|k| (k % f.size, f._propose_shift(k % f.size))).collect();
+    for (i, new) in &proposals {
+        f._shift_dvi(*i, new);
+    }
+
+    let before = ALLOCATIONS.with(|a| a.get());
+    drop(std::hint::black_box(Box::new(0u64)));
+    assert_eq!(ALLOCATIONS.with(|a| a.get()) - before, 1, "the allocator does not count");
+
+    let before = ALLOCATIONS.with(|a| a.get());
+    for (i, new) in &proposals {
+        f._shift_dvi(*i, new);
+    }
+    assert_eq!(ALLOCATIONS.with(|a| a.get()) - before, 0, "allocations in {} calls of _shift_dvi", proposals.len());
+}
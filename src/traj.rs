@@ -0,0 +1,150 @@
+//! trajectory editing for the traj subcommand: frames of one or more trajectories sliced
+//! (start:stop:step), concatenated and written in the format of the output extension, so long
+//! runs are thinned and converted without external scripts. The inputs are read through the
+//! memory mapped Trajectory, only the frames taken are parsed
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::potential::Potential;
+use crate::selection::Selection;
+use crate::trajectory::Trajectory;
+use crate::utilities::get_file_buffer;
+use crate::Fuleren;
+
+/// frames start:stop:step as a python slice: stop excluded, negative indices count from the end,
+/// empty parts take the whole trajectory; a single index k is the frame k alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slice {
+    pub start: Option<i64>,
+    pub stop: Option<i64>,
+    pub step: usize,
+}
+
+impl Slice {
+    /// indices of the frames taken from a trajectory of len frames
+    pub fn indices(&self, len: usize) -> impl Iterator<Item = usize> {
+        let resolve = |k: i64| if k < 0 { (len as i64 + k).max(0) as usize } else { (k as usize).min(len) };
+        let start = self.start.map_or(0, resolve);
+        let stop = self.stop.map_or(len, resolve);
+        (start..stop.max(start)).step_by(self.step)
+    }
+}
+
+/// start:stop:step, start:stop, ::step, or a single frame index
+pub fn parse_slice(s: &str) -> Result<Slice, String> {
+    let err = || format!("cannot parse frames \"{}\", expected start:stop:step", s);
+    let index = |part: &str| match part.trim() {
+        "" => Ok(None),
+        k => k.parse::<i64>().map(Some).map_err(|_| err()),
+    };
+    match s.split(':').collect::<Vec<_>>()[..] {
+        [k] => {
+            let k = index(k)?.ok_or_else(err)?;
+            Ok(Slice { start: Some(k), stop: if k == -1 { None } else { Some(k + 1) }, step: 1 })
+        }
+        [start, stop] => Ok(Slice { start: index(start)?, stop: index(stop)?, step: 1 }),
+        [start, stop, step] => {
+            let step = match step.trim() {
+                "" => 1,
+                step => step.parse::<usize>().ok().filter(|&k| k > 0).ok_or_else(err)?,
+            };
+            Ok(Slice { start: index(start)?, stop: index(stop)?, step })
+        }
+        _ => Err(err()),
+    }
+}
+
+/// multi-frame output by extension: .xyz (plain XYZ), .extxyz (the save_extxyz format with
+/// per-atom energies), .dat or .txt ("x y z" blocks separated by blank lines), anything else
+/// through chemfiles
+pub enum FrameSink {
+    Xyz(BufWriter<File>),
+    ExtXyz(BufWriter<File>),
+    Blocks(BufWriter<File>),
+    #[cfg(feature = "chemfiles")]
+    Chemfiles(crate::chemfiles_io::ChemfilesWriter),
+}
+
+impl FrameSink {
+    pub fn create(path: &str) -> Result<FrameSink, String> {
+        let err = |e: io::Error| format!("cannot write {}: {}", path, e);
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("xyz") => Ok(FrameSink::Xyz(get_file_buffer(path).map_err(err)?)),
+            Some("extxyz") => Ok(FrameSink::ExtXyz(get_file_buffer(path).map_err(err)?)),
+            Some("dat") | Some("txt") => Ok(FrameSink::Blocks(get_file_buffer(path).map_err(err)?)),
+            #[cfg(feature = "chemfiles")]
+            _ => Ok(FrameSink::Chemfiles(crate::chemfiles_io::ChemfilesWriter::create(path)?)),
+            #[cfg(not(feature = "chemfiles"))]
+            _ => Err(format!("{}: unknown trajectory format, expected .xyz, .extxyz, .dat or .txt (others need chemfiles)", path)),
+        }
+    }
+
+    /// appends a frame, `info` ("key=value ...") in the comment line of the XYZ formats; the
+    /// extended XYZ output expects E to be up to date
+    pub fn write(&mut self, f: &Fuleren, info: &str) -> Result<(), String> {
+        let result = match self {
+            FrameSink::Xyz(out) => write_xyz(out, f, info),
+            FrameSink::ExtXyz(out) => f.write_extxyz(out, info),
+            FrameSink::Blocks(out) => write_block(out, f, info),
+            #[cfg(feature = "chemfiles")]
+            FrameSink::Chemfiles(writer) => return writer.write(f),
+        };
+        result.map_err(|e| format!("cannot write frame: {}", e))
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        match self {
+            FrameSink::Xyz(mut out) | FrameSink::ExtXyz(mut out) | FrameSink::Blocks(mut out) => {
+                out.flush().map_err(|e| format!("cannot write frame: {}", e))
+            }
+            #[cfg(feature = "chemfiles")]
+            FrameSink::Chemfiles(_) => Ok(()),
+        }
+    }
+}
+
+fn write_xyz<W: Write>(out: &mut W, f: &Fuleren, info: &str) -> io::Result<()> {
+    writeln!(out, "{}", f.size())?;
+    writeln!(out, "{}", info)?;
+    for (p, element) in f.positions.iter().zip(f.species()) {
+        writeln!(out, "{:<2} {:>14.8} {:>14.8} {:>14.8}", element.symbol(), p.x(), p.y(), p.z())?;
+    }
+    Ok(())
+}
+
+fn write_block<W: Write>(out: &mut W, f: &Fuleren, info: &str) -> io::Result<()> {
+    writeln!(out, "# {}", info)?;
+    for p in f.positions.iter() {
+        writeln!(out, "{:<10.5}\t{:<10.5}\t{:<10.5}", p.x(), p.y(), p.z())?;
+    }
+    writeln!(out)
+}
+
+/// the frames of `slice` of every input in turn, reduced to the atoms of `selection`, written to
+/// `out`, with the given potential (energies of the extended XYZ output and the energy of a
+/// selection). Returns the number of frames written
+pub fn edit(inputs: &[String], slice: Slice, selection: Option<&Selection>, potential: &Potential,
+            out: &str) -> Result<usize, String> {
+    let energies = out.to_ascii_lowercase().ends_with(".extxyz");
+    let mut sink = FrameSink::create(out)?;
+    let mut written = 0;
+    for input in inputs {
+        let trajectory = Trajectory::open(input)?;
+        for k in slice.indices(trajectory.len()) {
+            let mut f = trajectory.frame(k)?;
+            f.set_potential(potential.clone())?;
+            if let Some(selection) = selection {
+                f = f.subset(&f.select(selection));
+            }
+            if energies {
+                f.energy_calc();
+            }
+            sink.write(&f, &format!("source=\"{}\" frame={}", input, k))?;
+            written += 1;
+        }
+    }
+    sink.finish()?;
+    Ok(written)
+}
@@ -0,0 +1,119 @@
+//! random access to the frames of large trajectory files: the file is memory mapped, only the
+//! byte ranges of the frames are indexed and a frame is parsed when it is asked for, so
+//! multi-gigabyte trajectories are analyzed without being loaded
+use std::fs::File;
+use std::ops::Range;
+
+use memmap2::Mmap;
+
+use crate::Fuleren;
+
+/// frames of a trajectory in any format read by frames_from_file
+pub struct Trajectory {
+    path: String,
+    source: Source,
+}
+
+enum Source {
+    // XYZ, extended XYZ and "x y z" frames, with the byte range of every frame
+    Mapped(Mmap, Vec<Range<usize>>),
+    // formats of other libraries, read at once
+    #[cfg_attr(not(feature = "chemfiles"), allow(dead_code))]
+    Loaded(Vec<Fuleren>),
+}
+
+impl Trajectory {
+    /// maps the file and indexes its frames in one pass
+    pub fn open(path: &str) -> Result<Trajectory, String> {
+        #[cfg(feature = "chemfiles")]
+        if crate::chemfiles_io::uses_chemfiles(path) {
+            return Ok(Trajectory { path: path.to_string(), source: Source::Loaded(crate::chemfiles_io::read_frames(path)?) });
+        }
+
+        let file = File::open(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        // SAFETY: the map is only read; a trajectory truncated by another process while it is
+        // analyzed is not supported
+        let map = unsafe { Mmap::map(&file) }.map_err(|e| format!("cannot map {}: {}", path, e))?;
+        let frames = index_frames(&map, path)?;
+        Ok(Trajectory { path: path.to_string(), source: Source::Mapped(map, frames) })
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.source {
+            Source::Mapped(_, frames) => frames.len(),
+            Source::Loaded(frames) => frames.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// frame k, parsed from the mapped file (line numbers of errors count from the frame start)
+    pub fn frame(&self, k: usize) -> Result<Fuleren, String> {
+        match &self.source {
+            Source::Mapped(map, frames) => {
+                let range = frames.get(k).ok_or(format!("{}: no frame {}", self.path, k))?;
+                let text = std::str::from_utf8(&map[range.clone()])
+                               .map_err(|_| format!("{}: frame {} is not valid UTF-8", self.path, k))?;
+                Fuleren::frames_from_str(text, &format!("{} (frame {})", self.path, k))?
+                    .pop()
+                    .ok_or(format!("{}: frame {} is empty", self.path, k))
+            }
+            Source::Loaded(frames) => frames.get(k).cloned().ok_or(format!("{}: no frame {}", self.path, k)),
+        }
+    }
+
+    /// all frames in order
+    pub fn frames(&self) -> impl Iterator<Item = Result<Fuleren, String>> + '_ {
+        (0..self.len()).map(|k| self.frame(k))
+    }
+}
+
+// byte ranges of the frames: (extended) XYZ frames are the atom count line, the comment line and
+// that many atom lines; otherwise frames are runs of non-blank lines ("#" header lines included).
+// Lines are streamed, only the frame ranges are kept
+fn index_frames(bytes: &[u8], path: &str) -> Result<Vec<Range<usize>>, String> {
+    let text = |r: &Range<usize>| std::str::from_utf8(&bytes[r.clone()]).unwrap_or("").trim();
+
+    let xyz = lines(bytes).map(|r| text(&r))
+                          .find(|l| !l.is_empty() && !l.starts_with('#'))
+                          .is_some_and(|l| l.parse::<usize>().is_ok());
+
+    let mut frames = Vec::new();
+    let mut lines = lines(bytes).enumerate();
+    while let Some((n, line)) = lines.next() {
+        if text(&line).is_empty() { continue }
+
+        let mut end = line.end;
+        if xyz {
+            let size: usize = text(&line).parse().map_err(|_| format!("{}:{}: expected the number of atoms", path, n + 1))?;
+            // the comment line and the atoms
+            for _ in 0..size + 1 {
+                end = lines.next().ok_or_else(|| format!("{}:{}: fewer atoms than declared", path, n + 1))?.1.end;
+            }
+        }
+        else {
+            for (_, next) in lines.by_ref() {
+                if text(&next).is_empty() { break }
+                end = next.end;
+            }
+        }
+        frames.push(line.start..end);
+    }
+    Ok(frames)
+}
+
+// byte ranges of the lines, newlines included
+fn lines(bytes: &[u8]) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        if start >= bytes.len() {
+            return None
+        }
+        let end = bytes[start..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |k| start + k + 1);
+        let line = start..end;
+        start = end;
+        Some(line)
+    })
+}
@@ -0,0 +1,206 @@
+//! umbrella sampling along a collective variable of the cage (mean radius, asphericity, pentagon
+//! count, ...): every window adds a harmonic bias k/2 (s - s_0)^2 on s to the Brenner energy and samples s with biased Metropolis
+//! moves at fixed beta; WHAM combines the biased histograms into the free energy profile F(s)
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{mpsc, Mutex};
+
+use rand::Rng;
+use tracing::{error, info};
+
+use crate::cv::CollectiveVariable;
+use crate::observables::ObservableWriter;
+use crate::utilities::write_atomic;
+use crate::{rng, seed_rng, Fuleren, Point6};
+
+/// harmonic bias k/2 (s - center)^2 [eV]
+#[derive(Debug, Clone, Copy)]
+pub struct Window {
+    pub center: f64,
+    pub k: f64,
+}
+
+impl Window {
+    pub fn bias(&self, s: f64) -> f64 {
+        0.5*self.k*(s - self.center).powi(2)
+    }
+}
+
+/// umbrella sampling driver; windows are sampled independently on `threads` worker threads,
+/// window i with the random generator seeded by seed + i
+pub struct UmbrellaSampling {
+    pub coordinate: Box<dyn CollectiveVariable>,
+    pub windows: Vec<Window>,
+    pub beta: f64,
+    pub step: f64,            // largest cartesian displacement of an atom move [A]
+    pub equilibration: usize, // sweeps per window before sampling
+    pub sweeps: usize,        // sampling sweeps per window
+    pub sample_every: usize,  // sweeps between samples of s
+    pub threads: usize,
+    pub seed: u64,
+}
+
+impl UmbrellaSampling {
+    /// n windows of the same k with centers evenly spaced on [from, to]
+    pub fn evenly_spaced(from: f64, to: f64, n: usize, k: f64) -> Vec<Window> {
+        (0..n).map(|i| Window { center: if n > 1 { from + (to - from)*i as f64/(n - 1) as f64 } else { from }, k })
+              .collect()
+    }
+
+    /// samples every window starting from `start`, writing the samples of window i to
+    /// out_dir/window_<i>.dat; returns the samples of s of every window
+    pub fn run(&self, start: &Fuleren, out_dir: &str) -> io::Result<Vec<Vec<f64>>> {
+        std::fs::create_dir_all(out_dir)?;
+        let queue = Mutex::new(self.windows.iter().copied().enumerate().collect::<VecDeque<_>>());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::scope(|s| {
+            for _ in 0..self.threads.max(1) {
+                let tx = tx.clone();
+                let queue = &queue;
+                s.spawn(move || {
+                    loop {
+                        let next = queue.lock().unwrap().pop_front();
+                        let Some((id, window)) = next else { break };
+                        match self.sample_window(id, window, start.clone(), out_dir) {
+                            Ok(samples) => tx.send((id, samples)).expect("sample channel closed"),
+                            Err(e) => error!(id, "window failed: {}", e),
+                        }
+                    }
+                });
+            }
+        });
+        drop(tx);
+
+        let mut samples: Vec<Vec<f64>> = vec![Vec::new(); self.windows.len()];
+        for (id, s) in rx {
+            samples[id] = s;
+        }
+        Ok(samples)
+    }
+
+    fn sample_window(&self, id: usize, window: Window, mut f: Fuleren, out_dir: &str) -> io::Result<Vec<f64>> {
+        seed_rng(self.seed + id as u64);
+        f.energy_calc();
+        let mut s = self.coordinate.value(&f);
+
+        for _ in 0..self.equilibration {
+            f.umbrella_sweep(self.beta, self.step, self.coordinate.as_ref(), &window, &mut s);
+        }
+
+        let mut log = ObservableWriter::create(&format!("{}/window_{}.dat", out_dir, id), &["s", "E"])?;
+        let mut samples = Vec::new();
+        let mut accepted = 0;
+        for it in 0..self.sweeps {
+            accepted += f.umbrella_sweep(self.beta, self.step, self.coordinate.as_ref(), &window, &mut s);
+            if it % self.sample_every.max(1) == 0 {
+                log.row(it, &[s, f.E])?;
+                samples.push(s);
+            }
+        }
+        log.flush()?;
+
+        let acceptance = accepted as f64/(self.sweeps*f.size).max(1) as f64;
+        let mean = samples.iter().sum::<f64>()/samples.len().max(1) as f64;
+        info!(id, center = window.center, s_mean = mean, acceptance, "window finished");
+        Ok(samples)
+    }
+}
+
+impl Fuleren {
+    /// Metropolis sweep of uniform cartesian atom shifts (symmetric, up to `step` per component)
+    /// with the bias of the window added to the energy; s is the current value of the coordinate
+    /// and is kept up to date. Returns the number of accepted moves
+    pub fn umbrella_sweep(&mut self, beta: f64, step: f64, coordinate: &dyn CollectiveVariable, window: &Window,
+                          s: &mut f64) -> usize {
+        let mut rng = rng();
+        let mut accepted = 0;
+
+        for i in 0..self.size {
+            let old = self.positions[i].clone();
+            let xyz = [old.x() + step*(2.*rng.gen::<f64>() - 1.),
+                       old.y() + step*(2.*rng.gen::<f64>() - 1.),
+                       old.z() + step*(2.*rng.gen::<f64>() - 1.)];
+            let new = Point6::from_cartesian(&xyz);
+            if self._overlap_with(i, &new).is_some() { continue }
+
+            let de = self._local_shift(i, new);
+            let s_new = coordinate.value(self);
+            let dw = window.bias(s_new) - window.bias(*s);
+
+            if rng.gen::<f64>() <= (-beta*(de + dw)).exp() {
+                self.E += de;
+                *s = s_new;
+                accepted += 1;
+            }
+            else {
+                self.positions[i] = old;
+            }
+        }
+        accepted
+    }
+}
+
+/// weighted histogram analysis: unbiased distribution of s from the samples of all windows on
+/// `bins` bins spanning the samples, iterated until the window free energies change less than
+/// tol [eV]. Returns (s, F(s) [eV], P(s)) of the visited bins, F shifted to a minimum of 0
+pub fn wham(windows: &[Window], samples: &[Vec<f64>], beta: f64, bins: usize, tol: f64, max_iter: usize)
+           -> Vec<(f64, f64, f64)> {
+    let all = samples.iter().flatten();
+    let lo = all.clone().copied().fold(f64::INFINITY, f64::min);
+    let hi = all.copied().fold(f64::NEG_INFINITY, f64::max);
+    // no samples, or all of them equal
+    if lo.partial_cmp(&hi) != Some(Ordering::Less) || bins == 0 {
+        return Vec::new();
+    }
+    let width = (hi - lo)/bins as f64;
+    let centers: Vec<f64> = (0..bins).map(|b| lo + (b as f64 + 0.5)*width).collect();
+
+    // total histogram and number of samples of every window
+    let mut counts = vec![0.; bins];
+    for s in samples.iter().flatten() {
+        counts[(((s - lo)/width) as usize).min(bins - 1)] += 1.;
+    }
+    let n: Vec<f64> = samples.iter().map(|s| s.len() as f64).collect();
+
+    // boltzmann factors of the biases exp(-beta w_i(s_b))
+    let c: Vec<Vec<f64>> = windows.iter().map(|w| centers.iter().map(|&s| (-beta*w.bias(s)).exp()).collect()).collect();
+
+    let mut f = vec![0.; windows.len()];
+    let mut p = vec![0.; bins];
+    for it in 0..max_iter {
+        for b in 0..bins {
+            let denominator: f64 = (0..windows.len()).map(|i| n[i]*(beta*f[i]).exp()*c[i][b]).sum();
+            p[b] = if denominator > 0. { counts[b]/denominator } else { 0. };
+        }
+        let f_new: Vec<f64> = (0..windows.len()).map(|i| {
+            -(0..bins).map(|b| p[b]*c[i][b]).sum::<f64>().ln()/beta
+        }).collect();
+        // only differences of the window free energies matter
+        let shift = f_new[0];
+        let change = f_new.iter().zip(f.iter()).map(|(a, b)| (a - shift - b).abs()).fold(0., f64::max);
+        f = f_new.iter().map(|x| x - shift).collect();
+        if change < tol {
+            tracing::debug!(it, "WHAM converged");
+            break
+        }
+    }
+
+    let norm: f64 = p.iter().sum::<f64>()*width;
+    let f_min = p.iter().filter(|&&x| x > 0.).map(|x| -x.ln()/beta).fold(f64::INFINITY, f64::min);
+    (0..bins).filter(|&b| p[b] > 0.)
+             .map(|b| (centers[b], -p[b].ln()/beta - f_min, p[b]/norm))
+             .collect()
+}
+
+/// saves the "s F P" rows of a WHAM profile
+pub fn save_profile(profile: &[(f64, f64, f64)], path: &str) -> io::Result<()> {
+    write_atomic(path, |f| {
+        writeln!(f, "# {:<12} {:<12} {:<12}", "s", "F[eV]", "P")?;
+        for (s, free, p) in profile {
+            writeln!(f, "  {:<12.6} {:<12.6} {:<12.6e}", s, free, p)?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,56 @@
+//! units: energies in eV, lengths in A, masses in amu, temperatures in K and beta = 1/(kB T) in 1/eV;
+//! the MD time unit sqrt(amu A^2/eV) is TIME_FS femtoseconds
+use std::fmt;
+
+pub const KB: f64 = 8.617333262e-5; // Boltzmann constant [eV/K]
+pub const HBAR: f64 = 6.582119569e-16; // [eV s]
+pub const TIME_FS: f64 = 10.1805055; // MD time unit [fs]
+
+/// absolute temperature [K]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Temperature(pub f64);
+
+/// inverse temperature 1/(kB T) [1/eV], what the Metropolis criterion uses
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Beta(pub f64);
+
+impl Temperature {
+    pub fn beta(self) -> Beta {
+        Beta(1./(KB*self.0))
+    }
+
+    /// kB T [eV]
+    pub fn kt(self) -> f64 {
+        KB*self.0
+    }
+}
+
+impl Beta {
+    pub fn temperature(self) -> Temperature {
+        Temperature(1./(KB*self.0))
+    }
+}
+
+impl From<Temperature> for Beta {
+    fn from(t: Temperature) -> Beta {
+        t.beta()
+    }
+}
+
+impl From<Beta> for Temperature {
+    fn from(beta: Beta) -> Temperature {
+        beta.temperature()
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} K", self.0)
+    }
+}
+
+impl fmt::Display for Beta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.4} 1/eV", self.0)
+    }
+}
@@ -1,69 +1,83 @@
-use ndarray::prelude::*;
-
-
 use ndarray::{Array1, Array2};
-use std::fmt::{Display, Debug};
+use std::fmt::Display;
 /// files
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 
-pub fn get_file_buffer(path: &str) -> BufWriter<File>{
-    let f = File::create(path).expect("unable to create file");
-    BufWriter::new(f)
+/// creates the file for buffered writing, together with missing parent directories
+pub fn get_file_buffer(path: &str) -> io::Result<BufWriter<File>> {
+    if let Some(dir) = Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let f = File::create(path)?;
+    Ok(BufWriter::new(f))
+}
+
+/// writes a whole file through `write`: into path.tmp first and renamed to path once complete,
+/// so a crash or a full disk never leaves a truncated file behind
+pub fn write_atomic<F>(path: &str, write: F) -> io::Result<()>
+where F: FnOnce(&mut BufWriter<File>) -> io::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    let mut f = get_file_buffer(&tmp)?;
+    write(&mut f)?;
+    f.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&tmp, path)
 }
 
 /// saves given 1D ndarray to file named in path argument; Produces Gnuplot ready files
-pub fn save_gnuplot1D<T: Display>(data: &Array1<T>, path: &str){
-    
-    let mut f = get_file_buffer(path);    
+#[tracing::instrument(level = "trace", skip_all)]
+#[allow(dead_code, non_snake_case)]
+pub fn save_gnuplot1D<T: Display>(data: &Array1<T>, path: &str) -> io::Result<()> {
+    write_atomic(path, |f| {
+        let i_width = std::cmp::max(5,data.len().to_string().len()+2);
+        let data_width = data.first().map_or(8, |d| std::cmp::max(8, d.to_string().len()));
 
-    let i_width = std::cmp::max(5,data.len().to_string().len()+2);
-    let data_width = std::cmp::max(8, data[0].to_string().len());
+        for i in 0..data.len(){
+            writeln!(f, "{:<i_width$} {:<data_width$}", i, data[i])?;
+        }
+        writeln!(f)
+    })
+}
 
-    for i in 0..data.len(){
-        write!(f, "{:<i_width$} {:<data_width$}\n", i, data[i]).expect("nie udało sie zapisac");
+/// saves y against its abscissa x as two columns under a "# <x label> <y label>" header;
+/// Produces Gnuplot ready files
+#[tracing::instrument(level = "trace", skip_all)]
+pub fn save_xy<X: Display, Y: Display>(x: &Array1<X>, y: &Array1<Y>, labels: [&str; 2], path: &str) -> io::Result<()> {
+    if x.len() != y.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  format!("{}: {} abscissa values for {} data points", path, x.len(), y.len())));
     }
-    write!(f, "\n").expect("nie udało sie zapisac");
+    write_atomic(path, |f| {
+        let x_width = x.iter().map(|v| v.to_string().len()).max().map_or(8, |w| std::cmp::max(8, w));
+        writeln!(f, "# {:<x_width$} {}", labels[0], labels[1])?;
+        for (xi, yi) in x.iter().zip(y.iter()) {
+            writeln!(f, "  {:<x_width$} {}", xi, yi)?;
+        }
+        Ok(())
+    })
 }
 
 
 /// saves given 2D ndarray to file named in path argument; Produces Gnuplot ready files
-pub fn save_gnuplot2D<T: Display>(data: &Array2<T>, path: &str){
-    
-    let mut f = get_file_buffer(path);    
-
+#[tracing::instrument(level = "trace", skip_all)]
+#[allow(dead_code, non_snake_case)]
+pub fn save_gnuplot2D<T: Display>(data: &Array2<T>, path: &str) -> io::Result<()> {
+    write_atomic(path, |f| {
+        // calculates width of given variable in string to save;
+        let i_width = std::cmp::max(5,data.shape()[0].to_string().len()+2);
+        let j_width = std::cmp::max(5,data.shape()[1].to_string().len()+2);
+        // notice assumption that single data element is enough to determine the width
+        let data_width = data.first().map_or(8, |d| std::cmp::max(8, d.to_string().len()));
 
-    // calculates width of given variable in string to save;
-    let i_width = std::cmp::max(5,data.shape()[0].to_string().len()+2);
-    let j_width = std::cmp::max(5,data.shape()[1].to_string().len()+2);
-    // notice assumption that single data element is enough to determine the width
-    let data_width = std::cmp::max(8, data[[0,0]].to_string().len());
+        for i in 0..data.shape()[0]{
+            for j in 0..data.shape()[1]{
 
-    for i in 0..data.shape()[0]{
-        for j in 0..data.shape()[1]{
-            
-            write!(f, "{:<i_width$} {:<j_width$} {:<data_width$}\n", i, j, data[[i,j]]).expect("nie udało sie zapisac");
+                writeln!(f, "{:<i_width$} {:<j_width$} {:<data_width$}", i, j, data[[i,j]])?;
+            }
+            writeln!(f)?;
         }
-        write!(f, "\n").expect("nie udało sie zapisac");
-    }
-    write!(f, "\n").expect("nie udało sie zapisac");
+        writeln!(f)
+    })
 }
 
-
-fn main() {
-    
-    
-    // test save of 1D array
-    let a = array![1./8.,2.55555,3./7.,4.,5.];
-
-    save_gnuplot1D(&a, "test_1D_array.txt");
-
-    // test save of 2D array
-    let b = arr2(&[[1,2,3,4,5],
-                    [2,3,4,5,6],
-                    [3,4,5,6,7],
-                    [4,5,6,7,8],
-                    [5,6,7,8,9]]);
-    save_gnuplot2D(&b, "test_2D_array.txt");
-    
-}
\ No newline at end of file
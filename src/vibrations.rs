@@ -0,0 +1,261 @@
+use std::io::Write;
+
+use ndarray::prelude::*;
+
+use crate::symmetry::SymmetryOperation;
+use crate::units::{HBAR, KB};
+use crate::utilities::write_atomic;
+use crate::{Fuleren, Point6, VectorFloat};
+
+// constants for turning hessian eigenvalues into frequencies
+const OMEGA_UNIT: f64 = 9.82269385e13; // sqrt(eV/(A^2 amu)) in [rad/s]
+const C_CM: f64 = 2.99792458e10; // speed of light [cm/s]
+
+/// normal modes of a (relaxed) structure; imaginary modes are stored as negative omega
+pub struct NormalModes {
+    pub e0: f64,
+    pub omega: VectorFloat,
+    pub vectors: Array2<f64>,
+    pub masses: VectorFloat,
+}
+
+/// degenerate modes (wavenumbers within a tolerance) and their spectroscopic activity
+#[derive(Debug, Clone)]
+pub struct ModeSet {
+    pub wavenumber: f64, // mean [cm^-1]
+    pub modes: Vec<usize>, // columns of vectors
+    pub ir: bool,
+    pub raman: bool,
+}
+
+impl NormalModes {
+    /// frequencies in cm^-1, convenient for comparing with spectra
+    pub fn wavenumbers(&self) -> VectorFloat {
+        self.omega.mapv(|w| w/(2.*std::f64::consts::PI*C_CM))
+    }
+
+    /// number of imaginary modes; nonzero means the structure is not a minimum
+    pub fn n_imaginary(&self) -> usize {
+        self._internal().iter().filter(|&&w| w < 0.).count()
+    }
+
+    /// harmonic (quantum) free energy at temperature t [K]; imaginary modes are skipped
+    pub fn free_energy(&self, t: f64) -> f64 {
+        self.e0 + self._internal().into_iter().map(|w| mode_free_energy(w, t)).sum::<f64>()
+    }
+
+    /// vibrational free energy of a degenerate set at temperature t [K], its part of free_energy
+    pub fn set_free_energy(&self, set: &ModeSet, t: f64) -> f64 {
+        set.modes.iter().map(|&m| mode_free_energy(self.omega[m], t)).sum()
+    }
+
+    /// internal modes grouped into degenerate sets, wavenumbers within tol [cm^-1] of the previous
+    /// one, with the activity from the symmetry operations (found with the same masses): a set is
+    /// IR active if its representation contains the one of the dipole (x, y, z), Raman active if
+    /// it contains the one of the polarizability (xx, xy, ...). With the identity alone every
+    /// mode is active
+    pub fn activity(&self, operations: &[SymmetryOperation], tol: f64) -> Vec<ModeSet> {
+        let wavenumbers = self.wavenumbers();
+        let mut order: Vec<usize> = (0..self.omega.len()).collect();
+        order.sort_by(|&a, &b| self.omega[a].abs().total_cmp(&self.omega[b].abs()));
+        let mut internal = order.split_off(6.min(order.len()));
+        internal.sort_by(|&a, &b| wavenumbers[a].total_cmp(&wavenumbers[b]));
+
+        let mut sets: Vec<Vec<usize>> = Vec::new();
+        for k in internal {
+            match sets.last_mut() {
+                Some(set) if wavenumbers[k] - wavenumbers[*set.last().unwrap()] < tol => set.push(k),
+                _ => sets.push(vec![k]),
+            }
+        }
+
+        let order = operations.len().max(1) as f64;
+        sets.into_iter().map(|modes| {
+            let characters: Vec<f64> = operations.iter().map(|op| self.character(&modes, op)).collect();
+            let multiplicity = |chi: &dyn Fn(&SymmetryOperation) -> f64|
+                operations.iter().zip(&characters).map(|(op, c)| c*chi(op)).sum::<f64>()/order;
+            ModeSet {
+                wavenumber: modes.iter().map(|&k| wavenumbers[k]).sum::<f64>()/modes.len() as f64,
+                ir: multiplicity(&|op| op.trace()) > 0.5,
+                raman: multiplicity(&|op| op.tensor_character()) > 0.5,
+                modes,
+            }
+        }).collect()
+    }
+
+    // trace of the operation on the space of the modes, sum over k of q_k . O q_k with the atom
+    // displacements moved as (O u)_perm(i) = R u_i
+    fn character(&self, modes: &[usize], op: &SymmetryOperation) -> f64 {
+        let mut chi = 0.;
+        for &k in modes {
+            let q = self.vectors.column(k);
+            for (i, &j) in op.permutation.iter().enumerate() {
+                let u = op.apply([q[3*i], q[3*i + 1], q[3*i + 2]]);
+                chi += (0..3).map(|c| u[c]*q[3*j + c]).sum::<f64>();
+            }
+        }
+        chi
+    }
+
+    // omega without the 6 smallest |omega| (translations and rotations, only numerically zero)
+    fn _internal(&self) -> Vec<f64> {
+        let mut omega = self.omega.to_vec();
+        omega.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+        omega.split_off(6.min(omega.len()))
+    }
+}
+
+impl Fuleren {
+    /// cartesian hessian (3N x 3N) from central differences of the forces
+    pub fn hessian(&self) -> Array2<f64> {
+        let h = 1e-4;
+        let n = 3*self.size;
+        let mut hess = Array2::<f64>::zeros((n, n));
+        let mut work = self.clone();
+
+        for i in 0..self.size {
+            let old = self.positions[i].clone();
+            let xyz_old = [old.x(), old.y(), old.z()];
+
+            for c in 0..3 {
+                let mut xyz = xyz_old;
+                xyz[c] = xyz_old[c] + h;
+                work.positions[i] = Point6::from_cartesian(&xyz);
+                let f_plus = work.forces();
+
+                xyz[c] = xyz_old[c] - h;
+                work.positions[i] = Point6::from_cartesian(&xyz);
+                let f_minus = work.forces();
+
+                let col = (&f_minus - &f_plus)/(2.*h);
+                hess.column_mut(3*i + c).assign(&Array1::from_iter(col.iter().cloned()));
+            }
+            work.positions[i] = old;
+        }
+        // remove the finite difference asymmetry
+        let hess_t = hess.t().to_owned();
+        0.5*(hess + hess_t)
+    }
+
+    /// normal modes from the mass weighted hessian H_ab/sqrt(m_a m_b), sorted by frequency
+    pub fn normal_modes(&mut self) -> NormalModes {
+        let masses = self.masses();
+        self.normal_modes_with_masses(masses)
+    }
+
+    /// normal modes with the given masses per atom [amu] instead of those of the elements, for
+    /// isotope substitution (13C)
+    pub fn normal_modes_with_masses(&mut self, masses: VectorFloat) -> NormalModes {
+        let e0 = self.energy_calc();
+        let mut hessian = self.hessian();
+        hessian.indexed_iter_mut().for_each(|((a, b), h)| *h /= (masses[a/3]*masses[b/3]).sqrt());
+        let (lambda, vectors) = jacobi_eigen(hessian);
+
+        let omega = lambda.mapv(|l| l.signum()*l.abs().sqrt()*OMEGA_UNIT);
+        NormalModes { e0, omega, vectors, masses }
+    }
+
+    /// harmonic superposition estimate of the free energy at temperature t [K]
+    pub fn harmonic_free_energy(&mut self, t: f64) -> f64 {
+        self.normal_modes().free_energy(t)
+    }
+}
+
+// zero point energy and thermal part of the free energy of a mode of angular frequency w [rad/s]
+// at temperature t [K]; nothing for an imaginary mode
+fn mode_free_energy(w: f64, t: f64) -> f64 {
+    if w <= 0. { return 0. }
+
+    let hw = HBAR*w;
+    let mut f = 0.5*hw;
+    if t > 0. {
+        f += KB*t*(1. - (-hw/(KB*t)).exp()).ln();
+    }
+    f
+}
+
+/// atom and mass of an isotope substitution, "atom:mass" (e.g. 0:13.003 for a 13C)
+pub fn parse_isotope(s: &str) -> Result<(usize, f64), String> {
+    let err = || format!("cannot parse isotope \"{}\", expected atom:mass", s);
+    match s.split(':').collect::<Vec<_>>()[..] {
+        [atom, mass] => Ok((atom.parse::<usize>().map_err(|_| err())?, mass.parse::<f64>().map_err(|_| err())?)),
+        _ => Err(err()),
+    }
+}
+
+/// degenerate mode sets with their activity and vibrational free energy at temperature t [K], the
+/// point group order and the harmonic free energy of the structure in the header
+pub fn save_modes(modes: &NormalModes, sets: &[ModeSet], group_order: usize, t: f64, path: &str) -> std::io::Result<()> {
+    write_atomic(path, |f| {
+        writeln!(f, "# point group order: {}", group_order)?;
+        writeln!(f, "# F(T = {} K): {:.6} eV, E0: {:.6} eV", t, modes.free_energy(t), modes.e0)?;
+        writeln!(f, "# {:<6} {:<14} {:<6} {:<4} {:<6} F(T)[eV]", "set", "nu[cm^-1]", "deg", "IR", "Raman")?;
+        for (n, set) in sets.iter().enumerate() {
+            let yes_no = |b: bool| if b { "yes" } else { "no" };
+            writeln!(f, "  {:<6} {:<14.3} {:<6} {:<4} {:<6} {:.6}", n, set.wavenumber, set.modes.len(), yes_no(set.ir),
+                     yes_no(set.raman), modes.set_free_energy(set, t))?;
+        }
+        Ok(())
+    })
+}
+
+/// ranks isomers by harmonic free energy at temperature t, lowest first and a NaN F (degenerate
+/// or unrelaxed structures) last; returns (index, F)
+pub fn rank_by_free_energy(isomers: &mut [Fuleren], t: f64) -> Vec<(usize, f64)> {
+    let mut ranking: Vec<(usize, f64)> = isomers.iter_mut()
+                                                .map(|f| f.harmonic_free_energy(t))
+                                                .enumerate()
+                                                .collect();
+    ranking.sort_by(|a, b| a.1.is_nan().cmp(&b.1.is_nan()).then(a.1.total_cmp(&b.1)));
+    ranking
+}
+
+/// eigenvalues (ascending) and eigenvectors (as columns) of a symmetric matrix; cyclic Jacobi method
+pub fn jacobi_eigen(mut a: Array2<f64>) -> (VectorFloat, Array2<f64>) {
+    let n = a.nrows();
+    let mut v = Array2::<f64>::eye(n);
+
+    for _sweep in 0..100 {
+        let off: f64 = (0..n).flat_map(|p| ((p+1)..n).map(move |q| (p, q)))
+                             .map(|(p, q)| a[[p, q]].powi(2))
+                             .sum();
+        if off < 1e-22 { break }
+
+        for p in 0..n {
+            for q in (p+1)..n {
+                if a[[p, q]].abs() < 1e-300 { continue }
+
+                let theta = (a[[q, q]] - a[[p, p]])/(2.*a[[p, q]]);
+                let t = theta.signum()/(theta.abs() + (theta.powi(2) + 1.).sqrt());
+                let c = 1./(t.powi(2) + 1.).sqrt();
+                let s = t*c;
+
+                for k in 0..n {
+                    let akp = a[[k, p]];
+                    let akq = a[[k, q]];
+                    a[[k, p]] = c*akp - s*akq;
+                    a[[k, q]] = s*akp + c*akq;
+                }
+                for k in 0..n {
+                    let apk = a[[p, k]];
+                    let aqk = a[[q, k]];
+                    a[[p, k]] = c*apk - s*aqk;
+                    a[[q, k]] = s*apk + c*aqk;
+                }
+                for k in 0..n {
+                    let vkp = v[[k, p]];
+                    let vkq = v[[k, q]];
+                    v[[k, p]] = c*vkp - s*vkq;
+                    v[[k, q]] = s*vkp + c*vkq;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| a[[i, i]].total_cmp(&a[[j, j]]));
+
+    let values = order.iter().map(|&i| a[[i, i]]).collect::<VectorFloat>();
+    let vectors = v.select(Axis(1), &order);
+    (values, vectors)
+}
@@ -0,0 +1,75 @@
+use wasm_bindgen::prelude::*;
+
+use crate::anneal::Schedule;
+use crate::{seed_rng, Fuleren};
+
+/// step by step annealing for the browser; the page calls step() from its animation loop
+/// and redraws positions() in between. No file I/O, no clock
+#[wasm_bindgen]
+pub struct Annealer {
+    f: Fuleren,
+    schedule: Schedule,
+    it: usize,
+}
+
+#[wasm_bindgen]
+impl Annealer {
+    /// n atoms at random on a sphere of radius 2.5, power law schedule
+    #[wasm_bindgen(constructor)]
+    pub fn new(n: usize, beta_min: f64, beta_max: f64, p: f64, it_max: usize, seed: u64) -> Annealer {
+        seed_rng(seed);
+        let mut f = Fuleren::new(n);
+        f.randomize_on_sphere(2.5);
+        f.energy_calc();
+        let schedule = Schedule { beta_min, beta_max, p, it_max, ..Default::default() };
+        Annealer { f, schedule, it: 0 }
+    }
+
+    /// runs up to `sweeps` iterations; false once the schedule is finished
+    pub fn step(&mut self, sweeps: usize) -> bool {
+        for _ in 0..sweeps {
+            if self.finished() { break }
+            let beta = self.schedule.beta(self.it);
+            self.f.sweep(&self.schedule, beta);
+            self.it += 1;
+        }
+        !self.finished()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.it >= self.schedule.it_max
+    }
+
+    pub fn iteration(&self) -> usize {
+        self.it
+    }
+
+    pub fn beta(&self) -> f64 {
+        self.schedule.beta(self.it)
+    }
+
+    /// temperature [K] of the current iteration
+    pub fn temperature(&self) -> f64 {
+        self.schedule.temperature(self.it).0
+    }
+
+    pub fn energy(&self) -> f64 {
+        self.f.E
+    }
+
+    pub fn mean_r(&self) -> f64 {
+        self.f.mean_r()
+    }
+
+    /// positions as x0 y0 z0 x1 ... (a Float64Array on the JS side)
+    pub fn positions(&self) -> Vec<f64> {
+        self.f.positions.iter().flat_map(|p| [p.x(), p.y(), p.z()]).collect()
+    }
+
+    /// bonds as pairs i0 j0 i1 j1 ..., each bond once
+    pub fn bonds(&self) -> Vec<u32> {
+        self.f.bond_graph(crate::R1).iter().enumerate()
+              .flat_map(|(i, nb)| nb.iter().filter(move |&&j| j > i).flat_map(move |&j| [i as u32, j as u32]))
+              .collect()
+    }
+}
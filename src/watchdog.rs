@@ -0,0 +1,63 @@
+//! stuck run detection for unattended anneals: a run whose atom move acceptance has collapsed
+//! while its energy is still far above a formed cage is jammed in a glassy state; the watchdog
+//! perturbs it, by a partial reheat or by shaking the atoms of highest energy, and logs the event
+use crate::{gauss, rng, Fuleren, Point6};
+
+/// checked every `window` iterations: acceptance below `min_acceptance` with E/N above `e_max`
+/// [eV] is a stuck run, perturbed at most `max_kicks` times
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Watchdog {
+    pub window: usize,
+    pub min_acceptance: f64,
+    pub e_max: f64,
+    pub perturbation: Perturbation,
+    pub max_kicks: usize,
+}
+
+/// what the watchdog does to a stuck run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Perturbation {
+    /// schedule rewound by this many iterations, back to a higher temperature
+    Reheat(usize),
+    /// the `fraction` of atoms with the highest energy displaced by gaussian shifts of `amplitude` [A]
+    Shake { fraction: f64, amplitude: f64 },
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Watchdog { window: 5000, min_acceptance: 0.01, e_max: -6.9,
+                   perturbation: Perturbation::Shake { fraction: 0.2, amplitude: 0.3 }, max_kicks: 5 }
+    }
+}
+
+impl Watchdog {
+    /// true for a jammed run: acceptance collapsed while the energy per atom is high
+    pub fn stuck(&self, acceptance: f64, e_per_atom: f64) -> bool {
+        acceptance < self.min_acceptance && e_per_atom > self.e_max
+    }
+}
+
+impl Fuleren {
+    /// gaussian cartesian shifts of the given rms amplitude for the `fraction` of atoms with the
+    /// highest V_i; shifts into the hard core of another atom are skipped. Returns the shaken atoms
+    pub fn shake_worst(&mut self, fraction: f64, amplitude: f64) -> Vec<usize> {
+        let mut order: Vec<(usize, f64)> = (0..self.size).map(|i| (i, self._vi(i))).collect();
+        order.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let count = ((fraction*self.size as f64).ceil() as usize).clamp(1, self.size);
+        let sigma = amplitude/3f64.sqrt();
+
+        let mut rng = rng();
+        let mut shaken = Vec::new();
+        for &(i, _) in &order[..count] {
+            let p = &self.positions[i];
+            let moved = Point6::from_cartesian(&[p.x() + sigma*gauss(&mut rng), p.y() + sigma*gauss(&mut rng),
+                                                 p.z() + sigma*gauss(&mut rng)]);
+            if self._overlap_with(i, &moved).is_none() {
+                self.positions[i] = moved;
+                shaken.push(i);
+            }
+        }
+        self.energy_calc();
+        shaken
+    }
+}